@@ -7,4 +7,18 @@ DROP TYPE IF EXISTS event_type;
 DROP TABLE IF EXISTS broadcasts;
 DROP TYPE IF EXISTS broadcast_status;
 
-DROP TABLE IF EXISTS payloads;
\ No newline at end of file
+DROP TABLE IF EXISTS payloads;
+
+DROP TABLE IF EXISTS chains;
+
+DROP TABLE IF EXISTS dead_letters;
+
+DROP TABLE IF EXISTS queue_jobs;
+
+DROP TABLE IF EXISTS wasm_events;
+DROP TABLE IF EXISTS indexer_checkpoints;
+
+DROP TABLE IF EXISTS tokens;
+DROP TABLE IF EXISTS token_metadata;
+
+DROP TABLE IF EXISTS transfers;
\ No newline at end of file
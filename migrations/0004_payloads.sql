@@ -1,4 +1,4 @@
 CREATE TABLE IF NOT EXISTS payloads (
     id	                TEXT NOT NULL PRIMARY KEY,
-    payload	            TEXT NOT NULL,
+    payload	            TEXT NOT NULL
 );
\ No newline at end of file
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS chains (
+    name TEXT NOT NULL PRIMARY KEY,
+    gateway TEXT NOT NULL,
+    voting_verifier TEXT NOT NULL,
+    multisig_prover TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS dead_letters (
+    id TEXT NOT NULL PRIMARY KEY,
+    item TEXT NOT NULL,
+    error TEXT NOT NULL,
+    attempts INTEGER NOT NULL,
+    first_seen_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS queue_jobs (
+    id BIGSERIAL PRIMARY KEY,
+    queue TEXT NOT NULL,
+    item TEXT NOT NULL,
+    headers TEXT NOT NULL DEFAULT '{}',
+    available_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    locked_until TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS queue_jobs_available_idx ON queue_jobs (queue, available_at);
@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS wasm_events (
+    tx_hash TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    height BIGINT NOT NULL,
+    event_type TEXT NOT NULL,
+    contract_address TEXT NOT NULL,
+    -- poll_id or session_id attribute, whichever the event has
+    event_id TEXT,
+    attributes TEXT NOT NULL,
+    block_time TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tx_hash, event_index)
+);
+
+CREATE INDEX IF NOT EXISTS wasm_events_lookup_idx ON wasm_events (event_type, contract_address, event_id);
+
+CREATE TABLE IF NOT EXISTS indexer_checkpoints (
+    name TEXT NOT NULL PRIMARY KEY,
+    height BIGINT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
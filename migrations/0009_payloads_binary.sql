@@ -0,0 +1,6 @@
+ALTER TABLE payloads ALTER COLUMN payload TYPE BYTEA USING decode(payload, 'base64');
+ALTER TABLE payloads ADD COLUMN IF NOT EXISTS content_type TEXT NOT NULL DEFAULT 'application/octet-stream';
+ALTER TABLE payloads ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE payloads ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS payloads_expires_at_idx ON payloads (expires_at) WHERE expires_at IS NOT NULL;
@@ -0,0 +1,7 @@
+ALTER TABLE tasks ALTER COLUMN task DROP DEFAULT;
+ALTER TABLE tasks ALTER COLUMN task TYPE JSONB USING task::jsonb;
+ALTER TABLE tasks ALTER COLUMN task SET DEFAULT '{}'::jsonb;
+
+CREATE INDEX IF NOT EXISTS tasks_chain_type_timestamp_idx ON tasks (chain, type, timestamp);
+CREATE INDEX IF NOT EXISTS tasks_message_id_idx ON tasks ((task -> 'task' -> 'message' ->> 'messageID'));
+CREATE INDEX IF NOT EXISTS tasks_scoped_messages_idx ON tasks USING GIN ((task -> 'meta' -> 'scopedMessages') jsonb_path_ops);
@@ -0,0 +1,2 @@
+ALTER TYPE event_type ADD VALUE IF NOT EXISTS 'MESSAGE_APPROVED';
+ALTER TYPE event_type ADD VALUE IF NOT EXISTS 'SIGNERS_ROTATED';
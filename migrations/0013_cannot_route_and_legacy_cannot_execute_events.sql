@@ -0,0 +1,2 @@
+ALTER TYPE event_type ADD VALUE IF NOT EXISTS 'CANNOT_EXECUTE_MESSAGE';
+ALTER TYPE event_type ADD VALUE IF NOT EXISTS 'CANNOT_ROUTE_MESSAGE';
@@ -0,0 +1,25 @@
+ALTER TYPE event_type ADD VALUE IF NOT EXISTS 'ITS_INTERCHAIN_TOKEN_DEPLOYMENT_STARTED';
+ALTER TYPE event_type ADD VALUE IF NOT EXISTS 'ITS_TOKEN_METADATA_REGISTERED';
+ALTER TYPE event_type ADD VALUE IF NOT EXISTS 'ITS_LINK_TOKEN_STARTED';
+
+CREATE TABLE IF NOT EXISTS tokens (
+    token_id TEXT NOT NULL,
+    chain TEXT NOT NULL,
+    address TEXT,
+    name TEXT,
+    symbol TEXT,
+    decimals INTEGER,
+    deployer TEXT,
+    linked_tokens JSONB NOT NULL DEFAULT '[]'::jsonb,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (token_id, chain)
+);
+
+CREATE TABLE IF NOT EXISTS token_metadata (
+    chain TEXT NOT NULL,
+    address TEXT NOT NULL,
+    decimals INTEGER NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (chain, address)
+);
@@ -0,0 +1,15 @@
+ALTER TYPE event_type ADD VALUE IF NOT EXISTS 'APP_INTERCHAIN_TRANSFER_SENT';
+ALTER TYPE event_type ADD VALUE IF NOT EXISTS 'APP_INTERCHAIN_TRANSFER_RECEIVED';
+
+CREATE TABLE IF NOT EXISTS transfers (
+    message_id TEXT NOT NULL PRIMARY KEY,
+    source_chain TEXT NOT NULL,
+    destination_chain TEXT NOT NULL,
+    sent JSONB,
+    sent_at TIMESTAMPTZ,
+    received JSONB,
+    received_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS transfers_source_chain_idx ON transfers (source_chain);
+CREATE INDEX IF NOT EXISTS transfers_destination_chain_idx ON transfers (destination_chain);
@@ -1,36 +1,66 @@
 use mock_gmp_api::{
-    Server, TasksModel,
-    models::{broadcasts::BroadcastsModel, events::EventsModel, payloads::PayloadsModel},
-    queue::LapinConnection,
-    utils::setup_logging,
+    Config, Server, TasksModel,
+    config::QueueBackend,
+    models::{
+        broadcasts::BroadcastsModel, chains::ChainsModel, dead_letters::DeadLettersModel,
+        events::EventsModel, payloads::PayloadsModel, tokens::TokensModel,
+        transfers::TransfersModel,
+    },
+    queue, subscriber, telemetry,
 };
+use tokio::sync::watch;
 use tracing::error;
 
 #[actix_web::main]
 async fn main() -> anyhow::Result<()> {
     dotenv::dotenv().ok();
-    setup_logging();
+    let config = Config::load()?;
+    let telemetry = telemetry::init("mock-gmp-api-server", &config.logging, &config.telemetry)?;
+
+    let tasks_model = TasksModel::new(&config.database.url).await?;
+    let events_model = EventsModel::new(&config.database.url).await?;
+    let broadcasts_model = BroadcastsModel::new(&config.database.url).await?;
+    let payloads_model = PayloadsModel::new(&config.database.url).await?;
+    let chains_model = ChainsModel::new(&config.database.url).await?;
+    for (name, contracts) in &config.chains {
+        chains_model.upsert(name, contracts).await?;
+    }
+    let dead_letters_model = DeadLettersModel::new(&config.database.url).await?;
+    let tokens_model = TokensModel::new(&config.database.url).await?;
+    let transfers_model = TransfersModel::new(&config.database.url).await?;
+    let queue = queue::connect(&config).await?;
+
+    // items of the memory queue only exist in this process, so the subscriber has to run here too
+    let (shutdown_sender, shutdown_receiver) = watch::channel(false);
+    let subscriber = if config.queue.backend == QueueBackend::Memory {
+        Some(subscriber::spawn_workers(&config, queue.clone(), shutdown_receiver).await?)
+    } else {
+        None
+    };
 
-    let tasks_model = TasksModel::new(&std::env::var("POSTGRES_URL").unwrap()).await?;
-    let events_model = EventsModel::new(&std::env::var("POSTGRES_URL").unwrap()).await?;
-    let broadcasts_model = BroadcastsModel::new(&std::env::var("POSTGRES_URL").unwrap()).await?;
-    let payloads_model = PayloadsModel::new(&std::env::var("POSTGRES_URL").unwrap()).await?;
-    let addr =
-        std::env::var("QUEUE_ADDRESS").unwrap_or_else(|_| "amqp://127.0.0.1:5672/%2f".into());
-    let queue = LapinConnection::new(&addr, "mock_gmp_api").await?;
     let server = Server::new(
-        std::env::var("SERVER_PORT")
-            .unwrap()
-            .parse::<u16>()
-            .unwrap(),
-        std::env::var("SERVER_ADDRESS").unwrap(),
+        config,
         tasks_model,
         events_model,
         broadcasts_model,
         payloads_model,
+        chains_model,
+        dead_letters_model,
+        tokens_model,
+        transfers_model,
         queue,
     );
-    if let Err(e) = server.run().await {
+    let result = server.run().await;
+    let _ = shutdown_sender.send(true);
+    if let Some(subscriber) = subscriber {
+        match subscriber.await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => error!("Subscriber error: {}", e),
+            Err(e) => error!("Subscriber panicked: {}", e),
+        }
+    }
+    telemetry.shutdown();
+    if let Err(e) = result {
         error!("Error: {}", e);
         std::process::exit(1);
     }
@@ -1,22 +1,51 @@
 use mock_gmp_api::{
-    models::tasks::TasksModel, queue::LapinConnection, subscriber::Subscriber, utils::setup_logging,
+    Config, config::QueueBackend, health, models::tasks::TasksModel, queue, subscriber, telemetry,
+    utils::shutdown_signal,
 };
+use tokio::sync::watch;
 
-#[tokio::main]
+#[actix_web::main]
 async fn main() -> anyhow::Result<()> {
     dotenv::dotenv().ok();
-    setup_logging();
+    let config = Config::load()?;
+    let telemetry = telemetry::init(
+        "mock-gmp-api-subscriber",
+        &config.logging,
+        &config.telemetry,
+    )?;
 
-    let addr =
-        std::env::var("QUEUE_ADDRESS").unwrap_or_else(|_| "amqp://127.0.0.1:5672/%2f".into());
+    if config.queue.backend == QueueBackend::Memory {
+        return Err(anyhow::anyhow!(
+            "The memory queue only lives in the server process, which runs the subscriber itself"
+        ));
+    }
 
-    let queue = LapinConnection::new(&addr, "mock_gmp_api").await?;
-    let database = TasksModel::new(&std::env::var("POSTGRES_URL").unwrap()).await?;
-    let rpc = std::env::var("AXELAR_RPC").unwrap();
+    let queue = queue::connect(&config).await?;
+    let database = TasksModel::new(&config.database.url).await?;
 
-    let mut subscriber = Subscriber::new(queue, database, rpc);
+    let readiness =
+        health::Readiness::new(database.clone(), queue.clone(), config.axelar.rpc.clone());
+    let health_server = health::serve(
+        &config.subscriber.health_address,
+        config.subscriber.health_port,
+        readiness,
+    )?;
+    let health_handle = health_server.handle();
+    actix_web::rt::spawn(health_server);
 
-    subscriber.run().await?;
+    let (shutdown_sender, shutdown_receiver) = watch::channel(false);
+    actix_web::rt::spawn(async move {
+        shutdown_signal().await;
+        let _ = shutdown_sender.send(true);
+    });
 
-    Ok(())
+    let result = subscriber::spawn_workers(&config, queue, shutdown_receiver)
+        .await?
+        .await
+        .unwrap_or_else(|e| Err(anyhow::anyhow!("Subscriber panicked: {}", e)));
+
+    health_handle.stop(true).await;
+    telemetry.shutdown();
+
+    result
 }
@@ -1,9 +1,9 @@
 use reqwest;
 use serde_json::Value;
-use std::error::Error;
 use tracing::{debug, error, info};
 
-use crate::gmp_types::StorePayloadResult;
+use crate::gmp_types::{PostEventResponse, StorePayloadResult};
+use crate::models::tasks::TaskFilter;
 
 pub struct Client {
     pub base_url: String,
@@ -13,45 +13,322 @@ pub struct Client {
 impl Client {
     pub fn new(base_url: String) -> Self {
         Self {
-            base_url,
+            base_url: base_url.trim_end_matches('/').to_string(),
             client: reqwest::Client::new(),
         }
     }
 
-    pub async fn get_tasks(&self) -> Result<String, Box<dyn Error>> {
-        let url = format!("{}/chains/xrpl/tasks", self.base_url);
+    /// Get the tasks of a chain, optionally only the ones after the task with the given ID
+    pub async fn get_tasks(
+        &self,
+        chain: &str,
+        after: Option<&str>,
+    ) -> Result<Vec<Value>, anyhow::Error> {
+        self.find_tasks(chain, after, &TaskFilter::default()).await
+    }
+
+    /// Get the tasks of a chain matching `filter`, e.g. the GATEWAY_TX tasks of a message
+    pub async fn find_tasks(
+        &self,
+        chain: &str,
+        after: Option<&str>,
+        filter: &TaskFilter,
+    ) -> Result<Vec<Value>, anyhow::Error> {
+        let url = format!("{}/chains/{}/tasks", self.base_url, chain);
 
         debug!("Making GET request to: {}", url);
 
+        let mut query = Vec::new();
+        if let Some(after) = after {
+            query.push(("after", after.to_string()));
+        }
+        if let Some(kind) = &filter.r#type {
+            query.push(("type", kind.as_str().to_string()));
+        }
+        if let Some(from) = filter.from {
+            query.push(("from", from.to_rfc3339()));
+        }
+        if let Some(to) = filter.to {
+            query.push(("to", to.to_rfc3339()));
+        }
+        if let Some(message_id) = &filter.message_id {
+            query.push(("messageID", message_id.clone()));
+        }
+        if let Some(limit) = filter.limit {
+            query.push(("limit", limit.to_string()));
+        }
+        if let Some(offset) = filter.offset {
+            query.push(("offset", offset.to_string()));
+        }
+        let request = self.client.get(&url).query(&query);
+        let response = Self::check_status(request.send().await?).await?;
+
+        let body: Value = response.json().await?;
+        let tasks = body
+            .get("tasks")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Response does not contain a tasks array"))?;
+
+        info!("Got {} tasks", tasks.len());
+        Ok(tasks)
+    }
+
+    /// Get a task of a chain, `None` if the chain has no task with this ID
+    pub async fn get_task(
+        &self,
+        chain: &str,
+        task_id: &str,
+    ) -> Result<Option<Value>, anyhow::Error> {
+        let url = format!("{}/chains/{}/tasks/{}", self.base_url, chain, task_id);
         let response = self.client.get(&url).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
 
-        if response.status().is_success() {
-            let body = response.text().await?;
-            info!("Response: {}", body);
-            Ok(body)
-        } else {
-            let error_msg = format!("Request failed with status: {}", response.status());
-            error!("{}", error_msg);
-            Err(error_msg.into())
+        Ok(Some(Self::check_status(response).await?.json().await?))
+    }
+
+    /// Lease the claimable tasks of a chain to `consumer`, for the configured visibility timeout
+    /// unless one is given
+    pub async fn claim_tasks(
+        &self,
+        chain: &str,
+        consumer: &str,
+        visibility_timeout: Option<u64>,
+    ) -> Result<Vec<Value>, anyhow::Error> {
+        let url = format!("{}/chains/{}/tasks", self.base_url, chain);
+
+        let mut request = self.client.get(&url).query(&[("consumer", consumer)]);
+        if let Some(timeout) = visibility_timeout {
+            request = request.query(&[("visibilityTimeout", timeout)]);
         }
+        let response = Self::check_status(request.send().await?).await?;
+
+        let body: Value = response.json().await?;
+        let tasks = body
+            .get("tasks")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Response does not contain a tasks array"))?;
+
+        info!("Claimed {} tasks", tasks.len());
+        Ok(tasks)
     }
 
-    pub async fn post_task(&self, task: Value) -> Result<String, anyhow::Error> {
-        let url = format!("{}/chains/xrpl/task", self.base_url);
-        let response = self.client.post(&url).json(&task).send().await?;
+    /// Complete a task leased to `consumer`
+    pub async fn ack_task(
+        &self,
+        chain: &str,
+        task_id: &str,
+        consumer: &str,
+    ) -> Result<Value, anyhow::Error> {
+        let url = format!("{}/chains/{}/tasks/{}/ack", self.base_url, chain, task_id);
+        let response = self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({ "consumerID": consumer }))
+            .send()
+            .await?;
 
-        Ok(response.text().await?)
+        Ok(Self::check_status(response).await?.json().await?)
     }
 
-    pub async fn post_events(&self, events: Value) -> Result<String, anyhow::Error> {
-        let url = format!("{}/chains/xrpl/events", self.base_url);
-        let response = self.client.post(&url).json(&events).send().await?;
+    /// Get the lease states of the tasks of a chain, optionally only the ones in `status`
+    pub async fn get_task_states(
+        &self,
+        chain: &str,
+        status: Option<&str>,
+    ) -> Result<Vec<Value>, anyhow::Error> {
+        let url = format!("{}/admin/tasks", self.base_url);
 
-        Ok(response.text().await?)
+        let mut request = self.client.get(&url).query(&[("chain", chain)]);
+        if let Some(status) = status {
+            request = request.query(&[("status", status)]);
+        }
+        let response = Self::check_status(request.send().await?).await?;
+
+        let body: Value = response.json().await?;
+        body.get("tasks")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Response does not contain a tasks array"))
+    }
+
+    pub async fn post_task(&self, chain: &str, task: &Value) -> Result<Value, anyhow::Error> {
+        let url = format!("{}/chains/{}/task", self.base_url, chain);
+        let response = self.client.post(&url).json(task).send().await?;
+
+        Ok(Self::check_status(response).await?.json().await?)
+    }
+
+    pub async fn post_events(
+        &self,
+        chain: &str,
+        events: &Value,
+    ) -> Result<PostEventResponse, anyhow::Error> {
+        let url = format!("{}/chains/{}/events", self.base_url, chain);
+        let response = self.client.post(&url).json(events).send().await?;
+
+        Ok(Self::check_status(response).await?.json().await?)
+    }
+
+    /// Broadcast a message to a contract, returning the broadcast ID
+    pub async fn post_broadcast(
+        &self,
+        contract_address: &str,
+        broadcast: &Value,
+    ) -> Result<String, anyhow::Error> {
+        let url = format!(
+            "{}/contracts/{}/broadcasts",
+            self.base_url, contract_address
+        );
+        let response = self.client.post(&url).json(broadcast).send().await?;
+
+        let body: Value = Self::check_status(response).await?.json().await?;
+        body.get("broadcastID")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow::anyhow!("Response does not contain a broadcastID"))
+    }
+
+    pub async fn get_broadcast(
+        &self,
+        contract_address: &str,
+        broadcast_id: &str,
+    ) -> Result<Value, anyhow::Error> {
+        let url = format!(
+            "{}/contracts/{}/broadcasts/{}",
+            self.base_url, contract_address, broadcast_id
+        );
+        let response = self.client.get(&url).send().await?;
+
+        Ok(Self::check_status(response).await?.json().await?)
+    }
+
+    pub async fn post_query(
+        &self,
+        contract_address: &str,
+        query: &Value,
+    ) -> Result<Value, anyhow::Error> {
+        let url = format!("{}/contracts/{}/queries", self.base_url, contract_address);
+        let response = self.client.post(&url).json(query).send().await?;
+
+        let body = Self::check_status(response).await?.text().await?;
+        // the server answers with plain text which is JSON most of the time
+        Ok(serde_json::from_str(&body).unwrap_or(Value::String(body)))
+    }
+
+    /// Get everything the mock knows about a message: its events and the tasks referencing it
+    pub async fn get_message(&self, message_id: &str) -> Result<Value, anyhow::Error> {
+        let url = format!("{}/messages/{}", self.base_url, message_id);
+        let response = self.client.get(&url).send().await?;
+
+        Ok(Self::check_status(response).await?.json().await?)
+    }
+
+    /// Inject `count` random `kind` ("tasks" or "events") into a chain; the response has the seed
+    /// to reproduce them with
+    pub async fn generate(
+        &self,
+        chain: &str,
+        kind: &str,
+        count: usize,
+        seed: Option<u64>,
+        edge_cases: bool,
+    ) -> Result<Value, anyhow::Error> {
+        let url = format!("{}/admin/chains/{}/generate", self.base_url, chain);
+        let response = self
+            .client
+            .post(&url)
+            .json(&serde_json::json!({
+                "kind": kind,
+                "count": count,
+                "seed": seed,
+                "edgeCases": edge_cases,
+            }))
+            .send()
+            .await?;
+
+        Ok(Self::check_status(response).await?.json().await?)
+    }
+
+    /// Get the registered tokens, optionally only those with the given ID or on the given chain
+    pub async fn get_tokens(
+        &self,
+        token_id: Option<&str>,
+        chain: Option<&str>,
+    ) -> Result<Vec<Value>, anyhow::Error> {
+        let url = format!("{}/admin/tokens", self.base_url);
+        let mut query = Vec::new();
+        if let Some(token_id) = token_id {
+            query.push(("tokenID", token_id));
+        }
+        if let Some(chain) = chain {
+            query.push(("chain", chain));
+        }
+        let response = self.client.get(&url).query(&query).send().await?;
+
+        let body: Value = Self::check_status(response).await?.json().await?;
+        match body.get("tokens").and_then(|tokens| tokens.as_array()) {
+            Some(tokens) => Ok(tokens.clone()),
+            None => Err(anyhow::anyhow!("No tokens in response: {}", body)),
+        }
+    }
+
+    /// Register a token of a chain by hand, `details` being its address, name, symbol, decimals,
+    /// deployer and linked tokens, all optional
+    pub async fn put_token(
+        &self,
+        token_id: &str,
+        chain: &str,
+        details: &Value,
+    ) -> Result<Value, anyhow::Error> {
+        let url = format!("{}/admin/tokens/{}/{}", self.base_url, token_id, chain);
+        let response = self.client.put(&url).json(details).send().await?;
+
+        Ok(Self::check_status(response).await?.json().await?)
+    }
+
+    /// Get the transfer ledger, optionally only the transfers from or to a chain or with a status
+    pub async fn get_transfers(
+        &self,
+        chain: Option<&str>,
+        status: Option<&str>,
+    ) -> Result<Vec<Value>, anyhow::Error> {
+        let url = format!("{}/admin/transfers", self.base_url);
+        let mut query = Vec::new();
+        if let Some(chain) = chain {
+            query.push(("chain", chain));
+        }
+        if let Some(status) = status {
+            query.push(("status", status));
+        }
+        let response = self.client.get(&url).query(&query).send().await?;
+
+        let body: Value = Self::check_status(response).await?.json().await?;
+        match body
+            .get("transfers")
+            .and_then(|transfers| transfers.as_array())
+        {
+            Some(transfers) => Ok(transfers.clone()),
+            None => Err(anyhow::anyhow!("No transfers in response: {}", body)),
+        }
+    }
+
+    /// Get the transfer of a message, `None` if neither of its legs was reported
+    pub async fn get_transfer(&self, message_id: &str) -> Result<Option<Value>, anyhow::Error> {
+        let url = format!("{}/admin/transfers/{}", self.base_url, message_id);
+        let response = self.client.get(&url).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        Ok(Some(Self::check_status(response).await?.json().await?))
     }
 
     /// Post binary payload and get keccak256 hash
-    pub async fn post_payload(&self, payload: &[u8]) -> Result<String, Box<dyn Error>> {
+    pub async fn post_payload(&self, payload: &[u8]) -> Result<String, anyhow::Error> {
         let url = format!("{}/payloads", self.base_url);
 
         debug!("Posting payload of {} bytes to: {}", payload.len(), url);
@@ -71,12 +348,12 @@ impl Client {
         } else {
             let error_msg = format!("Payload upload failed with status: {}", response.status());
             error!("{}", error_msg);
-            Err(error_msg.into())
+            Err(anyhow::anyhow!(error_msg))
         }
     }
 
     /// Get payload by keccak256 hash (with 0x prefix)
-    pub async fn get_payload(&self, hash: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    pub async fn get_payload(&self, hash: &str) -> Result<Vec<u8>, anyhow::Error> {
         let url = format!("{}/payloads/{}", self.base_url, hash);
 
         debug!("Getting payload from: {}", url);
@@ -90,19 +367,27 @@ impl Client {
         } else if response.status() == 404 {
             let error_msg = format!("Payload not found for hash: {}", hash);
             error!("{}", error_msg);
-            Err(error_msg.into())
+            Err(anyhow::anyhow!(error_msg))
         } else {
             let error_msg = format!(
                 "Payload retrieval failed with status: {}",
                 response.status()
             );
             error!("{}", error_msg);
-            Err(error_msg.into())
+            Err(anyhow::anyhow!(error_msg))
         }
     }
 
+    /// Get the size, content type, timestamps and referencing messages of a payload
+    pub async fn get_payload_metadata(&self, hash: &str) -> Result<Value, anyhow::Error> {
+        let url = format!("{}/payloads/{}/metadata", self.base_url, hash);
+        let response = self.client.get(&url).send().await?;
+
+        Ok(Self::check_status(response).await?.json().await?)
+    }
+
     /// Test method: post payload, get it back, and verify they match
-    pub async fn test_payload_roundtrip(&self, test_data: &[u8]) -> Result<bool, Box<dyn Error>> {
+    pub async fn test_payload_roundtrip(&self, test_data: &[u8]) -> Result<bool, anyhow::Error> {
         info!("Testing payload roundtrip with {} bytes", test_data.len());
 
         // Post the payload
@@ -123,4 +408,15 @@ impl Client {
 
         Ok(matches)
     }
+
+    async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, anyhow::Error> {
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        let error_msg = format!("Request failed with status {}: {}", status, body);
+        error!("{}", error_msg);
+        Err(anyhow::anyhow!(error_msg))
+    }
 }
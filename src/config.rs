@@ -0,0 +1,852 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::EnvFilter;
+
+use crate::models::chains::ChainContracts;
+use crate::rules::{CONSTRUCT_PROOF, EventRule, VERIFY_MESSAGES};
+
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+/// Longest lease of a claimed task in seconds, the same as the maximum visibility timeout of SQS
+pub const MAX_VISIBILITY_TIMEOUT: u64 = 43_200;
+
+/// Configuration shared by the server and the subscriber.
+///
+/// Loaded from a TOML file (`CONFIG_PATH`, or `config.toml` if it exists) and then overridden
+/// by the environment variables listed in [`Config::apply_overrides`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub queue: QueueConfig,
+    #[serde(default)]
+    pub axelar: AxelarConfig,
+    #[serde(default)]
+    pub subscriber: SubscriberConfig,
+    #[serde(default)]
+    pub indexer: IndexerConfig,
+    #[serde(default)]
+    pub payloads: PayloadsConfig,
+    #[serde(default)]
+    pub tasks: TasksConfig,
+    #[serde(default)]
+    pub transfers: TransfersConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    /// Contracts of every chain integration served by the mock, keyed by chain name. They are
+    /// written to the chains table on startup and can be changed through the admin API.
+    #[serde(default)]
+    pub chains: BTreeMap<String, ChainContracts>,
+    /// Flows the subscriber follows besides the built-in verify_messages and construct_proof
+    #[serde(default)]
+    pub rules: Vec<EventRule>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ServerConfig {
+    pub address: String,
+    pub port: u16,
+    /// Maximum size in bytes of a request body
+    pub max_body_size: usize,
+    /// Seconds to let in-flight requests (e.g. broadcasts) finish after SIGTERM
+    pub shutdown_timeout: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1".to_string(),
+            port: 3001,
+            max_body_size: 262_144, // 256k
+            shutdown_timeout: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct DatabaseConfig {
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct QueueConfig {
+    pub backend: QueueBackend,
+    /// AMQP broker, only used by the `amqp` backend
+    pub address: String,
+    pub name: String,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            backend: QueueBackend::Amqp,
+            address: "amqp://127.0.0.1:5672/%2f".to_string(),
+            name: "mock_gmp_api".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueBackend {
+    /// RabbitMQ at `queue.address`
+    #[default]
+    Amqp,
+    /// Job table in the database at `database.url`
+    Postgres,
+    /// In-process channel, the server then runs the subscriber itself
+    Memory,
+}
+
+impl FromStr for QueueBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "amqp" => Ok(QueueBackend::Amqp),
+            "postgres" => Ok(QueueBackend::Postgres),
+            "memory" => Ok(QueueBackend::Memory),
+            _ => Err("expected amqp, postgres or memory".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct AxelarConfig {
+    pub rpc: String,
+    /// Key needs to be in the test keyring-backend so that it does not require a password
+    pub key_name: String,
+    pub chain_id: String,
+    pub gas_price: String,
+    pub gas_adjustment: f64,
+    /// Multisig contract shared by every chain, whose signing sessions the websocket watcher
+    /// subscribes to
+    pub multisig_contract: String,
+}
+
+impl Default for AxelarConfig {
+    fn default() -> Self {
+        Self {
+            rpc: String::new(),
+            key_name: String::new(),
+            chain_id: String::new(),
+            gas_price: "0.00005uamplifier".to_string(),
+            gas_adjustment: 1.5,
+            multisig_contract: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct SubscriberConfig {
+    /// Address and port of the /healthz and /readyz endpoints of the subscriber
+    pub health_address: String,
+    pub health_port: u16,
+    /// Seconds to let an in-flight queue item finish after SIGTERM before it is requeued
+    pub shutdown_timeout: u64,
+    /// Queue items handled in parallel
+    pub workers: usize,
+    /// Unacked AMQP deliveries the broker sends ahead, at least `workers` to keep them all busy
+    pub prefetch: u16,
+    /// Seconds after which a queue item that is still being handled counts as failed
+    pub job_timeout: u64,
+    /// Watch for quorum_reached and signing_completed events on the CometBFT websocket of the RPC
+    /// instead of paging through `axelard query txs` on every attempt
+    pub websocket: bool,
+    /// Seconds a job waits for its event on the websocket before it falls back to paging
+    pub websocket_wait: u64,
+    /// Attempts after which a failing queue item is moved to the dead letters
+    pub max_attempts: u32,
+    /// Seconds before the first retry of a failed item, doubled on every further attempt
+    pub retry_initial_delay: u64,
+    /// Upper bound in seconds of the delay between two attempts
+    pub retry_max_delay: u64,
+}
+
+impl Default for SubscriberConfig {
+    fn default() -> Self {
+        Self {
+            health_address: "127.0.0.1".to_string(),
+            health_port: 3002,
+            shutdown_timeout: 30,
+            workers: 4,
+            prefetch: 8,
+            job_timeout: 300,
+            websocket: false,
+            websocket_wait: 60,
+            max_attempts: 10,
+            retry_initial_delay: 5,
+            retry_max_delay: 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct IndexerConfig {
+    /// Store the wasm events of the registered contracts in the database, so that the subscriber
+    /// looks them up there instead of paging through `axelard query txs`
+    pub enabled: bool,
+    /// Height to start from when there is no checkpoint yet, 0 for the latest block
+    pub start_height: u64,
+    /// Blocks searched per RPC query
+    pub batch_size: u64,
+    /// Seconds to wait for new blocks once the indexer has caught up
+    pub poll_interval: u64,
+}
+
+impl Default for IndexerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start_height: 0,
+            batch_size: 500,
+            poll_interval: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct PayloadsConfig {
+    /// Maximum size in bytes of a payload posted to `/payloads`, separate from
+    /// `server.max_body_size` so that large GMP payloads can be tested
+    pub max_size: usize,
+    /// Seconds a stored payload is served for, 0 to keep payloads forever
+    pub ttl: u64,
+}
+
+impl Default for PayloadsConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 16_777_216, // 16M
+            ttl: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct TasksConfig {
+    /// Seconds a task claimed by a consumer stays hidden from other consumers before it is
+    /// handed out again, unless it is acked
+    pub visibility_timeout: u64,
+    /// Maximum number of tasks leased by one claim
+    pub claim_limit: i64,
+}
+
+impl Default for TasksConfig {
+    fn default() -> Self {
+        Self {
+            visibility_timeout: 60,
+            claim_limit: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct TransfersConfig {
+    /// Seconds after which a transfer that was sent but not received is reported as overdue, 0
+    /// to never report one
+    pub arrival_timeout: u64,
+}
+
+impl Default for TransfersConfig {
+    fn default() -> Self {
+        Self {
+            arrival_timeout: 600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct LoggingConfig {
+    /// Default level of every module
+    pub level: String,
+    /// Per-module directives in `RUST_LOG` syntax, e.g. `sqlx=warn,mock_gmp_api::subscriber=debug`
+    pub filter: String,
+    pub format: LogFormat,
+    /// Also write daily rotated log files to this directory if set
+    pub directory: String,
+    /// Logged payloads, broadcasts and command outputs are truncated to this many characters
+    pub max_field_length: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            filter: String::new(),
+            format: LogFormat::Text,
+            directory: String::new(),
+            max_field_length: 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("expected text or json".to_string()),
+        }
+    }
+}
+
+impl LoggingConfig {
+    pub fn level_filter(&self) -> Result<LevelFilter, anyhow::Error> {
+        LevelFilter::from_str(&self.level)
+            .map_err(|_| anyhow::anyhow!("logging.level: invalid log level '{}'", self.level))
+    }
+
+    /// The level followed by the per-module directives
+    pub fn env_filter(&self) -> Result<EnvFilter, anyhow::Error> {
+        let level = self.level_filter()?;
+        let directives = if self.filter.trim().is_empty() {
+            level.to_string()
+        } else {
+            format!("{},{}", level, self.filter)
+        };
+        EnvFilter::try_new(&directives)
+            .map_err(|e| anyhow::anyhow!("logging.filter: invalid filter '{}': {}", self.filter, e))
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct TelemetryConfig {
+    /// OTLP/HTTP traces endpoint, e.g. http://localhost:4318/v1/traces. Spans are not exported
+    /// if empty.
+    pub otlp_endpoint: String,
+}
+
+impl Config {
+    /// Load the configuration file, apply the environment overrides and validate the result
+    pub fn load() -> Result<Self, anyhow::Error> {
+        let path = std::env::var("CONFIG_PATH").ok();
+        let mut config = match &path {
+            Some(path) => Self::from_file(path)?,
+            None if std::path::Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(DEFAULT_CONFIG_PATH)?
+            }
+            None => Self::default(),
+        };
+        config.apply_overrides(|name| std::env::var(name).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, anyhow::Error> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read config file {}: {}", path, e))?;
+        Self::from_toml(&contents)
+            .map_err(|e| anyhow::anyhow!("Invalid config file {}: {}", path, e))
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, anyhow::Error> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// Override config values with the variables returned by `lookup`, which is the environment
+    /// outside of tests
+    pub fn apply_overrides(
+        &mut self,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<(), anyhow::Error> {
+        override_with(&lookup, "SERVER_ADDRESS", &mut self.server.address)?;
+        override_with(&lookup, "SERVER_PORT", &mut self.server.port)?;
+        override_with(&lookup, "MAX_BODY_SIZE", &mut self.server.max_body_size)?;
+        override_with(
+            &lookup,
+            "SHUTDOWN_TIMEOUT",
+            &mut self.server.shutdown_timeout,
+        )?;
+        override_with(&lookup, "POSTGRES_URL", &mut self.database.url)?;
+        override_with(&lookup, "QUEUE_BACKEND", &mut self.queue.backend)?;
+        override_with(&lookup, "QUEUE_ADDRESS", &mut self.queue.address)?;
+        override_with(&lookup, "QUEUE_NAME", &mut self.queue.name)?;
+        override_with(&lookup, "AXELAR_RPC", &mut self.axelar.rpc)?;
+        override_with(&lookup, "AXELAR_KEY_NAME", &mut self.axelar.key_name)?;
+        override_with(&lookup, "CHAIN_ID", &mut self.axelar.chain_id)?;
+        override_with(&lookup, "GAS_PRICE", &mut self.axelar.gas_price)?;
+        override_with(&lookup, "GAS_ADJUSTMENT", &mut self.axelar.gas_adjustment)?;
+        override_with(
+            &lookup,
+            "MULTISIG_CONTRACT",
+            &mut self.axelar.multisig_contract,
+        )?;
+        override_with(
+            &lookup,
+            "SUBSCRIBER_HEALTH_ADDRESS",
+            &mut self.subscriber.health_address,
+        )?;
+        override_with(
+            &lookup,
+            "SUBSCRIBER_HEALTH_PORT",
+            &mut self.subscriber.health_port,
+        )?;
+        override_with(
+            &lookup,
+            "SUBSCRIBER_SHUTDOWN_TIMEOUT",
+            &mut self.subscriber.shutdown_timeout,
+        )?;
+        override_with(&lookup, "SUBSCRIBER_WORKERS", &mut self.subscriber.workers)?;
+        override_with(
+            &lookup,
+            "SUBSCRIBER_PREFETCH",
+            &mut self.subscriber.prefetch,
+        )?;
+        override_with(
+            &lookup,
+            "SUBSCRIBER_JOB_TIMEOUT",
+            &mut self.subscriber.job_timeout,
+        )?;
+        override_with(
+            &lookup,
+            "SUBSCRIBER_WEBSOCKET",
+            &mut self.subscriber.websocket,
+        )?;
+        override_with(
+            &lookup,
+            "SUBSCRIBER_WEBSOCKET_WAIT",
+            &mut self.subscriber.websocket_wait,
+        )?;
+        override_with(
+            &lookup,
+            "SUBSCRIBER_MAX_ATTEMPTS",
+            &mut self.subscriber.max_attempts,
+        )?;
+        override_with(
+            &lookup,
+            "SUBSCRIBER_RETRY_INITIAL_DELAY",
+            &mut self.subscriber.retry_initial_delay,
+        )?;
+        override_with(
+            &lookup,
+            "SUBSCRIBER_RETRY_MAX_DELAY",
+            &mut self.subscriber.retry_max_delay,
+        )?;
+        override_with(&lookup, "INDEXER_ENABLED", &mut self.indexer.enabled)?;
+        override_with(
+            &lookup,
+            "INDEXER_START_HEIGHT",
+            &mut self.indexer.start_height,
+        )?;
+        override_with(&lookup, "INDEXER_BATCH_SIZE", &mut self.indexer.batch_size)?;
+        override_with(
+            &lookup,
+            "INDEXER_POLL_INTERVAL",
+            &mut self.indexer.poll_interval,
+        )?;
+        override_with(&lookup, "MAX_PAYLOAD_SIZE", &mut self.payloads.max_size)?;
+        override_with(&lookup, "PAYLOAD_TTL", &mut self.payloads.ttl)?;
+        override_with(
+            &lookup,
+            "TASK_VISIBILITY_TIMEOUT",
+            &mut self.tasks.visibility_timeout,
+        )?;
+        override_with(&lookup, "TASK_CLAIM_LIMIT", &mut self.tasks.claim_limit)?;
+        override_with(
+            &lookup,
+            "TRANSFER_ARRIVAL_TIMEOUT",
+            &mut self.transfers.arrival_timeout,
+        )?;
+        override_with(&lookup, "LOG_LEVEL", &mut self.logging.level)?;
+        override_with(&lookup, "RUST_LOG", &mut self.logging.filter)?;
+        override_with(&lookup, "LOG_FORMAT", &mut self.logging.format)?;
+        override_with(&lookup, "LOG_DIRECTORY", &mut self.logging.directory)?;
+        override_with(&lookup, "OTLP_ENDPOINT", &mut self.telemetry.otlp_endpoint)?;
+        Ok(())
+    }
+
+    /// Check that every required value is set, reporting all the missing ones at once
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        let mut errors = Vec::new();
+
+        let queue_address = match self.queue.backend {
+            QueueBackend::Amqp => Some(("queue.address", "QUEUE_ADDRESS", &self.queue.address)),
+            QueueBackend::Postgres | QueueBackend::Memory => None,
+        };
+        let required = [
+            ("server.address", "SERVER_ADDRESS", &self.server.address),
+            ("database.url", "POSTGRES_URL", &self.database.url),
+            ("queue.name", "QUEUE_NAME", &self.queue.name),
+            ("axelar.rpc", "AXELAR_RPC", &self.axelar.rpc),
+            ("axelar.key_name", "AXELAR_KEY_NAME", &self.axelar.key_name),
+            ("axelar.chain_id", "CHAIN_ID", &self.axelar.chain_id),
+            ("axelar.gas_price", "GAS_PRICE", &self.axelar.gas_price),
+            (
+                "subscriber.health_address",
+                "SUBSCRIBER_HEALTH_ADDRESS",
+                &self.subscriber.health_address,
+            ),
+        ];
+        let multisig_contract = self.subscriber.websocket.then_some((
+            "axelar.multisig_contract",
+            "MULTISIG_CONTRACT",
+            &self.axelar.multisig_contract,
+        ));
+        for (key, env_name, value) in required
+            .into_iter()
+            .chain(queue_address)
+            .chain(multisig_contract)
+        {
+            if value.trim().is_empty() {
+                errors.push(format!(
+                    "{} is not set (set it in the config file or {})",
+                    key, env_name
+                ));
+            }
+        }
+
+        for (name, contracts) in &self.chains {
+            let addresses = [
+                ("gateway", &contracts.gateway),
+                ("voting_verifier", &contracts.voting_verifier),
+                ("multisig_prover", &contracts.multisig_prover),
+            ];
+            for (key, address) in addresses {
+                if address.trim().is_empty() {
+                    errors.push(format!("chains.{}.{} must not be empty", name, key));
+                }
+            }
+        }
+
+        for (i, rule) in self.rules.iter().enumerate() {
+            let fields = [
+                ("name", &rule.name),
+                ("trigger_event", &rule.trigger_event),
+                ("correlation_attribute", &rule.correlation_attribute),
+                ("follow_up_event", &rule.follow_up_event),
+            ];
+            for (key, value) in fields {
+                if value.trim().is_empty() {
+                    errors.push(format!("rules[{}].{} must not be empty", i, key));
+                }
+            }
+            if rule.name == VERIFY_MESSAGES
+                || rule.name == CONSTRUCT_PROOF
+                || self.rules[..i].iter().any(|other| other.name == rule.name)
+            {
+                errors.push(format!(
+                    "rules[{}].name '{}' is already taken",
+                    i, rule.name
+                ));
+            }
+            // follow-up events are keyed by the correlation attribute of the first rule they
+            // complete, so a rule with another attribute would never find its event
+            let built_in = [EventRule::verify_messages(), EventRule::construct_proof()];
+            if let Some(other) = built_in.iter().chain(&self.rules[..i]).find(|other| {
+                other.follow_up_event == rule.follow_up_event
+                    && other.correlation_attribute != rule.correlation_attribute
+            }) {
+                errors.push(format!(
+                    "rules[{}].correlation_attribute '{}' differs from '{}' of rule '{}', which has the same follow_up_event",
+                    i, rule.correlation_attribute, other.correlation_attribute, other.name
+                ));
+            }
+        }
+
+        if self.server.max_body_size == 0 {
+            errors.push("server.max_body_size must be greater than 0".to_string());
+        }
+        if self.axelar.gas_adjustment <= 0.0 {
+            errors.push("axelar.gas_adjustment must be greater than 0".to_string());
+        }
+        if self.subscriber.workers == 0 {
+            errors.push("subscriber.workers must be greater than 0".to_string());
+        }
+        if (self.subscriber.prefetch as usize) < self.subscriber.workers {
+            errors
+                .push("subscriber.prefetch must not be lower than subscriber.workers".to_string());
+        }
+        if self.subscriber.job_timeout == 0 {
+            errors.push("subscriber.job_timeout must be greater than 0".to_string());
+        }
+        if self.subscriber.websocket
+            && self.subscriber.websocket_wait >= self.subscriber.job_timeout
+        {
+            errors.push(
+                "subscriber.websocket_wait must be lower than subscriber.job_timeout".to_string(),
+            );
+        }
+        if self.subscriber.max_attempts == 0 {
+            errors.push("subscriber.max_attempts must be greater than 0".to_string());
+        }
+        if self.subscriber.retry_initial_delay == 0 {
+            errors.push("subscriber.retry_initial_delay must be greater than 0".to_string());
+        }
+        if self.subscriber.retry_max_delay < self.subscriber.retry_initial_delay {
+            errors.push(
+                "subscriber.retry_max_delay must not be lower than subscriber.retry_initial_delay"
+                    .to_string(),
+            );
+        }
+        if self.indexer.batch_size == 0 {
+            errors.push("indexer.batch_size must be greater than 0".to_string());
+        }
+        if self.indexer.poll_interval == 0 {
+            errors.push("indexer.poll_interval must be greater than 0".to_string());
+        }
+        if self.payloads.max_size == 0 {
+            errors.push("payloads.max_size must be greater than 0".to_string());
+        }
+        if self.tasks.visibility_timeout == 0 {
+            errors.push("tasks.visibility_timeout must be greater than 0".to_string());
+        }
+        if self.tasks.visibility_timeout > MAX_VISIBILITY_TIMEOUT {
+            errors.push(format!(
+                "tasks.visibility_timeout must not be greater than {}",
+                MAX_VISIBILITY_TIMEOUT
+            ));
+        }
+        if self.tasks.claim_limit <= 0 {
+            errors.push("tasks.claim_limit must be greater than 0".to_string());
+        }
+        if let Err(e) = self.logging.env_filter() {
+            errors.push(e.to_string());
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Invalid configuration:\n  {}",
+                errors.join("\n  ")
+            ))
+        }
+    }
+}
+
+fn override_with<T>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    name: &str,
+    target: &mut T,
+) -> Result<(), anyhow::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if let Some(value) = lookup(name).filter(|value| !value.is_empty()) {
+        *target = value
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid value for {}: '{}' ({})", name, value, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{Config, LogFormat, MAX_VISIBILITY_TIMEOUT, QueueBackend};
+    use crate::rules::{EventRule, RuleTask};
+
+    #[test]
+    fn test_example_config_is_valid() {
+        let config = Config::from_toml(include_str!("../config.example.toml")).unwrap();
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let mut config = Config::from_toml(include_str!("../config.example.toml")).unwrap();
+        let env = HashMap::from([
+            ("SERVER_PORT", "9000"),
+            ("POSTGRES_URL", "postgres://override"),
+            ("GAS_ADJUSTMENT", "2.5"),
+            ("MAX_PAYLOAD_SIZE", "1048576"),
+        ]);
+
+        config
+            .apply_overrides(|name| env.get(name).map(|v| v.to_string()))
+            .unwrap();
+
+        assert_eq!(config.server.port, 9000);
+        assert_eq!(config.database.url, "postgres://override");
+        assert_eq!(config.axelar.gas_adjustment, 2.5);
+        assert_eq!(config.payloads.max_size, 1_048_576);
+        assert_eq!(config.server.max_body_size, 262_144);
+    }
+
+    #[test]
+    fn test_invalid_override_is_an_error() {
+        let mut config = Config::default();
+        let result = config
+            .apply_overrides(|name| (name == "SERVER_PORT").then(|| "not_a_port".to_string()));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_missing_values_are_reported() {
+        let error = Config::default().validate().unwrap_err().to_string();
+
+        assert!(error.contains("database.url"));
+        assert!(error.contains("AXELAR_RPC"));
+        assert!(error.contains("CHAIN_ID"));
+    }
+
+    #[test]
+    fn test_zero_retry_delay_is_rejected() {
+        let mut config = Config::from_toml(include_str!("../config.example.toml")).unwrap();
+        config.subscriber.retry_initial_delay = 0;
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("subscriber.retry_initial_delay"));
+    }
+
+    #[test]
+    fn test_visibility_timeout_is_bounded() {
+        let mut config = Config::from_toml(include_str!("../config.example.toml")).unwrap();
+        config.tasks.visibility_timeout = MAX_VISIBILITY_TIMEOUT + 1;
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("tasks.visibility_timeout must not be greater than 43200"));
+    }
+
+    #[test]
+    fn test_websocket_requires_the_multisig_contract() {
+        let mut config = Config::from_toml(include_str!("../config.example.toml")).unwrap();
+        config.subscriber.websocket = true;
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("MULTISIG_CONTRACT"));
+
+        config.axelar.multisig_contract = "axelar1multisig".to_string();
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_chains_are_parsed() {
+        let config = Config::from_toml(include_str!("../config.example.toml")).unwrap();
+        let xrpl = config.chains.get("xrpl").unwrap();
+
+        assert_eq!(
+            xrpl.multisig_prover,
+            "axelar1ys83sedjffmqh70aksejmx3fy3q2d7twm3msurk7wn3l6nkwxp0sfelzhl"
+        );
+    }
+
+    #[test]
+    fn test_rules_are_parsed_and_validated() {
+        let rule = r#"
+            [[rules]]
+            name = "signer_rotation"
+            trigger_event = "wasm-signers_rotation_started"
+            correlation_attribute = "session_id"
+            follow_up_event = "wasm-signing_completed"
+            task = "gateway_tx"
+        "#;
+        let mut config = Config::from_toml(&format!(
+            "{}\n{}",
+            include_str!("../config.example.toml"),
+            rule
+        ))
+        .unwrap();
+        config.validate().unwrap();
+        assert_eq!(config.rules[0].task, RuleTask::GatewayTx);
+        assert_eq!(config.rules[0].chain_attribute, None);
+
+        config.rules.push(config.rules[0].clone());
+        config.rules[0].name = "verify_messages".to_string();
+        config.rules[1].follow_up_event = String::new();
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("rules[0].name 'verify_messages' is already taken"));
+        assert!(error.contains("rules[1].follow_up_event must not be empty"));
+    }
+
+    #[test]
+    fn test_rules_sharing_a_follow_up_event_share_its_correlation_attribute() {
+        let mut config = Config::from_toml(include_str!("../config.example.toml")).unwrap();
+        config.rules.push(EventRule {
+            name: "signer_rotation".to_string(),
+            trigger_event: "wasm-signers_rotation_started".to_string(),
+            correlation_attribute: "signing_session".to_string(),
+            chain_attribute: None,
+            follow_up_event: "wasm-signing_completed".to_string(),
+            task: RuleTask::GatewayTx,
+        });
+
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains(
+            "rules[0].correlation_attribute 'signing_session' differs from 'session_id' of rule 'construct_proof'"
+        ));
+
+        config.rules[0].correlation_attribute = "session_id".to_string();
+        config.validate().unwrap();
+    }
+
+    #[test]
+    fn test_logging_overrides() {
+        let mut config = Config::from_toml(include_str!("../config.example.toml")).unwrap();
+        let env = HashMap::from([
+            ("RUST_LOG", "mock_gmp_api::subscriber=debug"),
+            ("LOG_FORMAT", "json"),
+        ]);
+
+        config
+            .apply_overrides(|name| env.get(name).map(|v| v.to_string()))
+            .unwrap();
+
+        assert_eq!(config.logging.format, LogFormat::Json);
+        assert_eq!(
+            config.logging.env_filter().unwrap().to_string(),
+            "mock_gmp_api::subscriber=debug,info"
+        );
+        assert!(
+            Config::from_toml("[logging]\nfilter = \"sqlx=loud\"")
+                .unwrap()
+                .logging
+                .env_filter()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_queue_address_is_only_required_for_amqp() {
+        let mut config = Config::from_toml(include_str!("../config.example.toml")).unwrap();
+        config.queue.address = String::new();
+        assert!(config.validate().is_err());
+
+        let env = HashMap::from([("QUEUE_BACKEND", "postgres")]);
+        config
+            .apply_overrides(|name| env.get(name).map(|v| v.to_string()))
+            .unwrap();
+
+        assert_eq!(config.queue.backend, QueueBackend::Postgres);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        assert!(Config::from_toml("[server]\nprot = 3000").is_err());
+    }
+}
@@ -1,15 +1,27 @@
+use base64::{Engine as _, engine::general_purpose};
 use chrono::{DateTime, Utc};
-use tracing::{debug, info, warn};
+use sha3::{Digest, Keccak256};
+use tracing::{debug, info, instrument, warn};
 use uuid::Uuid;
 
 use crate::{
     TasksModel,
     gmp_types::{
-        CommonTaskFields, Event, EventType, TaskKind, TaskMetadata, VerifyTask, VerifyTaskFields,
+        Amount, CommonTaskFields, Event, EventType, ExecuteTask, ExecuteTaskFields, ScopedMessage,
+        TaskKind, TaskMetadata, VerifyTask, VerifyTaskFields,
+    },
+    logging::loggable,
+    metrics::{METRICS, TaskSource},
+    models::{
+        events::EventsModel,
+        payloads::PayloadsModel,
+        tasks::TaskFilter,
+        tokens::{LinkedToken, Token, TokensModel},
+        transfers::{TransferLeg, TransfersModel},
     },
-    models::events::EventsModel,
 };
 
+#[instrument(skip_all, fields(chain, event_type = event_type_str, message_id = %event.message_id()))]
 pub async fn handle_call_or_gas_credit_event(
     event: Event,
     events_model: &EventsModel,
@@ -24,8 +36,8 @@ pub async fn handle_call_or_gas_credit_event(
         .map_err(|e| anyhow::anyhow!(e.to_string()))?;
     if maybe_event_with_same_type_and_message_id.is_some() {
         warn!(
-            "Event with same type and message ID already exists: {:?}",
-            event
+            "Event with same type and message ID already exists: {}",
+            loggable(&event)
         );
         Ok(())
     } else {
@@ -50,8 +62,8 @@ pub async fn handle_call_or_gas_credit_event(
             } else {
                 (event.clone(), corresponding_event)
             };
-            debug!("Call event: {:?}", call_event);
-            debug!("Gas credit event: {:?}", gas_credit_event);
+            debug!("Call event: {}", loggable(&call_event));
+            debug!("Gas credit event: {}", loggable(&gas_credit_event));
 
             let (message, payload, meta) = match &call_event {
                 Event::Call {
@@ -102,9 +114,355 @@ pub async fn handle_call_or_gas_credit_event(
                 // Maybe here we delete the corresponding events from the DB?
                 warn!("Failed to write VERIFY task to database: {:?}", e);
             } else {
-                info!("Created VERIFY task: {:?}", task);
+                METRICS.task_created(&TaskKind::Verify, TaskSource::EventHandler);
+                info!("Created VERIFY task: {}", loggable(&task));
             }
         }
         Ok(())
     }
 }
+
+/// Issue the EXECUTE task of a message approved on `chain`, its destination chain, with its
+/// payload and the gas paid for it that the approval did not cost. No task is issued if one was
+/// issued already or if the payload of the message is unknown.
+#[instrument(skip_all, fields(chain, message_id = %event.message_id()))]
+pub async fn handle_message_approved_event(
+    event: &Event,
+    events_model: &EventsModel,
+    tasks_model: &TasksModel,
+    payloads_model: &PayloadsModel,
+    chain: &str,
+) -> Result<(), anyhow::Error> {
+    let Event::MessageApproved {
+        common,
+        message,
+        cost,
+    } = event
+    else {
+        return Ok(());
+    };
+
+    let filter = TaskFilter {
+        r#type: Some(TaskKind::Execute),
+        message_id: Some(message.message_id.clone()),
+        ..Default::default()
+    };
+    if !tasks_model
+        .get_tasks(chain, None, &filter)
+        .await?
+        .is_empty()
+    {
+        warn!("EXECUTE task already issued for approved message");
+        return Ok(());
+    }
+
+    let payload_hash = decode_payload_hash(&message.payload_hash)
+        .ok_or_else(|| anyhow::anyhow!("Invalid payloadHash {}", message.payload_hash))?;
+    let Some(payload) = payloads_model
+        .find(&format!("0x{}", hex::encode(payload_hash)))
+        .await?
+    else {
+        warn!("Payload of approved message is unknown, not issuing an EXECUTE task");
+        return Ok(());
+    };
+
+    let gas_credit = events_model
+        .find_event_by_type_and_message_id(EventType::GasCredit, &message.message_id)
+        .await?;
+    let available_gas_balance = match gas_credit {
+        Some(Event::GasCredit { payment, .. }) => remaining_gas(&payment, cost),
+        _ => Amount {
+            token_id: cost.token_id.clone(),
+            amount: "0".to_string(),
+        },
+    };
+
+    let task = ExecuteTask {
+        common: CommonTaskFields {
+            id: Uuid::new_v4().to_string(),
+            chain: chain.to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+            r#type: "EXECUTE".to_string(),
+            meta: Some(TaskMetadata {
+                tx_id: common
+                    .meta
+                    .as_ref()
+                    .and_then(|meta| meta.common_meta.tx_id.clone()),
+                from_address: None,
+                finalized: None,
+                source_context: None,
+                scoped_messages: Some(vec![ScopedMessage {
+                    message_id: message.message_id.clone(),
+                    source_chain: message.source_chain.clone(),
+                }]),
+            }),
+        },
+        task: ExecuteTaskFields {
+            message: message.clone(),
+            payload: general_purpose::STANDARD.encode(payload),
+            available_gas_balance,
+        },
+    };
+
+    tasks_model
+        .upsert(
+            &task.common.id,
+            &task.common.chain,
+            task.common.timestamp.parse::<DateTime<Utc>>()?,
+            TaskKind::Execute,
+            Some(&serde_json::to_string(&task)?),
+        )
+        .await?;
+    METRICS.task_created(&TaskKind::Execute, TaskSource::EventHandler);
+    info!("Created EXECUTE task: {}", loggable(&task));
+
+    Ok(())
+}
+
+/// Update the token registry with an ITS token deployment, metadata registration or link. Both
+/// ends of a deployment or link are registered, each linked to the other.
+#[instrument(skip_all, fields(chain, event_type = event.event_type().as_str(), message_id = %event.message_id()))]
+pub async fn handle_its_token_event(
+    event: &Event,
+    tokens_model: &TokensModel,
+    chain: &str,
+) -> Result<(), anyhow::Error> {
+    match event {
+        Event::ITSInterchainTokenDeploymentStarted {
+            common,
+            destination_chain,
+            token,
+            ..
+        } => {
+            let deployed = |chain: &str, linked_chain: &str| Token {
+                name: Some(token.name.clone()),
+                symbol: Some(token.symbol.clone()),
+                decimals: Some(token.decimals.into()),
+                deployer: common.meta.as_ref().and_then(|m| m.from_address.clone()),
+                linked_tokens: vec![LinkedToken {
+                    chain: linked_chain.to_string(),
+                    address: None,
+                }],
+                ..Token::new(&token.id, chain)
+            };
+            tokens_model
+                .upsert(&deployed(chain, destination_chain))
+                .await?;
+            tokens_model
+                .upsert(&deployed(destination_chain, chain))
+                .await?;
+            info!(
+                "Registered token {} on {} and {}",
+                token.id, chain, destination_chain
+            );
+        }
+        Event::ITSTokenMetadataRegistered {
+            address, decimals, ..
+        } => {
+            tokens_model
+                .register_metadata(chain, address, (*decimals).into())
+                .await?;
+            debug!("Registered metadata of token {}", address);
+        }
+        Event::ITSLinkTokenStarted {
+            common,
+            destination_chain,
+            token_id,
+            source_token_address,
+            destination_token_address,
+            ..
+        } => {
+            let deployer = common.meta.as_ref().and_then(|m| m.from_address.clone());
+            let source = Token {
+                address: Some(source_token_address.clone()),
+                decimals: tokens_model
+                    .find_decimals(chain, source_token_address)
+                    .await?,
+                deployer: deployer.clone(),
+                linked_tokens: vec![LinkedToken {
+                    chain: destination_chain.clone(),
+                    address: Some(destination_token_address.clone()),
+                }],
+                ..Token::new(token_id, chain)
+            };
+            let destination = Token {
+                address: Some(destination_token_address.clone()),
+                decimals: tokens_model
+                    .find_decimals(destination_chain, destination_token_address)
+                    .await?,
+                deployer,
+                linked_tokens: vec![LinkedToken {
+                    chain: chain.to_string(),
+                    address: Some(source_token_address.clone()),
+                }],
+                ..Token::new(token_id, destination_chain)
+            };
+            tokens_model.upsert(&source).await?;
+            tokens_model.upsert(&destination).await?;
+            info!(
+                "Linked token {} on {} and {}",
+                token_id, chain, destination_chain
+            );
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Record the sent or received leg of an application transfer in the transfer ledger, where it is
+/// paired with the other leg by message ID
+#[instrument(skip_all, fields(chain, event_type = event.event_type().as_str(), message_id = %event.message_id()))]
+pub async fn handle_app_transfer_event(
+    event: &Event,
+    transfers_model: &TransfersModel,
+    chain: &str,
+) -> Result<(), anyhow::Error> {
+    let (_, _, timestamp) = event.common_fields();
+    match event {
+        Event::AppInterchainTransferSent {
+            common,
+            message_id,
+            destination_chain,
+            sender,
+            recipient,
+            token_spent,
+        } => {
+            let leg = TransferLeg {
+                event_id: common.event_id.clone(),
+                source_chain: chain.to_string(),
+                destination_chain: destination_chain.clone(),
+                sender: sender.clone(),
+                recipient: recipient.clone(),
+                token: token_spent.clone(),
+                timestamp: timestamp.to_string(),
+            };
+            transfers_model.record_sent(message_id, &leg).await?;
+            debug!("Recorded transfer sent to {}", destination_chain);
+        }
+        Event::AppInterchainTransferReceived {
+            common,
+            message_id,
+            source_chain,
+            sender,
+            recipient,
+            token_received,
+        } => {
+            let leg = TransferLeg {
+                event_id: common.event_id.clone(),
+                source_chain: source_chain.clone(),
+                destination_chain: chain.to_string(),
+                sender: sender.clone(),
+                recipient: recipient.clone(),
+                token: token_received.clone(),
+                timestamp: timestamp.to_string(),
+            };
+            transfers_model.record_received(message_id, &leg).await?;
+            debug!("Recorded transfer received from {}", source_chain);
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// What is left of `payment` after `cost`, none if the amounts are not integers
+fn remaining_gas(payment: &Amount, cost: &Amount) -> Amount {
+    let remaining = match (payment.amount.parse::<u128>(), cost.amount.parse::<u128>()) {
+        (Ok(payment), Ok(cost)) => payment.saturating_sub(cost),
+        _ => 0,
+    };
+    Amount {
+        token_id: payment.token_id.clone(),
+        amount: remaining.to_string(),
+    }
+}
+
+/// The payload of a CALL event and its keccak256 hash with the 0x prefix, after checking the hash
+/// against the `payloadHash` of the message. Other events have no payload.
+pub fn verified_call_payload(event: &Event) -> Result<Option<(String, Vec<u8>)>, anyhow::Error> {
+    let Event::Call {
+        message, payload, ..
+    } = event
+    else {
+        return Ok(None);
+    };
+
+    let payload = general_purpose::STANDARD
+        .decode(payload)
+        .map_err(|e| anyhow::anyhow!("Invalid base64 payload: {}", e))?;
+    let hash = Keccak256::digest(&payload);
+    let expected = decode_payload_hash(&message.payload_hash)
+        .ok_or_else(|| anyhow::anyhow!("Invalid payloadHash {}", message.payload_hash))?;
+    if hash.as_slice() != expected.as_slice() {
+        return Err(anyhow::anyhow!(
+            "payloadHash {} does not match the keccak256 hash 0x{} of the payload",
+            message.payload_hash,
+            hex::encode(hash)
+        ));
+    }
+
+    Ok(Some((format!("0x{}", hex::encode(hash)), payload)))
+}
+
+/// Payload hashes are hex encoded, with or without 0x, or base64 encoded like in Amplifier tasks
+pub fn decode_payload_hash(payload_hash: &str) -> Option<Vec<u8>> {
+    let hex_hash = payload_hash.strip_prefix("0x").unwrap_or(payload_hash);
+    hex::decode(hex_hash)
+        .ok()
+        .or_else(|| general_purpose::STANDARD.decode(payload_hash).ok())
+        .filter(|hash| hash.len() == 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{Engine as _, engine::general_purpose};
+
+    use super::{decode_payload_hash, verified_call_payload};
+    use crate::gmp_types::Event;
+
+    #[test]
+    fn test_call_payload_is_checked_against_its_hash() {
+        let events: Vec<Event> =
+            serde_json::from_str(include_str!("../testdata/events/CallEvent.json")).unwrap();
+        let mut event = events[0].clone();
+
+        let (hash, payload) = verified_call_payload(&event).unwrap().unwrap();
+        assert_eq!(
+            hash,
+            "0x73940153ab066fd16b1ce5aacffbe6c693b90d2fcd29e5927c0a06cce85f9e27"
+        );
+        assert_eq!(payload.len(), 576);
+
+        let Event::Call { message, .. } = &mut event else {
+            panic!("Expected Call event");
+        };
+        message.payload_hash = general_purpose::STANDARD.encode(hex::decode(&hash[2..]).unwrap());
+        assert!(verified_call_payload(&event).unwrap().is_some());
+
+        let Event::Call { message, .. } = &mut event else {
+            panic!("Expected Call event");
+        };
+        message.payload_hash = format!("0x{}", "00".repeat(32));
+        let error = verified_call_payload(&event).unwrap_err().to_string();
+        assert!(error.contains("does not match"), "{}", error);
+    }
+
+    #[test]
+    fn test_payload_hash_encodings() {
+        let hash = "73940153ab066fd16b1ce5aacffbe6c693b90d2fcd29e5927c0a06cce85f9e27";
+        let bytes = hex::decode(hash).unwrap();
+
+        assert_eq!(decode_payload_hash(hash), Some(bytes.clone()));
+        assert_eq!(
+            decode_payload_hash(&format!("0x{}", hash)),
+            Some(bytes.clone())
+        );
+        assert_eq!(
+            decode_payload_hash(&general_purpose::STANDARD.encode(&bytes)),
+            Some(bytes)
+        );
+        assert_eq!(decode_payload_hash("0x1234"), None);
+        assert_eq!(decode_payload_hash("not a hash"), None);
+    }
+}
@@ -0,0 +1,638 @@
+use std::collections::HashMap;
+
+use base64::{Engine as _, engine::general_purpose};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::IndexedRandom};
+use serde_json::json;
+use sha3::{Digest, Keccak256};
+
+use crate::gmp_types::{
+    Amount, CannotExecuteMessageReason, CommonEventFields, CommonTaskFields, ConstructProofTask,
+    ConstructProofTaskFields, Event, EventAttribute, EventMetadata, EventType, ExecuteTask,
+    ExecuteTaskFields, GatewayTxTask, GatewayTxTaskFields, GatewayV2Message,
+    InterchainTokenDefinition, MessageApprovedEventMetadata, MessageExecutedEventMetadata,
+    MessageExecutionStatus, QuorumReachedEvent, ReactToExpiredSigningSessionTask,
+    ReactToExpiredSigningSessionTaskFields, ReactToRetriablePollTask,
+    ReactToRetriablePollTaskFields, ReactToWasmEventTask, ReactToWasmEventTaskFields, RefundTask,
+    RefundTaskFields, ScopedMessage, SignersRotatedEventMetadata, Task, TaskKind, TaskMetadata,
+    TokenManagerType, VerificationStatus, VerifyTask, VerifyTaskFields, WasmEvent,
+};
+
+/// Task kinds that can be generated, every kind the relayers handle
+pub const TASK_KINDS: [TaskKind; 8] = [
+    TaskKind::Verify,
+    TaskKind::Execute,
+    TaskKind::GatewayTx,
+    TaskKind::ConstructProof,
+    TaskKind::ReactToWasmEvent,
+    TaskKind::Refund,
+    TaskKind::ReactToExpiredSigningSession,
+    TaskKind::ReactToRetriablePoll,
+];
+
+pub const EVENT_TYPES: [EventType; 15] = [
+    EventType::Call,
+    EventType::GasRefunded,
+    EventType::GasCredit,
+    EventType::MessageExecuted,
+    EventType::CannotExecuteMessageV2,
+    EventType::ITSInterchainTransfer,
+    EventType::MessageApproved,
+    EventType::SignersRotated,
+    EventType::CannotExecuteMessage,
+    EventType::CannotRouteMessage,
+    EventType::ITSInterchainTokenDeploymentStarted,
+    EventType::ITSTokenMetadataRegistered,
+    EventType::ITSLinkTokenStarted,
+    EventType::AppInterchainTransferSent,
+    EventType::AppInterchainTransferReceived,
+];
+
+/// Chains the messages of the generated items come from or go to
+const CHAINS: &[&str] = &[
+    "ethereum",
+    "avalanche",
+    "polygon",
+    "xrpl",
+    "xrpl-evm",
+    "axelar",
+];
+const XRPL_ALPHABET: &[u8] = b"rpshnaf39wBUDNEGHJKLM4PQRST7VWXYZ2bcdeCg65jkm8oFqi1tuvAxyz";
+const BECH32_ALPHABET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const UNICODE_TEXT: &str = "ünïcödé ✓ 日本語 🚀 \u{200b}RTL: مرحبا";
+/// Largest payload generated, kept well below `payloads.max_size` so that generating thousands of
+/// items does not take gigabytes
+const MAX_PAYLOAD_SIZE: usize = 65_536;
+/// Size of the payloads generated outside of edge cases
+const TYPICAL_PAYLOAD_SIZE: usize = 512;
+const TOKEN_MANAGER_TYPES: [TokenManagerType; 5] = [
+    TokenManagerType::NativeInterchainToken,
+    TokenManagerType::MintBurnFrom,
+    TokenManagerType::LockUnlock,
+    TokenManagerType::LockUnlockFee,
+    TokenManagerType::MintBurn,
+];
+
+/// Generates random but well-formed tasks and events of a chain, for fuzzing relayers. The same
+/// seed and start time always give the same items.
+pub struct Generator {
+    rng: StdRng,
+    chain: String,
+    start: DateTime<Utc>,
+    edge_cases: bool,
+    generated: i64,
+    /// Tokens deployed or linked by the generated events, which the generated transfers spend
+    token_ids: Vec<String>,
+}
+
+impl Generator {
+    pub fn new(chain: &str, seed: u64, start: DateTime<Utc>) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            chain: chain.to_string(),
+            start,
+            edge_cases: false,
+            generated: 0,
+            token_ids: Vec::new(),
+        }
+    }
+
+    /// Now and then use extreme values: empty and largest payloads, zero and u128::MAX amounts,
+    /// unicode text and empty metadata
+    pub fn with_edge_cases(mut self, edge_cases: bool) -> Self {
+        self.edge_cases = edge_cases;
+        self
+    }
+
+    /// A task of a random kind
+    pub fn task(&mut self) -> Task {
+        let kind = TASK_KINDS.choose(&mut self.rng).unwrap().clone();
+        self.task_of(kind)
+    }
+
+    pub fn task_of(&mut self, kind: TaskKind) -> Task {
+        match kind {
+            TaskKind::Verify => {
+                let (message, payload) = self.message(&self.chain.clone(), None);
+                Task::Verify(VerifyTask {
+                    common: self.task_common("VERIFY"),
+                    task: VerifyTaskFields { message, payload },
+                })
+            }
+            TaskKind::Execute => {
+                let source_chain = self.other_chain();
+                let (message, payload) = self.message(&source_chain, Some(&self.chain.clone()));
+                Task::Execute(ExecuteTask {
+                    common: self.task_common("EXECUTE"),
+                    task: ExecuteTaskFields {
+                        message,
+                        payload,
+                        available_gas_balance: self.amount(),
+                    },
+                })
+            }
+            TaskKind::GatewayTx => Task::GatewayTx(GatewayTxTask {
+                common: self.task_common("GATEWAY_TX"),
+                task: GatewayTxTaskFields {
+                    execute_data: general_purpose::STANDARD.encode(self.payload_bytes()),
+                },
+            }),
+            TaskKind::ConstructProof => {
+                let source_chain = self.other_chain();
+                let (message, payload) = self.message(&source_chain, Some(&self.chain.clone()));
+                Task::ConstructProof(ConstructProofTask {
+                    common: self.task_common("CONSTRUCT_PROOF"),
+                    task: ConstructProofTaskFields { message, payload },
+                })
+            }
+            TaskKind::ReactToWasmEvent => {
+                let contract = self.address("axelar");
+                let message_id = self.message_id(&self.chain.clone());
+                let attributes = vec![
+                    EventAttribute {
+                        key: "_contract_address".to_string(),
+                        value: contract,
+                    },
+                    EventAttribute {
+                        key: "message_id".to_string(),
+                        value: message_id,
+                    },
+                    EventAttribute {
+                        key: "source_chain".to_string(),
+                        value: self.chain.clone(),
+                    },
+                ];
+                Task::ReactToWasmEvent(ReactToWasmEventTask {
+                    common: self.task_common("REACT_TO_WASM_EVENT"),
+                    task: ReactToWasmEventTaskFields {
+                        event: WasmEvent {
+                            attributes,
+                            r#type: "wasm-message_routed".to_string(),
+                        },
+                        height: self.rng.random_range(1..100_000_000),
+                    },
+                })
+            }
+            TaskKind::Refund => {
+                let (message, _) = self.message(&self.chain.clone(), None);
+                Task::Refund(RefundTask {
+                    common: self.task_common("REFUND"),
+                    task: RefundTaskFields {
+                        message,
+                        refund_recipient_address: self.address(&self.chain.clone()),
+                        remaining_gas_balance: self.amount(),
+                    },
+                })
+            }
+            TaskKind::ReactToExpiredSigningSession => {
+                let request_payload = json!({
+                    "construct_proof": [{
+                        "source_chain": self.other_chain(),
+                        "message_id": self.message_id(&self.chain.clone()),
+                    }]
+                });
+                Task::ReactToExpiredSigningSession(ReactToExpiredSigningSessionTask {
+                    common: self.task_common("REACT_TO_EXPIRED_SIGNING_SESSION"),
+                    task: ReactToExpiredSigningSessionTaskFields {
+                        session_id: self.rng.random_range(1..10_000_000),
+                        broadcast_id: self.uuid(),
+                        invoked_contract_address: self.address("axelar"),
+                        request_payload: request_payload.to_string(),
+                    },
+                })
+            }
+            TaskKind::ReactToRetriablePoll => {
+                let (message, _) = self.message(&self.chain.clone(), None);
+                let request_payload = json!({ "verify_messages": [message] });
+                let quorum_reached_events = self.rng.random_bool(0.5).then(|| {
+                    vec![QuorumReachedEvent {
+                        status: VerificationStatus::SucceededOnSourceChain,
+                        content: json!(message),
+                    }]
+                });
+                Task::ReactToRetriablePoll(ReactToRetriablePollTask {
+                    common: self.task_common("REACT_TO_RETRIABLE_POLL"),
+                    task: ReactToRetriablePollTaskFields {
+                        poll_id: self.rng.random_range(1..10_000_000),
+                        broadcast_id: self.uuid(),
+                        invoked_contract_address: self.address("axelar"),
+                        request_payload: request_payload.to_string(),
+                        quorum_reached_events,
+                    },
+                })
+            }
+            TaskKind::Unknown => unreachable!("unknown tasks are not generated"),
+        }
+    }
+
+    /// An event of a random type
+    pub fn event(&mut self) -> Event {
+        let event_type = EVENT_TYPES.choose(&mut self.rng).unwrap().clone();
+        self.event_of(event_type)
+    }
+
+    /// An event of the chain, as its relayer would post it
+    pub fn event_of(&mut self, event_type: EventType) -> Event {
+        let chain = self.chain.clone();
+        match event_type {
+            EventType::Call => {
+                let destination_chain = self.other_chain();
+                let (message, payload) = self.message(&chain, Some(&destination_chain));
+                Event::Call {
+                    common: self.event_common("CALL", "call"),
+                    message,
+                    destination_chain,
+                    payload,
+                }
+            }
+            EventType::GasRefunded => Event::GasRefunded {
+                common: self.event_common("GAS_REFUNDED", "gas-refunded"),
+                message_id: self.message_id(&chain),
+                recipient_address: self.address(&chain),
+                refunded_amount: self.amount(),
+                cost: self.amount(),
+            },
+            EventType::GasCredit => Event::GasCredit {
+                common: self.event_common("GAS_CREDIT", "gas"),
+                message_id: self.message_id(&chain),
+                refund_address: self.address(&chain),
+                payment: self.amount(),
+            },
+            EventType::MessageExecuted => {
+                let common = self.event_common("MESSAGE_EXECUTED", "message-executed");
+                let status = if self.rng.random_bool(0.8) {
+                    MessageExecutionStatus::SUCCESSFUL
+                } else {
+                    MessageExecutionStatus::REVERTED
+                };
+                let revert_reason =
+                    (status == MessageExecutionStatus::REVERTED).then(|| self.text());
+                let source_chain = self.other_chain();
+                Event::MessageExecuted {
+                    common: CommonEventFields {
+                        r#type: common.r#type,
+                        event_id: common.event_id,
+                        meta: common.meta.map(|meta| MessageExecutedEventMetadata {
+                            command_id: Some(self.hash()),
+                            child_message_ids: Some(vec![]),
+                            revert_reason,
+                            common_meta: meta,
+                        }),
+                    },
+                    message_id: self.message_id(&source_chain),
+                    source_chain,
+                    status,
+                    cost: self.amount(),
+                }
+            }
+            EventType::CannotExecuteMessageV2 => {
+                let source_chain = self.other_chain();
+                let reason = if self.rng.random_bool(0.5) {
+                    CannotExecuteMessageReason::InsufficientGas
+                } else {
+                    CannotExecuteMessageReason::Error
+                };
+                Event::CannotExecuteMessageV2 {
+                    common: self.event_common("CANNOT_EXECUTE_MESSAGE_V2", "cannot-execute"),
+                    message_id: self.message_id(&source_chain),
+                    source_chain,
+                    reason,
+                    details: self.text(),
+                }
+            }
+            EventType::CannotExecuteMessage => Event::CannotExecuteMessage {
+                common: self.event_common("CANNOT_EXECUTE_MESSAGE", "cannot-execute"),
+                task_item_id: self.uuid(),
+                reason: CannotExecuteMessageReason::Error,
+                details: self.text(),
+            },
+            EventType::CannotRouteMessage => {
+                let source_chain = self.other_chain();
+                Event::CannotRouteMessage {
+                    common: self.event_common("CANNOT_ROUTE_MESSAGE", "cannot-route"),
+                    message_id: self.message_id(&source_chain),
+                    reason: "ROUTING_FAILED".to_string(),
+                    details: self.text(),
+                }
+            }
+            EventType::ITSInterchainTransfer => {
+                let destination_chain = self.other_chain();
+                Event::ITSInterchainTransfer {
+                    common: self.event_common("ITS_INTERCHAIN_TRANSFER", "its-transfer"),
+                    message_id: self.message_id(&chain),
+                    destination_address: self.address(&destination_chain),
+                    destination_chain,
+                    token_spent: Amount {
+                        token_id: self.token_ids.choose(&mut self.rng).cloned(),
+                        ..self.amount()
+                    },
+                    source_address: self.address(&chain),
+                    data_hash: self.hash(),
+                }
+            }
+            EventType::ITSInterchainTokenDeploymentStarted => {
+                let token_id = self.hash();
+                self.token_ids.push(token_id.clone());
+                let symbol = format!("T{}", self.rng.random_range(0..10_000));
+                Event::ITSInterchainTokenDeploymentStarted {
+                    common: self
+                        .event_common("ITS_INTERCHAIN_TOKEN_DEPLOYMENT_STARTED", "its-deploy"),
+                    message_id: self.message_id(&chain),
+                    destination_chain: self.other_chain(),
+                    token: InterchainTokenDefinition {
+                        id: token_id,
+                        name: self.text(),
+                        symbol,
+                        decimals: self.rng.random_range(0..=18),
+                    },
+                }
+            }
+            EventType::ITSTokenMetadataRegistered => Event::ITSTokenMetadataRegistered {
+                common: self.event_common("ITS_TOKEN_METADATA_REGISTERED", "its-metadata"),
+                message_id: self.message_id(&chain),
+                address: self.address(&chain),
+                decimals: self.rng.random_range(0..=18),
+            },
+            EventType::ITSLinkTokenStarted => {
+                let token_id = self.hash();
+                self.token_ids.push(token_id.clone());
+                let destination_chain = self.other_chain();
+                Event::ITSLinkTokenStarted {
+                    common: self.event_common("ITS_LINK_TOKEN_STARTED", "its-link"),
+                    message_id: self.message_id(&chain),
+                    token_id,
+                    source_token_address: self.address(&chain),
+                    destination_token_address: self.address(&destination_chain),
+                    destination_chain,
+                    token_manager_type: TOKEN_MANAGER_TYPES.choose(&mut self.rng).unwrap().clone(),
+                    params: None,
+                }
+            }
+            EventType::AppInterchainTransferSent => {
+                let destination_chain = self.other_chain();
+                Event::AppInterchainTransferSent {
+                    common: self.event_common("APP_INTERCHAIN_TRANSFER_SENT", "app-sent"),
+                    message_id: self.message_id(&chain),
+                    sender: self.address(&chain),
+                    recipient: self.address(&destination_chain),
+                    destination_chain,
+                    token_spent: self.amount(),
+                }
+            }
+            EventType::AppInterchainTransferReceived => {
+                let source_chain = self.other_chain();
+                Event::AppInterchainTransferReceived {
+                    common: self.event_common("APP_INTERCHAIN_TRANSFER_RECEIVED", "app-received"),
+                    message_id: self.message_id(&source_chain),
+                    sender: self.address(&source_chain),
+                    recipient: self.address(&chain),
+                    source_chain,
+                    token_received: self.amount(),
+                }
+            }
+            EventType::MessageApproved => {
+                let common = self.event_common("MESSAGE_APPROVED", "message-approved");
+                let source_chain = self.other_chain();
+                let (message, _) = self.message(&source_chain, Some(&chain));
+                Event::MessageApproved {
+                    common: CommonEventFields {
+                        r#type: common.r#type,
+                        event_id: common.event_id,
+                        meta: common.meta.map(|meta| MessageApprovedEventMetadata {
+                            command_id: Some(self.hash()),
+                            common_meta: meta,
+                        }),
+                    },
+                    message,
+                    cost: self.amount(),
+                }
+            }
+            EventType::SignersRotated => {
+                let common = self.event_common("SIGNERS_ROTATED", "signers-rotated");
+                Event::SignersRotated {
+                    common: CommonEventFields {
+                        r#type: common.r#type,
+                        event_id: common.event_id,
+                        meta: common.meta.map(|meta| SignersRotatedEventMetadata {
+                            signers_hash: Some(
+                                general_purpose::STANDARD.encode(self.rng.random::<[u8; 32]>()),
+                            ),
+                            epoch: Some(self.rng.random_range(1..10_000)),
+                            common_meta: meta,
+                        }),
+                    },
+                    message_id: self.message_id(&chain),
+                }
+            }
+        }
+    }
+
+    fn edge_case(&mut self) -> bool {
+        self.edge_cases && self.rng.random_bool(0.25)
+    }
+
+    /// Timestamps increase by a second per generated item
+    fn next_timestamp(&mut self) -> String {
+        self.generated += 1;
+        (self.start + Duration::seconds(self.generated))
+            .to_rfc3339_opts(SecondsFormat::Micros, true)
+    }
+
+    fn task_common(&mut self, r#type: &str) -> CommonTaskFields {
+        let meta = if self.edge_case() {
+            None
+        } else {
+            let scoped_messages = vec![ScopedMessage {
+                message_id: self.message_id(&self.chain.clone()),
+                source_chain: self.chain.clone(),
+            }];
+            Some(TaskMetadata {
+                tx_id: Some(self.hash()),
+                from_address: None,
+                finalized: None,
+                source_context: None,
+                scoped_messages: Some(scoped_messages),
+            })
+        };
+        CommonTaskFields {
+            id: self.uuid(),
+            chain: self.chain.clone(),
+            timestamp: self.next_timestamp(),
+            r#type: r#type.to_string(),
+            meta,
+        }
+    }
+
+    fn event_common(&mut self, r#type: &str, suffix: &str) -> CommonEventFields<EventMetadata> {
+        let tx_id = self.hash();
+        // relayers always send the timestamp of an event, but everything else may be missing
+        let meta = if self.edge_case() {
+            EventMetadata {
+                tx_id: None,
+                from_address: None,
+                finalized: None,
+                source_context: None,
+                timestamp: self.next_timestamp(),
+            }
+        } else {
+            let source_context = HashMap::from([("note".to_string(), self.text())]);
+            EventMetadata {
+                tx_id: Some(tx_id.clone()),
+                from_address: Some(self.address(&self.chain.clone())),
+                finalized: Some(true),
+                source_context: Some(source_context),
+                timestamp: self.next_timestamp(),
+            }
+        };
+        CommonEventFields {
+            r#type: r#type.to_string(),
+            event_id: format!("{}-{}", tx_id, suffix),
+            meta: Some(meta),
+        }
+    }
+
+    /// A message from `source_chain` with its base64 payload, whose hash is the message's
+    /// payload hash
+    fn message(
+        &mut self,
+        source_chain: &str,
+        destination_chain: Option<&str>,
+    ) -> (GatewayV2Message, String) {
+        let destination_chain = match destination_chain {
+            Some(chain) => chain.to_string(),
+            None => self.other_chain(),
+        };
+        let payload = self.payload_bytes();
+        let message = GatewayV2Message {
+            message_id: self.message_id(source_chain),
+            source_chain: source_chain.to_string(),
+            source_address: self.address(source_chain),
+            destination_address: self.address(&destination_chain),
+            payload_hash: hex::encode(Keccak256::digest(&payload)),
+        };
+        (message, general_purpose::STANDARD.encode(payload))
+    }
+
+    fn other_chain(&mut self) -> String {
+        let chains: Vec<&&str> = CHAINS.iter().filter(|c| **c != self.chain).collect();
+        chains.choose(&mut self.rng).unwrap().to_string()
+    }
+
+    /// XRPL messages are identified by their transaction hash, the others by transaction hash
+    /// and log index
+    fn message_id(&mut self, chain: &str) -> String {
+        let hash = self.hash();
+        if chain == "xrpl" {
+            hash
+        } else {
+            format!("{}-{}", hash, self.rng.random_range(0..1_000))
+        }
+    }
+
+    fn address(&mut self, chain: &str) -> String {
+        match chain {
+            "xrpl" => {
+                let len = self.rng.random_range(24..=33);
+                format!("r{}", self.chars(XRPL_ALPHABET, len))
+            }
+            "axelar" => format!("axelar1{}", self.chars(BECH32_ALPHABET, 58)),
+            _ => format!("0x{}", hex::encode(self.rng.random::<[u8; 20]>())),
+        }
+    }
+
+    fn chars(&mut self, alphabet: &[u8], len: usize) -> String {
+        (0..len)
+            .map(|_| *alphabet.choose(&mut self.rng).unwrap() as char)
+            .collect()
+    }
+
+    fn hash(&mut self) -> String {
+        format!("0x{}", hex::encode(self.rng.random::<[u8; 32]>()))
+    }
+
+    fn uuid(&mut self) -> String {
+        uuid::Builder::from_random_bytes(self.rng.random())
+            .into_uuid()
+            .to_string()
+    }
+
+    fn amount(&mut self) -> Amount {
+        let amount = if self.edge_case() {
+            ["0".to_string(), u128::MAX.to_string()]
+                .choose(&mut self.rng)
+                .unwrap()
+                .clone()
+        } else {
+            self.rng.random_range(1..1_000_000_000_000u64).to_string()
+        };
+        let token_id = self.rng.random_bool(0.3).then(|| self.hash());
+        Amount { token_id, amount }
+    }
+
+    fn payload_bytes(&mut self) -> Vec<u8> {
+        let len = if self.edge_case() {
+            *[0, MAX_PAYLOAD_SIZE].choose(&mut self.rng).unwrap()
+        } else {
+            self.rng.random_range(0..=TYPICAL_PAYLOAD_SIZE)
+        };
+        let mut payload = vec![0u8; len];
+        self.rng.fill(&mut payload[..]);
+        payload
+    }
+
+    fn text(&mut self) -> String {
+        if self.edge_case() {
+            UNICODE_TEXT.to_string()
+        } else {
+            format!("generated {}", self.rng.random::<u32>())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::{EVENT_TYPES, Generator, TASK_KINDS};
+    use crate::event_handler::verified_call_payload;
+    use crate::gmp_types::Event;
+    use crate::utils::parse_task;
+    use crate::validation::validate_task;
+
+    #[test]
+    fn test_generated_items_parse_and_are_reproducible() {
+        let start = Utc::now();
+        let known_chains: Vec<String> = ["ethereum", "avalanche", "polygon", "xrpl", "xrpl-evm"]
+            .iter()
+            .map(|chain| chain.to_string())
+            .collect();
+        let mut generator = Generator::new("xrpl", 7, start).with_edge_cases(true);
+
+        for _ in 0..20 {
+            for kind in TASK_KINDS {
+                let task = generator.task_of(kind.clone());
+                let task_json = task.to_json().unwrap();
+                assert_eq!(parse_task(&task_json).unwrap(), task);
+                assert_eq!(task.kind(), kind);
+                validate_task(&task_json, "xrpl", &known_chains).unwrap();
+            }
+            for event_type in EVENT_TYPES {
+                let event = generator.event_of(event_type.clone());
+                let event_json = serde_json::to_value(&event).unwrap();
+                let parsed: Event = serde_json::from_value(event_json).unwrap();
+                assert_eq!(parsed.event_type(), event_type);
+                // CALL payloads always match their hash
+                verified_call_payload(&event).unwrap();
+            }
+        }
+
+        let items = |seed| {
+            let mut generator = Generator::new("xrpl", seed, start);
+            (0..10)
+                .map(|_| generator.task().to_json().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(items(1), items(1));
+        assert_ne!(items(1), items(2));
+    }
+}
@@ -302,6 +302,43 @@ pub enum TaskKind {
     Unknown,
 }
 
+impl TaskKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskKind::Verify => "VERIFY",
+            TaskKind::Execute => "EXECUTE",
+            TaskKind::GatewayTx => "GATEWAY_TX",
+            TaskKind::ConstructProof => "CONSTRUCT_PROOF",
+            TaskKind::ReactToWasmEvent => "REACT_TO_WASM_EVENT",
+            TaskKind::Refund => "REFUND",
+            TaskKind::ReactToExpiredSigningSession => "REACT_TO_EXPIRED_SIGNING_SESSION",
+            TaskKind::ReactToRetriablePoll => "REACT_TO_RETRIABLE_POLL",
+            TaskKind::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+impl std::str::FromStr for TaskKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            TaskKind::Verify,
+            TaskKind::Execute,
+            TaskKind::GatewayTx,
+            TaskKind::ConstructProof,
+            TaskKind::ReactToWasmEvent,
+            TaskKind::Refund,
+            TaskKind::ReactToExpiredSigningSession,
+            TaskKind::ReactToRetriablePoll,
+            TaskKind::Unknown,
+        ]
+        .into_iter()
+        .find(|kind| kind.as_str() == s)
+        .ok_or_else(|| anyhow::anyhow!("Unknown task type {}", s))
+    }
+}
+
 impl Task {
     pub fn id(&self) -> String {
         match self {
@@ -332,6 +369,15 @@ impl Task {
         }
     }
 
+    /// The task as the GMP API sends it, without the name of the variant
+    pub fn to_json(&self) -> Result<Value, serde_json::Error> {
+        let value = serde_json::to_value(self)?;
+        Ok(value
+            .as_object()
+            .and_then(|variant| variant.values().next().cloned())
+            .unwrap_or(value))
+    }
+
     pub fn common(&self) -> &CommonTaskFields {
         match self {
             Task::Execute(t) => &t.common,
@@ -392,6 +438,23 @@ pub struct MessageExecutedEventMetadata {
     pub revert_reason: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MessageApprovedEventMetadata {
+    #[serde(flatten)]
+    pub common_meta: EventMetadata,
+    #[serde(rename = "commandID")]
+    pub command_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SignersRotatedEventMetadata {
+    #[serde(flatten)]
+    pub common_meta: EventMetadata,
+    #[serde(rename = "signersHash")]
+    pub signers_hash: Option<String>,
+    pub epoch: Option<u64>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ScopedMessage {
     #[serde(rename = "messageID")]
@@ -407,6 +470,25 @@ pub enum CannotExecuteMessageReason {
     Error,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TokenManagerType {
+    NativeInterchainToken,
+    MintBurnFrom,
+    LockUnlock,
+    LockUnlockFee,
+    MintBurn,
+}
+
+/// The interchain token deployed by ITS_INTERCHAIN_TOKEN_DEPLOYMENT_STARTED
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InterchainTokenDefinition {
+    pub id: String,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum MessageExecutionStatus {
@@ -447,6 +529,48 @@ pub enum EventType {
     CannotExecuteMessageV2,
     #[sqlx(rename = "ITS_INTERCHAIN_TRANSFER")]
     ITSInterchainTransfer,
+    #[sqlx(rename = "MESSAGE_APPROVED")]
+    MessageApproved,
+    #[sqlx(rename = "SIGNERS_ROTATED")]
+    SignersRotated,
+    #[sqlx(rename = "CANNOT_EXECUTE_MESSAGE")]
+    CannotExecuteMessage,
+    #[sqlx(rename = "CANNOT_ROUTE_MESSAGE")]
+    CannotRouteMessage,
+    #[sqlx(rename = "ITS_INTERCHAIN_TOKEN_DEPLOYMENT_STARTED")]
+    ITSInterchainTokenDeploymentStarted,
+    #[sqlx(rename = "ITS_TOKEN_METADATA_REGISTERED")]
+    ITSTokenMetadataRegistered,
+    #[sqlx(rename = "ITS_LINK_TOKEN_STARTED")]
+    ITSLinkTokenStarted,
+    #[sqlx(rename = "APP_INTERCHAIN_TRANSFER_SENT")]
+    AppInterchainTransferSent,
+    #[sqlx(rename = "APP_INTERCHAIN_TRANSFER_RECEIVED")]
+    AppInterchainTransferReceived,
+}
+
+impl EventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::Call => "CALL",
+            EventType::GasRefunded => "GAS_REFUNDED",
+            EventType::GasCredit => "GAS_CREDIT",
+            EventType::MessageExecuted => "MESSAGE_EXECUTED",
+            EventType::CannotExecuteMessageV2 => "CANNOT_EXECUTE_MESSAGE_V2",
+            EventType::ITSInterchainTransfer => "ITS_INTERCHAIN_TRANSFER",
+            EventType::MessageApproved => "MESSAGE_APPROVED",
+            EventType::SignersRotated => "SIGNERS_ROTATED",
+            EventType::CannotExecuteMessage => "CANNOT_EXECUTE_MESSAGE",
+            EventType::CannotRouteMessage => "CANNOT_ROUTE_MESSAGE",
+            EventType::ITSInterchainTokenDeploymentStarted => {
+                "ITS_INTERCHAIN_TOKEN_DEPLOYMENT_STARTED"
+            }
+            EventType::ITSTokenMetadataRegistered => "ITS_TOKEN_METADATA_REGISTERED",
+            EventType::ITSLinkTokenStarted => "ITS_LINK_TOKEN_STARTED",
+            EventType::AppInterchainTransferSent => "APP_INTERCHAIN_TRANSFER_SENT",
+            EventType::AppInterchainTransferReceived => "APP_INTERCHAIN_TRANSFER_RECEIVED",
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -516,6 +640,102 @@ pub enum Event {
         #[serde(rename = "dataHash")]
         data_hash: String,
     },
+    ITSInterchainTokenDeploymentStarted {
+        #[serde(flatten)]
+        common: CommonEventFields<EventMetadata>,
+        #[serde(rename = "messageID")]
+        message_id: String,
+        #[serde(rename = "destinationChain")]
+        destination_chain: String,
+        token: InterchainTokenDefinition,
+    },
+    /// Registers the decimals of a token with the hub ahead of linking it
+    ITSTokenMetadataRegistered {
+        #[serde(flatten)]
+        common: CommonEventFields<EventMetadata>,
+        #[serde(rename = "messageID")]
+        message_id: String,
+        address: String,
+        decimals: u8,
+    },
+    ITSLinkTokenStarted {
+        #[serde(flatten)]
+        common: CommonEventFields<EventMetadata>,
+        #[serde(rename = "messageID")]
+        message_id: String,
+        #[serde(rename = "destinationChain")]
+        destination_chain: String,
+        #[serde(rename = "tokenID")]
+        token_id: String,
+        #[serde(rename = "sourceTokenAddress")]
+        source_token_address: String,
+        #[serde(rename = "destinationTokenAddress")]
+        destination_token_address: String,
+        #[serde(rename = "tokenManagerType")]
+        token_manager_type: TokenManagerType,
+        params: Option<String>,
+    },
+    /// Posted by the relayer of the source chain when an application sends tokens
+    AppInterchainTransferSent {
+        #[serde(flatten)]
+        common: CommonEventFields<EventMetadata>,
+        #[serde(rename = "messageID")]
+        message_id: String,
+        #[serde(rename = "destinationChain")]
+        destination_chain: String,
+        sender: String,
+        recipient: String,
+        #[serde(rename = "tokenSpent")]
+        token_spent: Amount,
+    },
+    /// Posted by the relayer of the destination chain when the application delivered the tokens
+    AppInterchainTransferReceived {
+        #[serde(flatten)]
+        common: CommonEventFields<EventMetadata>,
+        #[serde(rename = "messageID")]
+        message_id: String,
+        #[serde(rename = "sourceChain")]
+        source_chain: String,
+        sender: String,
+        recipient: String,
+        #[serde(rename = "tokenReceived")]
+        token_received: Amount,
+    },
+    /// Posted by the destination relayer once the gateway approved the message
+    MessageApproved {
+        #[serde(flatten)]
+        common: CommonEventFields<MessageApprovedEventMetadata>,
+        message: GatewayV2Message,
+        cost: Amount,
+    },
+    /// The v1 schema of CANNOT_EXECUTE_MESSAGE_V2, which refers to the EXECUTE task instead of
+    /// the message
+    CannotExecuteMessage {
+        #[serde(flatten)]
+        common: CommonEventFields<EventMetadata>,
+        #[serde(rename = "taskItemID")]
+        task_item_id: String,
+        reason: CannotExecuteMessageReason,
+        details: String,
+    },
+    /// Posted when Amplifier could not route the message to its destination chain. Has to come
+    /// after CANNOT_EXECUTE_MESSAGE_V2, which has the same fields and more.
+    CannotRouteMessage {
+        #[serde(flatten)]
+        common: CommonEventFields<EventMetadata>,
+        #[serde(rename = "messageID")]
+        message_id: String,
+        reason: String,
+        details: String,
+    },
+    /// Posted by the relayer once the gateway rotated to a new verifier set. Since it only has a
+    /// message ID it matches any event with one, so it has to stay the last variant.
+    SignersRotated {
+        #[serde(flatten)]
+        common: CommonEventFields<SignersRotatedEventMetadata>,
+        #[serde(rename = "messageID")]
+        message_id: String,
+    },
 }
 
 impl Event {
@@ -529,7 +749,14 @@ impl Event {
             | Event::GasRefunded { common, .. }
             | Event::GasCredit { common, .. }
             | Event::CannotExecuteMessageV2 { common, .. }
-            | Event::ITSInterchainTransfer { common, .. } => {
+            | Event::CannotExecuteMessage { common, .. }
+            | Event::CannotRouteMessage { common, .. }
+            | Event::ITSInterchainTransfer { common, .. }
+            | Event::ITSInterchainTokenDeploymentStarted { common, .. }
+            | Event::ITSTokenMetadataRegistered { common, .. }
+            | Event::ITSLinkTokenStarted { common, .. }
+            | Event::AppInterchainTransferSent { common, .. }
+            | Event::AppInterchainTransferReceived { common, .. } => {
                 let ts = unwrap_ts(&common.meta, |m| &m.timestamp);
                 (&common.event_id, &common.r#type, ts)
             }
@@ -537,9 +764,18 @@ impl Event {
                 let ts = unwrap_ts(&common.meta, |m| &m.common_meta.timestamp);
                 (&common.event_id, &common.r#type, ts)
             }
+            Event::MessageApproved { common, .. } => {
+                let ts = unwrap_ts(&common.meta, |m| &m.common_meta.timestamp);
+                (&common.event_id, &common.r#type, ts)
+            }
+            Event::SignersRotated { common, .. } => {
+                let ts = unwrap_ts(&common.meta, |m| &m.common_meta.timestamp);
+                (&common.event_id, &common.r#type, ts)
+            }
         }
     }
 
+    /// The ID of the message of the event; CANNOT_EXECUTE_MESSAGE only has the ID of its task
     pub fn message_id(&self) -> String {
         match self {
             Event::Call { message, .. } => message.message_id.clone(),
@@ -548,6 +784,15 @@ impl Event {
             Event::MessageExecuted { message_id, .. } => message_id.clone(),
             Event::CannotExecuteMessageV2 { message_id, .. } => message_id.clone(),
             Event::ITSInterchainTransfer { message_id, .. } => message_id.clone(),
+            Event::MessageApproved { message, .. } => message.message_id.clone(),
+            Event::SignersRotated { message_id, .. } => message_id.clone(),
+            Event::CannotExecuteMessage { task_item_id, .. } => task_item_id.clone(),
+            Event::CannotRouteMessage { message_id, .. } => message_id.clone(),
+            Event::ITSInterchainTokenDeploymentStarted { message_id, .. } => message_id.clone(),
+            Event::ITSTokenMetadataRegistered { message_id, .. } => message_id.clone(),
+            Event::ITSLinkTokenStarted { message_id, .. } => message_id.clone(),
+            Event::AppInterchainTransferSent { message_id, .. } => message_id.clone(),
+            Event::AppInterchainTransferReceived { message_id, .. } => message_id.clone(),
         }
     }
 
@@ -559,6 +804,17 @@ impl Event {
             Event::MessageExecuted { .. } => EventType::MessageExecuted,
             Event::CannotExecuteMessageV2 { .. } => EventType::CannotExecuteMessageV2,
             Event::ITSInterchainTransfer { .. } => EventType::ITSInterchainTransfer,
+            Event::MessageApproved { .. } => EventType::MessageApproved,
+            Event::SignersRotated { .. } => EventType::SignersRotated,
+            Event::CannotExecuteMessage { .. } => EventType::CannotExecuteMessage,
+            Event::CannotRouteMessage { .. } => EventType::CannotRouteMessage,
+            Event::ITSInterchainTokenDeploymentStarted { .. } => {
+                EventType::ITSInterchainTokenDeploymentStarted
+            }
+            Event::ITSTokenMetadataRegistered { .. } => EventType::ITSTokenMetadataRegistered,
+            Event::ITSLinkTokenStarted { .. } => EventType::ITSLinkTokenStarted,
+            Event::AppInterchainTransferSent { .. } => EventType::AppInterchainTransferSent,
+            Event::AppInterchainTransferReceived { .. } => EventType::AppInterchainTransferReceived,
         }
     }
 }
@@ -576,11 +832,6 @@ pub struct PostEventResponse {
     pub results: Vec<PostEventResult>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
-struct EventMessage {
-    pub events: Vec<Event>,
-}
-
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum BroadcastRequest {
     Generic(Value),
@@ -598,7 +849,12 @@ pub struct StorePayloadResult {
 
 #[cfg(test)]
 mod tests {
-    use super::{ReactToExpiredSigningSessionTask, ReactToRetriablePollTask};
+    use serde_json::Value;
+
+    use super::{
+        Event, EventType, ReactToExpiredSigningSessionTask, ReactToRetriablePollTask,
+        TokenManagerType,
+    };
 
     #[test]
     fn test_react_to_expired_signing_session_task() {
@@ -693,4 +949,102 @@ mod tests {
             task.split_whitespace().collect::<String>()
         );
     }
+
+    #[test]
+    fn test_approval_and_rotation_events() {
+        for (file, event_type) in [
+            ("MessageApprovedEvent.json", EventType::MessageApproved),
+            ("SignersRotatedEvent.json", EventType::SignersRotated),
+        ] {
+            let events_json = std::fs::read_to_string(format!("testdata/events/{}", file)).unwrap();
+            let events: Vec<Event> = serde_json::from_str(&events_json).unwrap();
+            for event in events {
+                assert_eq!(event.event_type(), event_type);
+                assert_eq!(event.common_fields().1, event_type.as_str());
+            }
+        }
+
+        // a GAS_CREDIT without its payment still parses, but not as a GAS_CREDIT
+        let event: Event = serde_json::from_value(serde_json::json!({
+            "type": "GAS_CREDIT",
+            "eventID": "0xabc-gas",
+            "meta": null,
+            "messageID": "0xabc",
+            "refundAddress": "rAddress"
+        }))
+        .unwrap();
+        assert_ne!(event.event_type().as_str(), event.common_fields().1);
+    }
+
+    #[test]
+    fn test_cannot_route_and_legacy_cannot_execute_events() {
+        for (file, event_type, message_id) in [
+            (
+                "CannotExecuteMessageEvent.json",
+                EventType::CannotExecuteMessage,
+                "0197f4a1-3c2e-7a10-9d2b-5e8c1f0a6b34",
+            ),
+            (
+                "CannotRouteMessageEvent.json",
+                EventType::CannotRouteMessage,
+                "0xe168dcf7f0e7ce7c4676a71ee21abd2e8a78a5c6ac49706cc99a884d2000de54",
+            ),
+        ] {
+            let events_json = std::fs::read_to_string(format!("testdata/events/{}", file)).unwrap();
+            let events: Vec<Event> = serde_json::from_str(&events_json).unwrap();
+            assert_eq!(events[0].event_type(), event_type);
+            assert_eq!(events[0].common_fields().1, event_type.as_str());
+            assert_eq!(events[0].message_id(), message_id);
+        }
+    }
+
+    #[test]
+    fn test_its_token_lifecycle_events() {
+        let events_json =
+            std::fs::read_to_string("testdata/events/ITSTokenLifecycleEvents.json").unwrap();
+        let events: Vec<Event> = serde_json::from_str(&events_json).unwrap();
+        let event_types: Vec<EventType> = events.iter().map(Event::event_type).collect();
+        assert_eq!(
+            event_types,
+            vec![
+                EventType::ITSInterchainTokenDeploymentStarted,
+                EventType::ITSTokenMetadataRegistered,
+                EventType::ITSLinkTokenStarted,
+            ]
+        );
+        for event in &events {
+            assert_eq!(event.common_fields().1, event.event_type().as_str());
+        }
+
+        let Event::ITSLinkTokenStarted {
+            token_manager_type, ..
+        } = &events[2]
+        else {
+            panic!("Not an ITS_LINK_TOKEN_STARTED event");
+        };
+        assert_eq!(token_manager_type, &TokenManagerType::LockUnlock);
+        assert_eq!(
+            serde_json::to_value(&events[2]).unwrap(),
+            serde_json::from_str::<Value>(&events_json).unwrap()[2]
+        );
+    }
+
+    #[test]
+    fn test_app_interchain_transfer_events() {
+        let events_json =
+            std::fs::read_to_string("testdata/events/AppInterchainTransferEvents.json").unwrap();
+        let events: Vec<Event> = serde_json::from_str(&events_json).unwrap();
+        let event_types: Vec<EventType> = events.iter().map(Event::event_type).collect();
+        assert_eq!(
+            event_types,
+            vec![
+                EventType::AppInterchainTransferSent,
+                EventType::AppInterchainTransferReceived,
+            ]
+        );
+        for event in &events {
+            assert_eq!(event.common_fields().1, event.event_type().as_str());
+        }
+        assert_eq!(events[0].message_id(), events[1].message_id());
+    }
 }
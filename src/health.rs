@@ -0,0 +1,187 @@
+use std::{sync::Arc, time::Duration};
+
+use actix_web::{App, HttpResponse, HttpServer, dev::Server, get, web};
+use serde::Serialize;
+
+use crate::{TasksModel, metrics::metrics, queue::QueueTrait};
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Debug)]
+pub struct HealthCheck {
+    pub name: &'static str,
+    pub healthy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct HealthReport {
+    pub status: &'static str,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    pub fn is_healthy(&self) -> bool {
+        self.checks.iter().all(|check| check.healthy)
+    }
+}
+
+/// Dependencies that need to be reachable for a binary to do its job
+#[derive(Clone)]
+pub struct Readiness {
+    tasks_model: TasksModel,
+    queue: Arc<dyn QueueTrait>,
+    rpc: String,
+    http_client: reqwest::Client,
+}
+
+impl Readiness {
+    pub fn new(tasks_model: TasksModel, queue: Arc<dyn QueueTrait>, rpc: String) -> Self {
+        Self {
+            tasks_model,
+            queue,
+            rpc: rpc.trim_end_matches('/').to_string(),
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn report(&self) -> HealthReport {
+        let (postgres, queue, rpc, axelard) = tokio::join!(
+            self.check_postgres(),
+            self.check_queue(),
+            self.check_rpc(),
+            check_axelard()
+        );
+
+        let checks = vec![
+            health_check("postgres", postgres),
+            health_check("queue", queue),
+            health_check("rpc", rpc),
+            health_check("axelard", axelard),
+        ];
+        let status = if checks.iter().all(|check| check.healthy) {
+            "ok"
+        } else {
+            "unavailable"
+        };
+
+        HealthReport { status, checks }
+    }
+
+    async fn check_postgres(&self) -> Result<(), anyhow::Error> {
+        tokio::time::timeout(CHECK_TIMEOUT, self.tasks_model.ping())
+            .await
+            .map_err(|_| anyhow::anyhow!("Timed out"))?
+    }
+
+    async fn check_queue(&self) -> Result<(), anyhow::Error> {
+        tokio::time::timeout(CHECK_TIMEOUT, self.queue.check())
+            .await
+            .map_err(|_| anyhow::anyhow!("Timed out"))?
+    }
+
+    async fn check_rpc(&self) -> Result<(), anyhow::Error> {
+        let response = self
+            .http_client
+            .get(format!("{}/status", self.rpc))
+            .timeout(CHECK_TIMEOUT)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("RPC answered with {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+async fn check_axelard() -> Result<(), anyhow::Error> {
+    let output = tokio::time::timeout(
+        CHECK_TIMEOUT,
+        tokio::process::Command::new("axelard")
+            .arg("version")
+            .output(),
+    )
+    .await
+    .map_err(|_| anyhow::anyhow!("Timed out"))?
+    .map_err(|e| anyhow::anyhow!("Failed to run axelard: {}", e))?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "axelard version failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(())
+}
+
+fn health_check(name: &'static str, result: Result<(), anyhow::Error>) -> HealthCheck {
+    HealthCheck {
+        name,
+        healthy: result.is_ok(),
+        error: result.err().map(|e| e.to_string()),
+    }
+}
+
+/// Liveness: the process is up and serving requests
+#[get("/healthz")]
+pub async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({ "status": "ok" }))
+}
+
+/// Readiness: every dependency is reachable
+#[get("/readyz")]
+pub async fn readyz(readiness: web::Data<Readiness>) -> HttpResponse {
+    let report = readiness.report().await;
+    if report.is_healthy() {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
+
+/// Serve only the health and metrics endpoints, for binaries without an API of their own. Signals are left
+/// to the caller, which stops the returned server once it is done draining.
+pub fn serve(address: &str, port: u16, readiness: Readiness) -> anyhow::Result<Server> {
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(web::Data::from(readiness.queue.clone()))
+            .app_data(web::Data::new(readiness.clone()))
+            .service(healthz)
+            .service(readyz)
+            .service(metrics)
+    })
+    .workers(1)
+    .disable_signals()
+    .bind((address, port))?
+    .run();
+
+    Ok(server)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HealthReport, health_check};
+
+    #[test]
+    fn test_report_is_unhealthy_if_any_check_fails() {
+        let report = HealthReport {
+            status: "unavailable",
+            checks: vec![
+                health_check("postgres", Ok(())),
+                health_check(
+                    "queue",
+                    Err(anyhow::anyhow!("AMQP channel is not connected")),
+                ),
+            ],
+        };
+
+        assert!(!report.is_healthy());
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json["checks"][0],
+            serde_json::json!({"name": "postgres", "healthy": true})
+        );
+        assert_eq!(json["checks"][1]["error"], "AMQP channel is not connected");
+    }
+}
@@ -0,0 +1,358 @@
+use std::{collections::HashMap, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+use crate::{
+    config::IndexerConfig,
+    models::{
+        chains::ChainsModel,
+        wasm_events::{IndexedEvent, WasmEventsModel},
+    },
+    rules::{Rules, VERIFY_MESSAGES},
+};
+
+/// Name of the checkpoint of the indexer in the database
+pub const CHECKPOINT: &str = "wasm_events";
+const PER_PAGE: u64 = 100;
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Walks the chain height by height with the `tx_search` endpoint of the CometBFT RPC and stores
+/// the wasm events of the registered contracts, and the follow-up events of the rules, e.g. the
+/// signing_completed events of the multisig contract, in the database. The last processed height is
+/// persisted along with the events so that a restarted indexer continues where it stopped.
+pub struct Indexer {
+    rpc: String,
+    http_client: reqwest::Client,
+    chains: ChainsModel,
+    events: WasmEventsModel,
+    rules: Rules,
+    start_height: u64,
+    batch_size: u64,
+    poll_interval: Duration,
+}
+
+impl Indexer {
+    pub fn new(
+        rpc: &str,
+        chains: ChainsModel,
+        events: WasmEventsModel,
+        rules: Rules,
+        config: &IndexerConfig,
+    ) -> Self {
+        Self {
+            rpc: rpc.trim_end_matches('/').to_string(),
+            http_client: reqwest::Client::new(),
+            chains,
+            events,
+            rules,
+            start_height: config.start_height,
+            batch_size: config.batch_size,
+            poll_interval: Duration::from_secs(config.poll_interval),
+        }
+    }
+
+    /// Run the indexer in the background until `shutdown` turns true
+    pub fn spawn(self, shutdown: watch::Receiver<bool>) {
+        tokio::spawn(async move { self.run(shutdown).await });
+    }
+
+    /// Index new blocks until `shutdown` turns true
+    pub async fn run(&self, mut shutdown: watch::Receiver<bool>) {
+        loop {
+            let delay = match self.index_next_batch().await {
+                Ok(true) => self.poll_interval,
+                Ok(false) => Duration::ZERO,
+                Err(e) => {
+                    warn!("Indexing failed: {}, retrying in {:?}", e, RETRY_DELAY);
+                    RETRY_DELAY
+                }
+            };
+            tokio::select! {
+                _ = shutdown.wait_for(|stop| *stop) => return,
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
+    }
+
+    /// Index the blocks after the checkpoint, up to `batch_size` of them. Returns whether the
+    /// indexer has caught up with the latest block.
+    async fn index_next_batch(&self) -> Result<bool, anyhow::Error> {
+        let latest = self.latest_height().await?;
+        let from = match self.events.checkpoint(CHECKPOINT).await? {
+            Some(height) => height as u64 + 1,
+            None if self.start_height > 0 => self.start_height,
+            None => latest,
+        };
+        if from > latest {
+            return Ok(true);
+        }
+        let to = latest.min(from + self.batch_size - 1);
+
+        let events = self.search(from, to).await?;
+        self.events.store(CHECKPOINT, &events, to as i64).await?;
+        info!(
+            "Indexed {} wasm events from heights {} to {}",
+            events.len(),
+            from,
+            to
+        );
+
+        Ok(to == latest)
+    }
+
+    async fn search(&self, from: u64, to: u64) -> Result<Vec<IndexedEvent>, anyhow::Error> {
+        let mut events = Vec::new();
+        let mut block_times = HashMap::new();
+
+        for condition in self.conditions().await? {
+            let query = format!(
+                "{} AND tx.height>={} AND tx.height<={}",
+                condition, from, to
+            );
+            for tx in self.tx_search(&query).await? {
+                let height = as_u64(tx.get("height"))
+                    .ok_or_else(|| anyhow::anyhow!("Transaction without height"))?;
+                let block_time = match block_times.get(&height) {
+                    Some(block_time) => *block_time,
+                    None => {
+                        let block_time = self.block_time(height).await?;
+                        block_times.insert(height, block_time);
+                        block_time
+                    }
+                };
+                events.extend(wasm_events(&tx, block_time, &self.rules));
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// One condition per registered contract, plus the follow-up events of the rules, which may be
+    /// emitted by contracts that are not in the registry such as the multisig contract
+    async fn conditions(&self) -> Result<Vec<String>, anyhow::Error> {
+        let mut conditions: Vec<String> = self
+            .chains
+            .get_chains()
+            .await?
+            .into_iter()
+            .flat_map(|chain| {
+                [
+                    chain.contracts.gateway,
+                    chain.contracts.voting_verifier,
+                    chain.contracts.multisig_prover,
+                ]
+            })
+            .map(|address| format!("wasm._contract_address='{}'", address))
+            .collect();
+        // the quorums of the polls are emitted by the registered voting verifiers
+        conditions.extend(
+            self.rules
+                .iter()
+                .filter(|rule| rule.name != VERIFY_MESSAGES)
+                .map(|rule| {
+                    format!(
+                        "{}.{} EXISTS",
+                        rule.follow_up_event, rule.correlation_attribute
+                    )
+                }),
+        );
+        conditions.sort();
+        conditions.dedup();
+        Ok(conditions)
+    }
+
+    async fn tx_search(&self, query: &str) -> Result<Vec<Value>, anyhow::Error> {
+        let mut txs = Vec::new();
+        for page in 1.. {
+            let result = self
+                .rpc_call(
+                    "tx_search",
+                    &[
+                        ("query", format!("\"{}\"", query)),
+                        ("page", page.to_string()),
+                        ("per_page", PER_PAGE.to_string()),
+                        ("order_by", "\"asc\"".to_string()),
+                    ],
+                )
+                .await?;
+            let total_count = as_u64(result.get("total_count")).unwrap_or(0);
+            let page_txs = result
+                .get("txs")
+                .and_then(|v| v.as_array())
+                .cloned()
+                .unwrap_or_default();
+            debug!(
+                "{} transactions on page {} of '{}'",
+                page_txs.len(),
+                page,
+                query
+            );
+
+            let done = page_txs.is_empty() || page * PER_PAGE >= total_count;
+            txs.extend(page_txs);
+            if done {
+                break;
+            }
+        }
+        Ok(txs)
+    }
+
+    async fn latest_height(&self) -> Result<u64, anyhow::Error> {
+        let result = self.rpc_call("status", &[]).await?;
+        as_u64(
+            result
+                .get("sync_info")
+                .and_then(|v| v.get("latest_block_height")),
+        )
+        .ok_or_else(|| anyhow::anyhow!("Latest block height not found in status"))
+    }
+
+    async fn block_time(&self, height: u64) -> Result<DateTime<Utc>, anyhow::Error> {
+        let result = self
+            .rpc_call("header", &[("height", height.to_string())])
+            .await?;
+        let time = result
+            .get("header")
+            .and_then(|v| v.get("time"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Time of block {} not found", height))?;
+        Ok(DateTime::parse_from_rfc3339(time)?.into())
+    }
+
+    async fn rpc_call(
+        &self,
+        method: &str,
+        params: &[(&str, String)],
+    ) -> Result<Value, anyhow::Error> {
+        let response: Value = self
+            .http_client
+            .get(format!("{}/{}", self.rpc, method))
+            .query(params)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow::anyhow!("RPC {} failed: {}", method, error));
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("RPC {} returned no result", method))
+    }
+}
+
+/// The wasm events of a `tx_search` result, i.e. the `wasm` and `wasm-*` events. Follow-up events
+/// of a rule are keyed by its correlation attribute.
+fn wasm_events(tx: &Value, block_time: DateTime<Utc>, rules: &Rules) -> Vec<IndexedEvent> {
+    let (Some(tx_hash), Some(height)) = (
+        tx.get("hash").and_then(|v| v.as_str()),
+        as_u64(tx.get("height")),
+    ) else {
+        return Vec::new();
+    };
+    let Some(events) = tx
+        .get("tx_result")
+        .and_then(|v| v.get("events"))
+        .and_then(|v| v.as_array())
+    else {
+        return Vec::new();
+    };
+
+    events
+        .iter()
+        .enumerate()
+        .filter_map(|(index, event)| {
+            let event_type = event.get("type")?.as_str()?;
+            if event_type != "wasm" && !event_type.starts_with("wasm-") {
+                return None;
+            }
+            let attributes = event.get("attributes")?.as_array()?;
+            let attribute = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|attr| attr.get("key").and_then(|v| v.as_str()) == Some(name))
+                    .and_then(|attr| attr.get("value").and_then(|v| v.as_str()))
+                    .map(|value| value.to_string())
+            };
+            // keep the key and value only, like `axelard query txs` does
+            let attributes = attributes
+                .iter()
+                .map(|attr| {
+                    serde_json::json!({
+                        "key": attr.get("key"),
+                        "value": attr.get("value"),
+                    })
+                })
+                .collect();
+
+            Some(IndexedEvent {
+                tx_hash: tx_hash.to_string(),
+                event_index: index as i32,
+                height: height as i64,
+                event_type: event_type.to_string(),
+                contract_address: attribute("_contract_address")?,
+                event_id: rules
+                    .by_follow_up(event_type)
+                    .and_then(|rule| attribute(&rule.correlation_attribute)),
+                attributes: Value::Array(attributes),
+                block_time,
+            })
+        })
+        .collect()
+}
+
+/// CometBFT encodes 64-bit integers as strings
+fn as_u64(value: Option<&Value>) -> Option<u64> {
+    match value? {
+        Value::String(value) => value.parse().ok(),
+        value => value.as_u64(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::wasm_events;
+    use crate::rules::Rules;
+
+    #[test]
+    fn test_wasm_events_of_a_transaction() {
+        let tx = serde_json::json!({
+            "hash": "ABCD",
+            "height": "1234",
+            "index": 0,
+            "tx_result": {
+                "code": 0,
+                "events": [
+                    {"type": "message", "attributes": [{"key": "sender", "value": "axelar1sender", "index": true}]},
+                    {"type": "wasm", "attributes": [{"key": "_contract_address", "value": "axelar1verifier", "index": true}]},
+                    {"type": "wasm-quorum_reached", "attributes": [
+                        {"key": "_contract_address", "value": "axelar1verifier", "index": true},
+                        {"key": "poll_id", "value": "\"42\"", "index": true}
+                    ]}
+                ]
+            }
+        });
+
+        let events = wasm_events(&tx, Utc::now(), &Rules::default());
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_type, "wasm");
+        assert_eq!(events[0].event_id, None);
+
+        let quorum_reached = &events[1];
+        assert_eq!(quorum_reached.event_index, 2);
+        assert_eq!(quorum_reached.height, 1234);
+        assert_eq!(quorum_reached.contract_address, "axelar1verifier");
+        assert_eq!(quorum_reached.event_id.as_deref(), Some("\"42\""));
+        assert_eq!(
+            quorum_reached.attributes[1],
+            serde_json::json!({"key": "poll_id", "value": "\"42\""})
+        );
+    }
+}
@@ -1,13 +1,24 @@
 pub mod client;
+pub mod config;
 pub mod event_handler;
+pub mod generator;
 pub mod gmp_types;
+pub mod health;
+pub mod indexer;
+pub mod logging;
+pub mod metrics;
 pub mod models;
 pub mod queue;
+pub mod rules;
 pub mod server;
 pub mod subscriber;
+pub mod telemetry;
 pub mod utils;
+pub mod validation;
+pub mod watcher;
 
 pub use client::Client;
+pub use config::Config;
 pub use models::tasks::TasksModel;
 pub use queue::LapinConnection;
 pub use server::Server;
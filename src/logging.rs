@@ -0,0 +1,128 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::Serialize;
+use serde_json::Value;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{Layer, Registry, fmt, fmt::MakeWriter, prelude::*, registry::LookupSpan};
+
+use crate::config::{LogFormat, LoggingConfig};
+
+/// Fields replaced by their size in [`loggable`]: payloads and proofs are large and may be sensitive
+const REDACTED_FIELDS: [&str; 3] = ["payload", "execute_data", "executeData"];
+
+static MAX_FIELD_LENGTH: AtomicUsize = AtomicUsize::new(1024);
+
+/// Flushes the log file writer when dropped, keep it alive until the process exits
+pub struct LogGuard(#[allow(dead_code)] Option<WorkerGuard>);
+
+/// Log output to stdout and, if configured, to daily rotated files, filtered by the configured
+/// level and per-module directives
+pub fn log_layer<S>(
+    config: &LoggingConfig,
+    file_prefix: &str,
+) -> Result<(Box<dyn Layer<S> + Send + Sync>, LogGuard), anyhow::Error>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+{
+    MAX_FIELD_LENGTH.store(config.max_field_length, Ordering::Relaxed);
+
+    let mut layers = vec![fmt_layer(config.format, std::io::stdout, true)];
+    let guard = if config.directory.is_empty() {
+        None
+    } else {
+        let appender = tracing_appender::rolling::daily(&config.directory, file_prefix);
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+        layers.push(fmt_layer(config.format, writer, false));
+        Some(guard)
+    };
+
+    Ok((
+        layers.with_filter(config.env_filter()?).boxed(),
+        LogGuard(guard),
+    ))
+}
+
+fn fmt_layer<S, W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let layer = fmt::layer()
+        .with_target(true)
+        .with_ansi(ansi)
+        .with_writer(writer);
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
+
+pub fn setup_logging(config: &LoggingConfig) -> Result<LogGuard, anyhow::Error> {
+    let (layer, guard) = log_layer(config, "mock-gmp-api")?;
+    let gmp_api = Registry::default().with(layer);
+
+    tracing::subscriber::set_global_default(gmp_api)
+        .expect("Failed to set global tracing subscriber");
+    Ok(guard)
+}
+
+/// Cut `text` to the configured `logging.max_field_length`
+pub fn truncate(text: &str) -> String {
+    let max_length = MAX_FIELD_LENGTH.load(Ordering::Relaxed);
+    match text.char_indices().nth(max_length) {
+        Some((end, _)) => format!("{}... ({} bytes truncated)", &text[..end], text.len() - end),
+        None => text.to_string(),
+    }
+}
+
+/// JSON of `value` for logging, with payloads redacted and the result truncated
+pub fn loggable<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(mut json) => {
+            redact(&mut json);
+            truncate(&json.to_string())
+        }
+        Err(e) => format!("<not serializable: {}>", e),
+    }
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value {
+                    Value::String(s) if REDACTED_FIELDS.contains(&key.as_str()) => {
+                        *value = Value::String(format!("<redacted {} bytes>", s.len()));
+                    }
+                    _ => redact(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{loggable, truncate};
+
+    #[test]
+    fn test_payloads_are_redacted_and_output_truncated() {
+        let event = serde_json::json!({
+            "eventID": "0x1",
+            "payload": "aGVsbG8=",
+            "message": {"messageID": "0x1-0"}
+        });
+        assert_eq!(
+            loggable(&event),
+            r#"{"eventID":"0x1","message":{"messageID":"0x1-0"},"payload":"<redacted 8 bytes>"}"#
+        );
+
+        let long = "a".repeat(1030);
+        assert_eq!(
+            truncate(&long),
+            format!("{}... (6 bytes truncated)", "a".repeat(1024))
+        );
+    }
+}
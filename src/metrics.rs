@@ -0,0 +1,269 @@
+use std::time::Instant;
+
+use actix_web::{
+    Error, HttpResponse,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    get,
+    middleware::Next,
+    web,
+};
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use tracing::warn;
+
+use crate::{
+    gmp_types::{EventType, TaskKind},
+    queue::QueueTrait,
+};
+
+pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+/// Where a task was created
+pub enum TaskSource {
+    Api,
+    EventHandler,
+    Subscriber,
+    Generator,
+}
+
+impl TaskSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskSource::Api => "api",
+            TaskSource::EventHandler => "event_handler",
+            TaskSource::Subscriber => "subscriber",
+            TaskSource::Generator => "generator",
+        }
+    }
+}
+
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    tasks_created: IntCounterVec,
+    events: IntCounterVec,
+    broadcasts: IntCounterVec,
+    axelard_call_duration: HistogramVec,
+    queue_depth: IntGauge,
+    queue_retries: IntCounterVec,
+    subscriber_jobs: IntCounterVec,
+    subscriber_wait: HistogramVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("mock_gmp_api".to_string()), None)
+            .expect("Failed to create metrics registry");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests per route and chain"),
+            &["method", "route", "chain", "status"],
+        )
+        .unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency per route and chain",
+            ),
+            &["method", "route", "chain"],
+        )
+        .unwrap();
+        let tasks_created = IntCounterVec::new(
+            Opts::new("tasks_created_total", "Tasks stored per kind and source"),
+            &["kind", "source"],
+        )
+        .unwrap();
+        let events = IntCounterVec::new(
+            Opts::new("events_total", "Posted events per type and result"),
+            &["event_type", "status"],
+        )
+        .unwrap();
+        let broadcasts = IntCounterVec::new(
+            Opts::new("broadcasts_total", "Broadcasts per outcome"),
+            &["outcome"],
+        )
+        .unwrap();
+        let axelard_call_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "axelard_call_duration_seconds",
+                "Duration of the axelard commands",
+            )
+            .buckets(vec![0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0]),
+            &["command"],
+        )
+        .unwrap();
+        let queue_depth = IntGauge::new("queue_depth", "Items waiting in the queue").unwrap();
+        let queue_retries = IntCounterVec::new(
+            Opts::new(
+                "queue_retries_total",
+                "Queue items requeued after a failure",
+            ),
+            &["item"],
+        )
+        .unwrap();
+        let subscriber_jobs = IntCounterVec::new(
+            Opts::new("subscriber_jobs_total", "Queue items handled per outcome"),
+            &["item", "outcome"],
+        )
+        .unwrap();
+        let subscriber_wait = HistogramVec::new(
+            HistogramOpts::new(
+                "subscriber_wait_seconds",
+                "Time from the broadcast to the quorum_reached or signing_completed event",
+            )
+            .buckets(vec![1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0]),
+            &["event"],
+        )
+        .unwrap();
+
+        for collector in [
+            Box::new(http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_request_duration.clone()),
+            Box::new(tasks_created.clone()),
+            Box::new(events.clone()),
+            Box::new(broadcasts.clone()),
+            Box::new(axelard_call_duration.clone()),
+            Box::new(queue_depth.clone()),
+            Box::new(queue_retries.clone()),
+            Box::new(subscriber_jobs.clone()),
+            Box::new(subscriber_wait.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("Failed to register metric");
+        }
+
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            tasks_created,
+            events,
+            broadcasts,
+            axelard_call_duration,
+            queue_depth,
+            queue_retries,
+            subscriber_jobs,
+            subscriber_wait,
+        }
+    }
+
+    pub fn task_created(&self, kind: &TaskKind, source: TaskSource) {
+        self.tasks_created
+            .with_label_values(&[kind.as_str(), source.as_str()])
+            .inc();
+    }
+
+    pub fn event_posted(&self, event_type: &EventType, status: &str) {
+        self.events
+            .with_label_values(&[event_type.as_str(), status])
+            .inc();
+    }
+
+    pub fn broadcast_finished(&self, outcome: &str) {
+        self.broadcasts.with_label_values(&[outcome]).inc();
+    }
+
+    /// Record how long an axelard command took, `command` being e.g. "tx_execute"
+    pub fn axelard_call(&self, command: &str, started_at: Instant) {
+        self.axelard_call_duration
+            .with_label_values(&[command])
+            .observe(started_at.elapsed().as_secs_f64());
+    }
+
+    pub fn queue_retry(&self, item: &str) {
+        self.queue_retries.with_label_values(&[item]).inc();
+    }
+
+    pub fn subscriber_job(&self, item: &str, outcome: &str) {
+        self.subscriber_jobs
+            .with_label_values(&[item, outcome])
+            .inc();
+    }
+
+    /// Record the time between a broadcast and the event the subscriber waited for
+    pub fn subscriber_wait(&self, event: &str, seconds: f64) {
+        self.subscriber_wait
+            .with_label_values(&[event])
+            .observe(seconds.max(0.0));
+    }
+
+    pub fn encode(&self) -> Result<String, anyhow::Error> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+/// Count requests and their latency, labelled by the matched route pattern (not the raw path,
+/// which would create a series per task ID) and the chain in the path if any
+pub async fn track_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let started_at = Instant::now();
+    let method = req.method().to_string();
+
+    let response = next.call(req).await?;
+
+    let request = response.request();
+    let route = request
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+    let chain = request.match_info().get("chain").unwrap_or("").to_string();
+    let status = response.status().as_u16().to_string();
+
+    METRICS
+        .http_requests
+        .with_label_values(&[&method, &route, &chain, &status])
+        .inc();
+    METRICS
+        .http_request_duration
+        .with_label_values(&[&method, &route, &chain])
+        .observe(started_at.elapsed().as_secs_f64());
+
+    Ok(response)
+}
+
+#[get("/metrics")]
+pub async fn metrics(queue: web::Data<dyn QueueTrait>) -> HttpResponse {
+    match queue.message_count().await {
+        Ok(count) => METRICS.queue_depth.set(count as i64),
+        Err(e) => warn!("Failed to get queue depth: {}", e),
+    }
+
+    match METRICS.encode() {
+        Ok(body) => HttpResponse::Ok()
+            .content_type(prometheus::TEXT_FORMAT)
+            .body(body),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gmp_types::{EventType, TaskKind};
+
+    use super::{METRICS, TaskSource};
+
+    #[test]
+    fn test_metrics_are_encoded() {
+        METRICS.task_created(&TaskKind::Verify, TaskSource::EventHandler);
+        METRICS.event_posted(&EventType::GasCredit, "REJECTED");
+
+        let body = METRICS.encode().unwrap();
+
+        assert!(body.contains(
+            "mock_gmp_api_tasks_created_total{kind=\"VERIFY\",source=\"event_handler\"}"
+        ));
+        assert!(
+            body.contains(
+                "mock_gmp_api_events_total{event_type=\"GAS_CREDIT\",status=\"REJECTED\"}"
+            )
+        );
+    }
+}
@@ -93,6 +93,20 @@ impl BroadcastsModel {
         Ok(result)
     }
 
+    /// Contract address and raw JSON of a broadcast, as sent to the contract
+    pub async fn find_request(&self, id: &str) -> Result<Option<(String, String)>, anyhow::Error> {
+        let query = format!(
+            "SELECT contract_address, broadcast FROM {} WHERE id = $1",
+            PG_TABLE_NAME
+        );
+        let row = sqlx::query(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| (row.get("contract_address"), row.get("broadcast"))))
+    }
+
     pub async fn insert(
         &self,
         id: &str,
@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row, postgres::PgRow};
+
+const PG_TABLE_NAME: &str = "chains";
+
+/// The Amplifier contracts of a chain integration
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainContracts {
+    pub gateway: String,
+    // the aliases allow the snake case keys of the config file
+    #[serde(rename = "votingVerifier", alias = "voting_verifier")]
+    pub voting_verifier: String,
+    #[serde(rename = "multisigProver", alias = "multisig_prover")]
+    pub multisig_prover: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Chain {
+    pub name: String,
+    #[serde(flatten)]
+    pub contracts: ChainContracts,
+}
+
+impl Chain {
+    /// Whether the address is one of the contracts of this chain
+    pub fn has_contract(&self, address: &str) -> bool {
+        self.contracts.gateway == address
+            || self.contracts.voting_verifier == address
+            || self.contracts.multisig_prover == address
+    }
+
+    fn from_row(row: &PgRow) -> Self {
+        Self {
+            name: row.get("name"),
+            contracts: ChainContracts {
+                gateway: row.get("gateway"),
+                voting_verifier: row.get("voting_verifier"),
+                multisig_prover: row.get("multisig_prover"),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ChainsModel {
+    pool: PgPool,
+}
+
+impl ChainsModel {
+    pub async fn new(url: &str) -> Result<Self, anyhow::Error> {
+        let pool = PgPool::connect(url).await?;
+        Ok(Self { pool })
+    }
+
+    pub async fn find(&self, name: &str) -> Result<Option<Chain>, anyhow::Error> {
+        let query = format!(
+            "SELECT name, gateway, voting_verifier, multisig_prover FROM {} WHERE name = $1",
+            PG_TABLE_NAME
+        );
+        let row = sqlx::query(&query)
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(Chain::from_row))
+    }
+
+    /// Find the chain that one of the given contracts belongs to
+    pub async fn find_by_contract(&self, address: &str) -> Result<Option<Chain>, anyhow::Error> {
+        let query = format!(
+            "SELECT name, gateway, voting_verifier, multisig_prover FROM {} WHERE gateway = $1 OR voting_verifier = $1 OR multisig_prover = $1",
+            PG_TABLE_NAME
+        );
+        let row = sqlx::query(&query)
+            .bind(address)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(Chain::from_row))
+    }
+
+    pub async fn get_chains(&self) -> Result<Vec<Chain>, anyhow::Error> {
+        let query = format!(
+            "SELECT name, gateway, voting_verifier, multisig_prover FROM {} ORDER BY name",
+            PG_TABLE_NAME
+        );
+        let rows = sqlx::query(&query).fetch_all(&self.pool).await?;
+
+        Ok(rows.iter().map(Chain::from_row).collect())
+    }
+
+    pub async fn upsert(
+        &self,
+        name: &str,
+        contracts: &ChainContracts,
+    ) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} (name, gateway, voting_verifier, multisig_prover) VALUES ($1, $2, $3, $4) ON CONFLICT (name) DO UPDATE SET gateway = $2, voting_verifier = $3, multisig_prover = $4, updated_at = NOW()",
+            PG_TABLE_NAME
+        );
+
+        sqlx::query(&query)
+            .bind(name)
+            .bind(&contracts.gateway)
+            .bind(&contracts.voting_verifier)
+            .bind(&contracts.multisig_prover)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, name: &str) -> Result<bool, anyhow::Error> {
+        let query = format!("DELETE FROM {} WHERE name = $1", PG_TABLE_NAME);
+        let result = sqlx::query(&query).bind(name).execute(&self.pool).await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testcontainers::{ContainerAsync, runners::AsyncRunner};
+    use testcontainers_modules::postgres;
+
+    use crate::models::chains::{ChainContracts, ChainsModel};
+
+    async fn setup_test_container() -> (ChainsModel, ContainerAsync<postgres::Postgres>) {
+        let container = postgres::Postgres::default()
+            .with_init_sql(
+                include_str!("../../migrations/0005_chains.sql")
+                    .to_string()
+                    .into_bytes(),
+            )
+            .start()
+            .await
+            .unwrap();
+        let connection_string = format!(
+            "postgres://postgres:postgres@{}:{}/postgres",
+            container.get_host().await.unwrap(),
+            container.get_host_port_ipv4(5432).await.unwrap()
+        );
+        let model = ChainsModel::new(&connection_string).await.unwrap();
+        // we need to return the container too otherwise it will be dropped and the test will run forever
+        (model, container)
+    }
+
+    #[tokio::test]
+    async fn test_upsert_and_find_chains() {
+        let (db, _container) = setup_test_container().await;
+
+        let xrpl = ChainContracts {
+            gateway: "axelar1gateway".to_string(),
+            voting_verifier: "axelar1verifier".to_string(),
+            multisig_prover: "axelar1prover".to_string(),
+        };
+        db.upsert("xrpl", &xrpl).await.unwrap();
+
+        let chain = db.find("xrpl").await.unwrap().unwrap();
+        assert_eq!(chain.contracts, xrpl);
+
+        let chain = db.find_by_contract("axelar1prover").await.unwrap().unwrap();
+        assert_eq!(chain.name, "xrpl");
+        assert!(
+            db.find_by_contract("axelar1unknown")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let updated = ChainContracts {
+            multisig_prover: "axelar1newprover".to_string(),
+            ..xrpl
+        };
+        db.upsert("xrpl", &updated).await.unwrap();
+        assert_eq!(db.get_chains().await.unwrap().len(), 1);
+        assert_eq!(db.find("xrpl").await.unwrap().unwrap().contracts, updated);
+
+        assert!(db.delete("xrpl").await.unwrap());
+        assert!(db.find("xrpl").await.unwrap().is_none());
+        assert!(!db.delete("xrpl").await.unwrap());
+    }
+}
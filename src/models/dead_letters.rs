@@ -0,0 +1,172 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{PgPool, Row, postgres::PgRow};
+
+use crate::queue::QueueItem;
+
+const PG_TABLE_NAME: &str = "dead_letters";
+
+/// A queue item that ran out of attempts
+#[derive(Clone, Debug, Serialize)]
+pub struct DeadLetter {
+    pub id: String,
+    pub item: QueueItem,
+    pub error: String,
+    pub attempts: i32,
+    #[serde(rename = "firstSeenAt")]
+    pub first_seen_at: DateTime<Utc>,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl DeadLetter {
+    fn from_row(row: &PgRow) -> Result<Self, anyhow::Error> {
+        let item_text: String = row.get("item");
+        Ok(Self {
+            id: row.get("id"),
+            item: serde_json::from_str(&item_text)?,
+            error: row.get("error"),
+            attempts: row.get("attempts"),
+            first_seen_at: row.get("first_seen_at"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DeadLettersModel {
+    pool: PgPool,
+}
+
+impl DeadLettersModel {
+    pub async fn new(url: &str) -> Result<Self, anyhow::Error> {
+        let pool = PgPool::connect(url).await?;
+        Ok(Self { pool })
+    }
+
+    pub async fn insert(
+        &self,
+        id: &str,
+        item: &QueueItem,
+        error: &str,
+    ) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} (id, item, error, attempts, first_seen_at) VALUES ($1, $2, $3, $4, $5)",
+            PG_TABLE_NAME
+        );
+
+        sqlx::query(&query)
+            .bind(id)
+            .bind(serde_json::to_string(item)?)
+            .bind(error)
+            .bind(item.retry().attempts as i32)
+            .bind(item.retry().first_seen_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn find(&self, id: &str) -> Result<Option<DeadLetter>, anyhow::Error> {
+        let query = format!(
+            "SELECT id, item, error, attempts, first_seen_at, created_at FROM {} WHERE id = $1",
+            PG_TABLE_NAME
+        );
+        let row = sqlx::query(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(DeadLetter::from_row).transpose()
+    }
+
+    pub async fn get_dead_letters(&self) -> Result<Vec<DeadLetter>, anyhow::Error> {
+        let query = format!(
+            "SELECT id, item, error, attempts, first_seen_at, created_at FROM {} ORDER BY created_at ASC",
+            PG_TABLE_NAME
+        );
+        let rows = sqlx::query(&query).fetch_all(&self.pool).await?;
+
+        rows.iter().map(DeadLetter::from_row).collect()
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<bool, anyhow::Error> {
+        let query = format!("DELETE FROM {} WHERE id = $1", PG_TABLE_NAME);
+        let result = sqlx::query(&query).bind(id).execute(&self.pool).await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete every dead letter, returning how many there were
+    pub async fn purge(&self) -> Result<u64, anyhow::Error> {
+        let query = format!("DELETE FROM {}", PG_TABLE_NAME);
+        let result = sqlx::query(&query).execute(&self.pool).await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use testcontainers::{ContainerAsync, runners::AsyncRunner};
+    use testcontainers_modules::postgres;
+
+    use crate::{
+        models::dead_letters::DeadLettersModel,
+        queue::{ConstructProofItem, QueueItem, RetryInfo},
+    };
+
+    async fn setup_test_container() -> (DeadLettersModel, ContainerAsync<postgres::Postgres>) {
+        let container = postgres::Postgres::default()
+            .with_init_sql(
+                include_str!("../../migrations/0006_dead_letters.sql")
+                    .to_string()
+                    .into_bytes(),
+            )
+            .start()
+            .await
+            .unwrap();
+        let connection_string = format!(
+            "postgres://postgres:postgres@{}:{}/postgres",
+            container.get_host().await.unwrap(),
+            container.get_host_port_ipv4(5432).await.unwrap()
+        );
+        let model = DeadLettersModel::new(&connection_string).await.unwrap();
+        // we need to return the container too otherwise it will be dropped and the test will run forever
+        (model, container)
+    }
+
+    #[tokio::test]
+    async fn test_insert_find_and_purge_dead_letters() {
+        let (db, _container) = setup_test_container().await;
+
+        let item = QueueItem::ConstructProof(ConstructProofItem {
+            session_id: "22128".to_string(),
+            contract_address: "axelar1multisig".to_string(),
+            broadcast_created_at: Utc::now(),
+            chain: "xrpl".to_string(),
+            broadcast_id: None,
+            expires_at: None,
+            retry: RetryInfo {
+                attempts: 10,
+                first_seen_at: Utc::now(),
+            },
+        });
+        db.insert("1", &item, "No signing completed event found")
+            .await
+            .unwrap();
+        db.insert("2", &item, "No signing completed event found")
+            .await
+            .unwrap();
+
+        let dead_letter = db.find("1").await.unwrap().unwrap();
+        assert_eq!(dead_letter.attempts, 10);
+        assert_eq!(dead_letter.item.retry(), item.retry());
+
+        assert!(db.delete("1").await.unwrap());
+        assert!(!db.delete("1").await.unwrap());
+        assert_eq!(db.get_dead_letters().await.unwrap().len(), 1);
+        assert_eq!(db.purge().await.unwrap(), 1);
+    }
+}
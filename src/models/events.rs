@@ -117,6 +117,35 @@ impl EventsModel {
             .collect::<Vec<_>>())
     }
 
+    pub async fn get_events_by_message_id(
+        &self,
+        message_id: &str,
+    ) -> Result<Vec<serde_json::Value>, anyhow::Error> {
+        let query = format!(
+            "SELECT event FROM {} WHERE message_id = $1 ORDER BY timestamp ASC",
+            PG_TABLE_NAME
+        );
+        let rows = sqlx::query(&query)
+            .bind(message_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let event_text: String = row.get("event");
+
+                match serde_json::from_str(&event_text) {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        error!("Failed to parse event JSON: {:?}", e);
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<_>>())
+    }
+
     pub async fn find_by_message_id(
         &self,
         message_id: &str,
@@ -169,6 +198,24 @@ impl EventsModel {
 
         Ok(event)
     }
+
+    /// IDs of the messages whose CALL event carries the payload with the given keccak256 hash,
+    /// with or without the 0x prefix
+    pub async fn find_message_ids_by_payload_hash(
+        &self,
+        payload_hash: &str,
+    ) -> Result<Vec<String>, anyhow::Error> {
+        let query = format!(
+            "SELECT DISTINCT message_id FROM {} WHERE type = 'CALL' AND lower(regexp_replace(event::jsonb -> 'message' ->> 'payloadHash', '^0x', '')) = $1 ORDER BY message_id",
+            PG_TABLE_NAME
+        );
+        let rows = sqlx::query(&query)
+            .bind(payload_hash.trim_start_matches("0x").to_lowercase())
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get("message_id")).collect())
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,9 @@
 pub mod broadcasts;
+pub mod chains;
+pub mod dead_letters;
 pub mod events;
 pub mod payloads;
 pub mod tasks;
+pub mod tokens;
+pub mod transfers;
+pub mod wasm_events;
@@ -1,5 +1,23 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use sqlx::{PgPool, Row};
 
+const PG_TABLE_NAME: &str = "payloads";
+/// Only payloads without an expiry or expiring in the future are served
+const LIVE: &str = "(expires_at IS NULL OR expires_at > NOW())";
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PayloadMetadata {
+    pub keccak256: String,
+    pub size: i64,
+    #[serde(rename = "contentType")]
+    pub content_type: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Clone, Debug)]
 pub struct PayloadsModel {
     pool: PgPool,
@@ -11,37 +29,178 @@ impl PayloadsModel {
         Ok(Self { pool })
     }
 
-    pub async fn insert(&self, id: &str, payload: &str) -> Result<(), anyhow::Error> {
-        sqlx::query("INSERT INTO payloads (id, payload) VALUES ($1, $2)")
+    pub async fn insert(
+        &self,
+        id: &str,
+        payload: &[u8],
+        content_type: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} (id, payload, content_type, expires_at) VALUES ($1, $2, $3, $4)",
+            PG_TABLE_NAME
+        );
+        sqlx::query(&query)
             .bind(id)
             .bind(payload)
+            .bind(content_type)
+            .bind(expires_at)
             .execute(&self.pool)
             .await?;
         Ok(())
     }
 
-    pub async fn find(&self, id: &str) -> Result<Option<String>, anyhow::Error> {
-        let row = sqlx::query("SELECT payload FROM payloads WHERE id = $1")
+    pub async fn find(&self, id: &str) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        let query = format!(
+            "SELECT payload FROM {} WHERE id = $1 AND {}",
+            PG_TABLE_NAME, LIVE
+        );
+        let row = sqlx::query(&query)
             .bind(id)
             .fetch_optional(&self.pool)
             .await?;
         Ok(row.map(|row| row.get("payload")))
     }
 
-    pub async fn delete(&self, id: &str) -> Result<(), anyhow::Error> {
-        sqlx::query("DELETE FROM payloads WHERE id = $1")
+    /// Size, content type and timestamps of a payload, without reading it
+    pub async fn metadata(&self, id: &str) -> Result<Option<PayloadMetadata>, anyhow::Error> {
+        let query = format!(
+            "SELECT id, octet_length(payload)::BIGINT AS size, content_type, created_at, expires_at FROM {} WHERE id = $1 AND {}",
+            PG_TABLE_NAME, LIVE
+        );
+        let row = sqlx::query(&query)
             .bind(id)
-            .execute(&self.pool)
+            .fetch_optional(&self.pool)
             .await?;
+        Ok(row.map(|row| PayloadMetadata {
+            keccak256: row.get("id"),
+            size: row.get("size"),
+            content_type: row.get("content_type"),
+            created_at: row.get("created_at"),
+            expires_at: row.get("expires_at"),
+        }))
+    }
+
+    /// Up to `len` bytes of a payload starting at byte `offset`, for streaming large payloads
+    /// without loading them at once
+    pub async fn read_chunk(
+        &self,
+        id: &str,
+        offset: i64,
+        len: i64,
+    ) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        let query = format!(
+            "SELECT substring(payload FROM $2::BIGINT::INTEGER FOR $3::BIGINT::INTEGER) AS chunk FROM {} WHERE id = $1 AND {}",
+            PG_TABLE_NAME, LIVE
+        );
+        let row = sqlx::query(&query)
+            .bind(id)
+            // substring counts from 1
+            .bind(offset + 1)
+            .bind(len)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.map(|row| row.get("chunk")))
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<(), anyhow::Error> {
+        let query = format!("DELETE FROM {} WHERE id = $1", PG_TABLE_NAME);
+        sqlx::query(&query).bind(id).execute(&self.pool).await?;
         Ok(())
     }
 
-    pub async fn upsert(&self, id: &str, payload: &str) -> Result<(), anyhow::Error> {
-        sqlx::query("INSERT INTO payloads (id, payload) VALUES ($1, $2) ON CONFLICT (id) DO UPDATE SET payload = $2")
+    /// Store a payload, or refresh the content type and expiry of an identical one stored before
+    pub async fn upsert(
+        &self,
+        id: &str,
+        payload: &[u8],
+        content_type: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} (id, payload, content_type, expires_at) VALUES ($1, $2, $3, $4) ON CONFLICT (id) DO UPDATE SET payload = $2, content_type = $3, expires_at = $4",
+            PG_TABLE_NAME
+        );
+        sqlx::query(&query)
             .bind(id)
             .bind(payload)
+            .bind(content_type)
+            .bind(expires_at)
             .execute(&self.pool)
             .await?;
         Ok(())
     }
+
+    /// Delete the expired payloads, returning how many there were
+    pub async fn purge_expired(&self) -> Result<u64, anyhow::Error> {
+        let query = format!(
+            "DELETE FROM {} WHERE expires_at IS NOT NULL AND expires_at <= NOW()",
+            PG_TABLE_NAME
+        );
+        let result = sqlx::query(&query).execute(&self.pool).await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use testcontainers::{ContainerAsync, runners::AsyncRunner};
+    use testcontainers_modules::postgres;
+
+    use crate::models::payloads::PayloadsModel;
+
+    async fn setup_test_container() -> (PayloadsModel, ContainerAsync<postgres::Postgres>) {
+        let container = postgres::Postgres::default()
+            .with_init_sql(
+                [
+                    include_str!("../../migrations/0004_payloads.sql"),
+                    include_str!("../../migrations/0009_payloads_binary.sql"),
+                ]
+                .join("\n")
+                .into_bytes(),
+            )
+            .start()
+            .await
+            .unwrap();
+        let connection_string = format!(
+            "postgres://postgres:postgres@{}:{}/postgres",
+            container.get_host().await.unwrap(),
+            container.get_host_port_ipv4(5432).await.unwrap()
+        );
+        let model = PayloadsModel::new(&connection_string).await.unwrap();
+        // we need to return the container too otherwise it will be dropped and the test will run forever
+        (model, container)
+    }
+
+    #[tokio::test]
+    async fn test_store_read_and_expire_payloads() {
+        let (db, _container) = setup_test_container().await;
+        let payload = [0u8, 1, 2, 254, 255];
+
+        db.upsert("0xabc", &payload, "application/octet-stream", None)
+            .await
+            .unwrap();
+        assert_eq!(db.find("0xabc").await.unwrap(), Some(payload.to_vec()));
+        let metadata = db.metadata("0xabc").await.unwrap().unwrap();
+        assert_eq!(metadata.size, 5);
+        assert_eq!(metadata.expires_at, None);
+        assert_eq!(
+            db.read_chunk("0xabc", 3, 4).await.unwrap(),
+            Some(vec![254, 255])
+        );
+
+        db.insert(
+            "0xdef",
+            &payload,
+            "application/octet-stream",
+            Some(Utc::now() - Duration::seconds(1)),
+        )
+        .await
+        .unwrap();
+        assert!(db.find("0xdef").await.unwrap().is_none());
+        assert!(db.metadata("0xdef").await.unwrap().is_none());
+        assert_eq!(db.purge_expired().await.unwrap(), 1);
+        assert_eq!(db.purge_expired().await.unwrap(), 0);
+    }
 }
@@ -3,11 +3,103 @@ use crate::{
     utils::parse_task,
 };
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json;
-use sqlx::{PgPool, Row};
+use sqlx::{PgPool, Row, postgres::PgRow};
 use tracing::error;
 
 const PG_TABLE_NAME: &str = "tasks";
+/// Status of a task computed from its lease columns
+const STATUS: &str = "CASE WHEN completed_at IS NOT NULL THEN 'completed' WHEN claimed_until IS NULL THEN 'pending' WHEN claimed_until > NOW() THEN 'claimed' ELSE 'expired' END";
+
+/// Condition on a task referencing the message ID bound to `param`, either directly in
+/// `task.message` or in `meta.scopedMessages`
+fn references_message(param: &str) -> String {
+    format!(
+        "(task -> 'task' -> 'message' ->> 'messageID' = {param} \
+        OR task -> 'meta' -> 'scopedMessages' @> jsonb_build_array(jsonb_build_object('messageID', {param}::TEXT)))"
+    )
+}
+
+/// Optional filters of [`TasksModel::get_tasks`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TaskFilter {
+    pub r#type: Option<TaskKind>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub message_id: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Where a task is in its lease: never claimed, claimed by a consumer whose lease runs, acked, or
+/// claimed without an ack before the lease ran out, in which case it is handed out again
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Pending,
+    Claimed,
+    Completed,
+    Expired,
+}
+
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Pending => "pending",
+            TaskStatus::Claimed => "claimed",
+            TaskStatus::Completed => "completed",
+            TaskStatus::Expired => "expired",
+        }
+    }
+
+    fn from_str(status: &str) -> Result<Self, anyhow::Error> {
+        match status {
+            "pending" => Ok(TaskStatus::Pending),
+            "claimed" => Ok(TaskStatus::Claimed),
+            "completed" => Ok(TaskStatus::Completed),
+            "expired" => Ok(TaskStatus::Expired),
+            _ => Err(anyhow::anyhow!("Unknown task status {}", status)),
+        }
+    }
+}
+
+/// The lease of a task, without the task itself
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TaskState {
+    pub id: String,
+    pub chain: String,
+    pub r#type: String,
+    pub timestamp: DateTime<Utc>,
+    pub status: TaskStatus,
+    #[serde(rename = "claimedBy")]
+    pub claimed_by: Option<String>,
+    #[serde(rename = "claimedUntil")]
+    pub claimed_until: Option<DateTime<Utc>>,
+    pub claims: i32,
+    #[serde(rename = "completedBy")]
+    pub completed_by: Option<String>,
+    #[serde(rename = "completedAt")]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl TaskState {
+    fn from_row(row: &PgRow) -> Result<Self, anyhow::Error> {
+        let status: String = row.get("status");
+        Ok(Self {
+            id: row.get("id"),
+            chain: row.get("chain"),
+            r#type: row.get("type"),
+            timestamp: row.get("timestamp"),
+            status: TaskStatus::from_str(&status)?,
+            claimed_by: row.get("claimed_by"),
+            claimed_until: row.get("claimed_until"),
+            claims: row.get("claims"),
+            completed_by: row.get("completed_by"),
+            completed_at: row.get("completed_at"),
+        })
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct TasksModel {
@@ -20,34 +112,49 @@ impl TasksModel {
         Ok(Self { pool })
     }
 
+    /// Check that the database answers queries
+    pub async fn ping(&self) -> Result<(), anyhow::Error> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
     pub async fn find(&self, id: &str) -> Result<Option<Task>, anyhow::Error> {
-        let query = format!("SELECT task FROM {} WHERE id = $1", PG_TABLE_NAME);
+        let task = self
+            .find_json(id)
+            .await?
+            .and_then(|task_json| match parse_task(&task_json) {
+                Ok(task) => Some(task),
+                Err(e) => {
+                    error!("Failed to parse task: {:?}", e);
+                    None
+                }
+            });
+
+        Ok(task)
+    }
+
+    /// The task as it was stored
+    pub async fn find_json(&self, id: &str) -> Result<Option<serde_json::Value>, anyhow::Error> {
+        let query = format!(
+            "SELECT task::TEXT AS task FROM {} WHERE id = $1",
+            PG_TABLE_NAME
+        );
         let row = sqlx::query(&query)
             .bind(id)
             .fetch_optional(&self.pool)
             .await?;
 
-        let task = row.and_then(|row| {
+        Ok(row.and_then(|row| {
             let task_text: String = row.get("task");
 
-            let task_json: serde_json::Value = match serde_json::from_str(&task_text) {
-                Ok(value) => value,
+            match serde_json::from_str(&task_text) {
+                Ok(value) => Some(value),
                 Err(e) => {
                     error!("Failed to parse task JSON: {:?}", e);
-                    return None;
-                }
-            };
-
-            match parse_task(&task_json) {
-                Ok(task) => Some(task),
-                Err(e) => {
-                    error!("Failed to parse task: {:?}", e);
                     None
                 }
             }
-        });
-
-        Ok(task)
+        }))
     }
 
     pub async fn upsert(
@@ -59,7 +166,7 @@ impl TasksModel {
         task: Option<&str>,
     ) -> Result<(), anyhow::Error> {
         let query = format!(
-            "INSERT INTO {} (id, chain, timestamp, type, task) VALUES ($1, $2, $3, $4, $5) ON CONFLICT (id) DO UPDATE SET chain = $2, timestamp = $3, type = $4, task = $5 RETURNING *",
+            "INSERT INTO {} (id, chain, timestamp, type, task) VALUES ($1, $2, $3, $4, $5::jsonb) ON CONFLICT (id) DO UPDATE SET chain = $2, timestamp = $3, type = $4, task = $5::jsonb RETURNING *",
             PG_TABLE_NAME
         );
 
@@ -82,10 +189,12 @@ impl TasksModel {
         Ok(())
     }
 
+    /// Tasks of a chain after the task with ID `after`, oldest first
     pub async fn get_tasks(
         &self,
         chain: &str,
         after: Option<&str>,
+        filter: &TaskFilter,
     ) -> Result<Vec<serde_json::Value>, anyhow::Error> {
         let after_task = match after {
             Some(id) => self.find(id).await?,
@@ -96,12 +205,146 @@ impl TasksModel {
             None => "1970-01-01T00:00:00Z".to_string(),
         };
         let query = format!(
-            "SELECT task FROM {} WHERE chain = $1 AND timestamp > $2",
-            PG_TABLE_NAME
+            "SELECT task::TEXT AS task FROM {} WHERE chain = $1 AND timestamp > $2 \
+            AND ($3::task_type IS NULL OR type = $3) \
+            AND ($4::TIMESTAMPTZ IS NULL OR timestamp >= $4) AND ($5::TIMESTAMPTZ IS NULL OR timestamp <= $5) \
+            AND ($6::TEXT IS NULL OR {}) \
+            ORDER BY timestamp ASC, id ASC LIMIT $7 OFFSET COALESCE($8, 0)",
+            PG_TABLE_NAME,
+            references_message("$6")
         );
         let rows = sqlx::query(&query)
             .bind(chain)
             .bind(after_task_timestamp.parse::<DateTime<Utc>>().unwrap())
+            .bind(filter.r#type.clone())
+            .bind(filter.from)
+            .bind(filter.to)
+            .bind(filter.message_id.as_deref())
+            .bind(filter.limit)
+            .bind(filter.offset)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let task_text: String = row.get("task");
+
+                match serde_json::from_str(&task_text) {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        error!("Failed to parse task JSON: {:?}", e);
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<_>>())
+    }
+
+    /// Lease up to `limit` of the oldest tasks of a chain that are pending or whose lease expired to
+    /// `consumer` for `visibility_timeout` seconds. Tasks leased to another consumer are skipped,
+    /// so that replicas polling concurrently never get the same task.
+    pub async fn claim(
+        &self,
+        chain: &str,
+        consumer: &str,
+        visibility_timeout: u64,
+        limit: i64,
+    ) -> Result<Vec<serde_json::Value>, anyhow::Error> {
+        let query = format!(
+            "UPDATE {table} SET claimed_by = $2, claimed_until = NOW() + make_interval(secs => $3), claims = claims + 1 \
+            WHERE id IN (SELECT id FROM {table} WHERE chain = $1 AND completed_at IS NULL AND (claimed_until IS NULL OR claimed_until <= NOW()) \
+            ORDER BY timestamp ASC LIMIT $4 FOR UPDATE SKIP LOCKED) \
+            RETURNING task::TEXT AS task, timestamp",
+            table = PG_TABLE_NAME
+        );
+        let mut rows = sqlx::query(&query)
+            .bind(chain)
+            .bind(consumer)
+            .bind(visibility_timeout as f64)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+        rows.sort_by_key(|row| row.get::<DateTime<Utc>, _>("timestamp"));
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let task_text: String = row.get("task");
+
+                match serde_json::from_str(&task_text) {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        error!("Failed to parse task JSON: {:?}", e);
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<_>>())
+    }
+
+    /// Mark a task leased to `consumer` as completed. Returns false if the task does not exist or
+    /// is leased to someone else, e.g. because the lease of `consumer` expired and it was claimed
+    /// again.
+    pub async fn ack(&self, id: &str, consumer: &str) -> Result<bool, anyhow::Error> {
+        let query = format!(
+            "UPDATE {} SET completed_by = COALESCE(completed_by, $2), completed_at = COALESCE(completed_at, NOW()) WHERE id = $1 AND claimed_by = $2",
+            PG_TABLE_NAME
+        );
+        let result = sqlx::query(&query)
+            .bind(id)
+            .bind(consumer)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn find_state(&self, id: &str) -> Result<Option<TaskState>, anyhow::Error> {
+        let query = format!(
+            "SELECT id, chain, type::TEXT AS type, timestamp, {} AS status, claimed_by, claimed_until, claims, completed_by, completed_at FROM {} WHERE id = $1",
+            STATUS, PG_TABLE_NAME
+        );
+        let row = sqlx::query(&query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(TaskState::from_row).transpose()
+    }
+
+    /// Lease states of the tasks, optionally only the ones of a chain or in a status
+    pub async fn get_task_states(
+        &self,
+        chain: Option<&str>,
+        status: Option<TaskStatus>,
+    ) -> Result<Vec<TaskState>, anyhow::Error> {
+        let query = format!(
+            "SELECT * FROM (SELECT id, chain, type::TEXT AS type, timestamp, {} AS status, claimed_by, claimed_until, claims, completed_by, completed_at FROM {}) states \
+            WHERE ($1::TEXT IS NULL OR chain = $1) AND ($2::TEXT IS NULL OR status = $2) ORDER BY timestamp ASC",
+            STATUS, PG_TABLE_NAME
+        );
+        let rows = sqlx::query(&query)
+            .bind(chain)
+            .bind(status.map(|status| status.as_str()))
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(TaskState::from_row).collect()
+    }
+
+    /// Tasks that reference a message, either directly in `task.message` or in `meta.scopedMessages`
+    pub async fn get_tasks_by_message_id(
+        &self,
+        message_id: &str,
+    ) -> Result<Vec<serde_json::Value>, anyhow::Error> {
+        let query = format!(
+            "SELECT task::TEXT AS task FROM {} WHERE {} ORDER BY timestamp ASC",
+            PG_TABLE_NAME,
+            references_message("$1")
+        );
+        let rows = sqlx::query(&query)
+            .bind(message_id)
             .fetch_all(&self.pool)
             .await?;
 
@@ -131,16 +374,20 @@ mod tests {
 
     use crate::{
         gmp_types::{ExecuteTask, GatewayTxTask, Task, TaskKind, VerifyTask},
-        models::tasks::TasksModel,
+        models::tasks::{TaskFilter, TaskStatus, TasksModel},
         utils::parse_task,
     };
 
     async fn setup_test_container() -> (TasksModel, ContainerAsync<postgres::Postgres>) {
         let container = postgres::Postgres::default()
             .with_init_sql(
-                include_str!("../../migrations/0001_tasks.sql")
-                    .to_string()
-                    .into_bytes(),
+                [
+                    include_str!("../../migrations/0001_tasks.sql"),
+                    include_str!("../../migrations/0010_task_leases.sql"),
+                    include_str!("../../migrations/0011_tasks_jsonb.sql"),
+                ]
+                .join("\n")
+                .into_bytes(),
             )
             .start()
             .await
@@ -266,7 +513,10 @@ mod tests {
             expected_tasks.push(Task::GatewayTx(valid_gateway_tx_task));
         }
 
-        let raw_tasks = db.get_tasks("xrpl", None).await.unwrap();
+        let raw_tasks = db
+            .get_tasks("xrpl", None, &TaskFilter::default())
+            .await
+            .unwrap();
         assert_eq!(raw_tasks.len(), expected_tasks.len());
 
         let parsed_tasks: Vec<Task> = raw_tasks
@@ -366,7 +616,11 @@ mod tests {
 
         // Get tasks after second task
         let tasks = db
-            .get_tasks("xrpl", Some("0197a679-9cf6-785c-8666-a2cf0c84c985"))
+            .get_tasks(
+                "xrpl",
+                Some("0197a679-9cf6-785c-8666-a2cf0c84c985"),
+                &TaskFilter::default(),
+            )
             .await
             .unwrap();
 
@@ -374,17 +628,177 @@ mod tests {
         assert_eq!(tasks.len(), 1);
 
         let tasks = db
-            .get_tasks("xrpl", Some("0197a679-9cf6-785c-8666-a2cf0c84c984"))
+            .get_tasks(
+                "xrpl",
+                Some("0197a679-9cf6-785c-8666-a2cf0c84c984"),
+                &TaskFilter::default(),
+            )
             .await
             .unwrap();
 
         assert_eq!(tasks.len(), 2);
 
         let tasks = db
-            .get_tasks("xrpl", Some("0197a679-9cf6-785c-8666-a2cf0c84c986"))
+            .get_tasks(
+                "xrpl",
+                Some("0197a679-9cf6-785c-8666-a2cf0c84c986"),
+                &TaskFilter::default(),
+            )
             .await
             .unwrap();
 
         assert_eq!(tasks.len(), 0);
     }
+
+    #[tokio::test]
+    async fn test_claim_and_ack_tasks() {
+        let (db, _container) = setup_test_container().await;
+        for (id, timestamp) in [
+            ("task1", "2024-01-01T00:00:00Z"),
+            ("task2", "2024-02-01T00:00:00Z"),
+        ] {
+            let task_json = serde_json::json!({
+                "id": id,
+                "chain": "xrpl",
+                "timestamp": timestamp,
+                "type": "GATEWAY_TX",
+                "meta": null,
+                "task": {
+                    "executeData": "data"
+                }
+            });
+            db.upsert(
+                id,
+                "xrpl",
+                timestamp.parse::<DateTime<Utc>>().unwrap(),
+                TaskKind::GatewayTx,
+                Some(&serde_json::to_string(&task_json).unwrap()),
+            )
+            .await
+            .unwrap();
+        }
+
+        let claimed = db.claim("xrpl", "relayer-a", 60, 1).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0]["id"], "task1");
+        // a second replica only gets what is left
+        let claimed = db.claim("xrpl", "relayer-b", 60, 10).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0]["id"], "task2");
+        assert!(
+            db.claim("xrpl", "relayer-b", 60, 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        assert!(!db.ack("task1", "relayer-b").await.unwrap());
+        assert!(db.ack("task1", "relayer-a").await.unwrap());
+        let state = db.find_state("task1").await.unwrap().unwrap();
+        assert_eq!(state.status, TaskStatus::Completed);
+        assert_eq!(state.completed_by.as_deref(), Some("relayer-a"));
+
+        // task2 is never acked, so it is handed out again once its lease expires
+        db.claim("xrpl", "relayer-a", 60, 10).await.unwrap();
+        let query =
+            "UPDATE tasks SET claimed_until = NOW() - INTERVAL '1 second' WHERE id = 'task2'";
+        sqlx::query(query).execute(&db.pool).await.unwrap();
+        let expired = db
+            .get_task_states(Some("xrpl"), Some(TaskStatus::Expired))
+            .await
+            .unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, "task2");
+        let claimed = db.claim("xrpl", "relayer-a", 60, 10).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(db.find_state("task2").await.unwrap().unwrap().claims, 2);
+    }
+
+    #[tokio::test]
+    async fn test_filter_tasks() {
+        let (db, _container) = setup_test_container().await;
+        for (id, kind, timestamp, task) in [
+            (
+                "verify1",
+                TaskKind::Verify,
+                "2024-01-01T00:00:00Z",
+                serde_json::json!({"message": {"messageID": "0xabc-1"}}),
+            ),
+            (
+                "gateway_tx1",
+                TaskKind::GatewayTx,
+                "2024-02-01T00:00:00Z",
+                serde_json::json!({"executeData": "data"}),
+            ),
+            (
+                "gateway_tx2",
+                TaskKind::GatewayTx,
+                "2024-03-01T00:00:00Z",
+                serde_json::json!({"executeData": "data"}),
+            ),
+        ] {
+            let meta = match id {
+                "gateway_tx1" => {
+                    serde_json::json!({"scopedMessages": [{"messageID": "0xabc-1", "sourceChain": "axelar"}]})
+                }
+                _ => serde_json::Value::Null,
+            };
+            let task_json = serde_json::json!({
+                "id": id,
+                "chain": "xrpl",
+                "timestamp": timestamp,
+                "type": kind.as_str(),
+                "meta": meta,
+                "task": task
+            });
+            db.upsert(
+                id,
+                "xrpl",
+                timestamp.parse::<DateTime<Utc>>().unwrap(),
+                kind,
+                Some(&serde_json::to_string(&task_json).unwrap()),
+            )
+            .await
+            .unwrap();
+        }
+
+        let ids = |tasks: Vec<serde_json::Value>| {
+            tasks
+                .iter()
+                .map(|task| task["id"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        let filter = TaskFilter {
+            r#type: Some(TaskKind::GatewayTx),
+            message_id: Some("0xabc-1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(db.get_tasks("xrpl", None, &filter).await.unwrap()),
+            vec!["gateway_tx1"]
+        );
+        let filter = TaskFilter {
+            message_id: Some("0xabc-1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(db.get_tasks("xrpl", None, &filter).await.unwrap()),
+            vec!["verify1", "gateway_tx1"]
+        );
+        let filter = TaskFilter {
+            from: Some("2024-02-01T00:00:00Z".parse().unwrap()),
+            to: Some("2024-03-01T00:00:00Z".parse().unwrap()),
+            limit: Some(1),
+            offset: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(db.get_tasks("xrpl", None, &filter).await.unwrap()),
+            vec!["gateway_tx2"]
+        );
+        assert_eq!(
+            db.find_json("verify1").await.unwrap().unwrap()["type"],
+            "VERIFY"
+        );
+    }
 }
@@ -0,0 +1,276 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row, postgres::PgRow};
+
+const PG_TABLE_NAME: &str = "tokens";
+const PG_METADATA_TABLE_NAME: &str = "token_metadata";
+
+/// The same interchain token on another chain
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LinkedToken {
+    pub chain: String,
+    pub address: Option<String>,
+}
+
+/// An interchain token on one chain. Fields that are unknown, e.g. the address of a token whose
+/// deployment is still in flight, are null.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Token {
+    #[serde(rename = "tokenID")]
+    pub token_id: String,
+    pub chain: String,
+    pub address: Option<String>,
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub decimals: Option<i32>,
+    pub deployer: Option<String>,
+    #[serde(rename = "linkedTokens", default)]
+    pub linked_tokens: Vec<LinkedToken>,
+}
+
+impl Token {
+    /// A token of which nothing but its ID and chain is known
+    pub fn new(token_id: &str, chain: &str) -> Self {
+        Self {
+            token_id: token_id.to_string(),
+            chain: chain.to_string(),
+            address: None,
+            name: None,
+            symbol: None,
+            decimals: None,
+            deployer: None,
+            linked_tokens: vec![],
+        }
+    }
+
+    fn from_row(row: &PgRow) -> Result<Self, anyhow::Error> {
+        let linked_tokens: String = row.get("linked_tokens");
+        Ok(Self {
+            token_id: row.get("token_id"),
+            chain: row.get("chain"),
+            address: row.get("address"),
+            name: row.get("name"),
+            symbol: row.get("symbol"),
+            decimals: row.get("decimals"),
+            deployer: row.get("deployer"),
+            linked_tokens: serde_json::from_str(&linked_tokens)?,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TokensModel {
+    pool: PgPool,
+}
+
+impl TokensModel {
+    pub async fn new(url: &str) -> Result<Self, anyhow::Error> {
+        let pool = PgPool::connect(url).await?;
+        Ok(Self { pool })
+    }
+
+    pub async fn find(&self, token_id: &str, chain: &str) -> Result<Option<Token>, anyhow::Error> {
+        let query = format!(
+            "SELECT token_id, chain, address, name, symbol, decimals, deployer, linked_tokens::TEXT AS linked_tokens FROM {} WHERE token_id = $1 AND chain = $2",
+            PG_TABLE_NAME
+        );
+        let row = sqlx::query(&query)
+            .bind(token_id)
+            .bind(chain)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(Token::from_row).transpose()
+    }
+
+    /// Tokens of all chains, optionally only those with the given ID or on the given chain
+    pub async fn get_tokens(
+        &self,
+        token_id: Option<&str>,
+        chain: Option<&str>,
+    ) -> Result<Vec<Token>, anyhow::Error> {
+        let query = format!(
+            "SELECT token_id, chain, address, name, symbol, decimals, deployer, linked_tokens::TEXT AS linked_tokens FROM {} WHERE ($1::TEXT IS NULL OR token_id = $1) AND ($2::TEXT IS NULL OR chain = $2) ORDER BY token_id, chain",
+            PG_TABLE_NAME
+        );
+        let rows = sqlx::query(&query)
+            .bind(token_id)
+            .bind(chain)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(Token::from_row).collect()
+    }
+
+    /// Insert the token or fill in what is known about it. Known fields are only overwritten by
+    /// known values, except for the deployer, and linked tokens are added to the existing ones.
+    pub async fn upsert(&self, token: &Token) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {table} (token_id, chain, address, name, symbol, decimals, deployer, linked_tokens) VALUES ($1, $2, $3, $4, $5, $6, $7, $8::jsonb) \
+             ON CONFLICT (token_id, chain) DO UPDATE SET \
+             address = COALESCE(EXCLUDED.address, {table}.address), \
+             name = COALESCE(EXCLUDED.name, {table}.name), \
+             symbol = COALESCE(EXCLUDED.symbol, {table}.symbol), \
+             decimals = COALESCE(EXCLUDED.decimals, {table}.decimals), \
+             deployer = COALESCE({table}.deployer, EXCLUDED.deployer), \
+             linked_tokens = COALESCE((SELECT jsonb_agg(DISTINCT linked) FROM jsonb_array_elements({table}.linked_tokens || EXCLUDED.linked_tokens) AS linked), '[]'::jsonb), \
+             updated_at = NOW()",
+            table = PG_TABLE_NAME
+        );
+
+        sqlx::query(&query)
+            .bind(&token.token_id)
+            .bind(&token.chain)
+            .bind(&token.address)
+            .bind(&token.name)
+            .bind(&token.symbol)
+            .bind(token.decimals)
+            .bind(&token.deployer)
+            .bind(serde_json::to_string(&token.linked_tokens)?)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, token_id: &str) -> Result<(), anyhow::Error> {
+        let query = format!("DELETE FROM {} WHERE token_id = $1", PG_TABLE_NAME);
+        sqlx::query(&query)
+            .bind(token_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Record the decimals of a token registered with ITS_TOKEN_METADATA_REGISTERED, which only
+    /// becomes an interchain token once it is linked
+    pub async fn register_metadata(
+        &self,
+        chain: &str,
+        address: &str,
+        decimals: i32,
+    ) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} (chain, address, decimals) VALUES ($1, $2, $3) ON CONFLICT (chain, address) DO UPDATE SET decimals = $3, updated_at = NOW()",
+            PG_METADATA_TABLE_NAME
+        );
+
+        sqlx::query(&query)
+            .bind(chain)
+            .bind(address)
+            .bind(decimals)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_decimals(
+        &self,
+        chain: &str,
+        address: &str,
+    ) -> Result<Option<i32>, anyhow::Error> {
+        let query = format!(
+            "SELECT decimals FROM {} WHERE chain = $1 AND address = $2",
+            PG_METADATA_TABLE_NAME
+        );
+        let row = sqlx::query(&query)
+            .bind(chain)
+            .bind(address)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("decimals")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use testcontainers::{ContainerAsync, runners::AsyncRunner};
+    use testcontainers_modules::postgres;
+
+    use crate::models::tokens::{LinkedToken, Token, TokensModel};
+
+    async fn setup_test_container() -> (TokensModel, ContainerAsync<postgres::Postgres>) {
+        let container = postgres::Postgres::default()
+            .with_init_sql(
+                [
+                    include_str!("../../migrations/0002_events.sql"),
+                    include_str!("../../migrations/0014_its_tokens.sql"),
+                ]
+                .join("\n")
+                .into_bytes(),
+            )
+            .start()
+            .await
+            .unwrap();
+        let connection_string = format!(
+            "postgres://postgres:postgres@{}:{}/postgres",
+            container.get_host().await.unwrap(),
+            container.get_host_port_ipv4(5432).await.unwrap()
+        );
+        let model = TokensModel::new(&connection_string).await.unwrap();
+        // we need to return the container too otherwise it will be dropped and the test will run forever
+        (model, container)
+    }
+
+    #[tokio::test]
+    async fn test_upsert_and_find_tokens() {
+        let (db, _container) = setup_test_container().await;
+
+        let deployed = Token {
+            name: Some("Wrapped Ether".to_string()),
+            symbol: Some("WETH".to_string()),
+            decimals: Some(18),
+            deployer: Some("0xdeployer".to_string()),
+            linked_tokens: vec![LinkedToken {
+                chain: "xrpl".to_string(),
+                address: None,
+            }],
+            ..Token::new("0xtoken", "ethereum")
+        };
+        db.upsert(&deployed).await.unwrap();
+        assert_eq!(
+            db.find("0xtoken", "ethereum").await.unwrap().unwrap(),
+            deployed
+        );
+        assert!(db.find("0xtoken", "xrpl").await.unwrap().is_none());
+
+        // a later link keeps what is known and adds the linked token
+        let linked = Token {
+            address: Some("0xweth".to_string()),
+            deployer: Some("0xsomeoneelse".to_string()),
+            linked_tokens: vec![
+                LinkedToken {
+                    chain: "xrpl".to_string(),
+                    address: None,
+                },
+                LinkedToken {
+                    chain: "avalanche".to_string(),
+                    address: Some("0xavaxweth".to_string()),
+                },
+            ],
+            ..Token::new("0xtoken", "ethereum")
+        };
+        db.upsert(&linked).await.unwrap();
+        let token = db.find("0xtoken", "ethereum").await.unwrap().unwrap();
+        assert_eq!(token.address, Some("0xweth".to_string()));
+        assert_eq!(token.decimals, Some(18));
+        assert_eq!(token.deployer, Some("0xdeployer".to_string()));
+        assert_eq!(token.linked_tokens.len(), 2);
+
+        db.upsert(&Token::new("0xtoken", "xrpl")).await.unwrap();
+        assert_eq!(db.get_tokens(Some("0xtoken"), None).await.unwrap().len(), 2);
+        assert_eq!(db.get_tokens(None, Some("xrpl")).await.unwrap().len(), 1);
+
+        db.register_metadata("ethereum", "0xusdc", 6).await.unwrap();
+        assert_eq!(
+            db.find_decimals("ethereum", "0xusdc").await.unwrap(),
+            Some(6)
+        );
+        assert_eq!(db.find_decimals("xrpl", "0xusdc").await.unwrap(), None);
+
+        db.delete("0xtoken").await.unwrap();
+        assert!(db.get_tokens(None, None).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,366 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Row, postgres::PgRow};
+
+use crate::gmp_types::Amount;
+
+const PG_TABLE_NAME: &str = "transfers";
+
+/// One side of an application transfer, as reported by the relayer of its chain
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TransferLeg {
+    #[serde(rename = "eventID")]
+    pub event_id: String,
+    /// Chains of the transfer as this leg sees them, one of which is the chain of its relayer
+    #[serde(rename = "sourceChain")]
+    pub source_chain: String,
+    #[serde(rename = "destinationChain")]
+    pub destination_chain: String,
+    pub sender: String,
+    pub recipient: String,
+    pub token: Amount,
+    pub timestamp: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferStatus {
+    /// Sent and not received yet
+    Pending,
+    /// Sent and not received within the arrival timeout
+    Overdue,
+    Completed,
+    /// Received with other chains, another amount or another recipient than was sent
+    Mismatched,
+    /// Received but never sent
+    Unmatched,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TransferMismatch {
+    pub field: String,
+    pub sent: String,
+    pub received: String,
+}
+
+/// The sent and received legs of a transfer, paired by message ID
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transfer {
+    pub message_id: String,
+    /// Chains of the leg that was recorded first
+    pub source_chain: String,
+    pub destination_chain: String,
+    pub sent: Option<TransferLeg>,
+    /// When the mock was told about the sent leg, which the arrival timeout is counted from
+    pub sent_at: Option<DateTime<Utc>>,
+    pub received: Option<TransferLeg>,
+    pub received_at: Option<DateTime<Utc>>,
+}
+
+impl Transfer {
+    fn from_row(row: &PgRow) -> Result<Self, anyhow::Error> {
+        let leg = |column: &str| -> Result<Option<TransferLeg>, anyhow::Error> {
+            let json: Option<String> = row.get(column);
+            Ok(json.map(|json| serde_json::from_str(&json)).transpose()?)
+        };
+        Ok(Self {
+            message_id: row.get("message_id"),
+            source_chain: row.get("source_chain"),
+            destination_chain: row.get("destination_chain"),
+            sent: leg("sent")?,
+            sent_at: row.get("sent_at"),
+            received: leg("received")?,
+            received_at: row.get("received_at"),
+        })
+    }
+
+    /// Differences in chains, amount and recipient between the sent and received legs
+    pub fn mismatches(&self) -> Vec<TransferMismatch> {
+        let (Some(sent), Some(received)) = (&self.sent, &self.received) else {
+            return vec![];
+        };
+
+        let mut mismatches = Vec::new();
+        let chains = [
+            ("sourceChain", &sent.source_chain, &received.source_chain),
+            (
+                "destinationChain",
+                &sent.destination_chain,
+                &received.destination_chain,
+            ),
+        ];
+        for (field, sent_chain, received_chain) in chains {
+            if sent_chain != received_chain {
+                mismatches.push(TransferMismatch {
+                    field: field.to_string(),
+                    sent: sent_chain.clone(),
+                    received: received_chain.clone(),
+                });
+            }
+        }
+        let same_amount = match (
+            sent.token.amount.parse::<u128>(),
+            received.token.amount.parse::<u128>(),
+        ) {
+            (Ok(sent), Ok(received)) => sent == received,
+            _ => sent.token.amount == received.token.amount,
+        };
+        if !same_amount {
+            mismatches.push(TransferMismatch {
+                field: "amount".to_string(),
+                sent: sent.token.amount.clone(),
+                received: received.token.amount.clone(),
+            });
+        }
+        // hex addresses may or may not be checksummed
+        let same_recipient = if sent.recipient.starts_with("0x") {
+            sent.recipient.eq_ignore_ascii_case(&received.recipient)
+        } else {
+            sent.recipient == received.recipient
+        };
+        if !same_recipient {
+            mismatches.push(TransferMismatch {
+                field: "recipient".to_string(),
+                sent: sent.recipient.clone(),
+                received: received.recipient.clone(),
+            });
+        }
+        mismatches
+    }
+
+    /// A transfer sent longer than `arrival_timeout` ago is overdue, if there is a timeout
+    pub fn status(&self, arrival_timeout: Option<Duration>, now: DateTime<Utc>) -> TransferStatus {
+        match (&self.sent, &self.received) {
+            (None, _) => TransferStatus::Unmatched,
+            (Some(_), Some(_)) if self.mismatches().is_empty() => TransferStatus::Completed,
+            (Some(_), Some(_)) => TransferStatus::Mismatched,
+            (Some(_), None) => match (arrival_timeout, self.sent_at) {
+                (Some(timeout), Some(sent_at)) if now - sent_at > timeout => {
+                    TransferStatus::Overdue
+                }
+                _ => TransferStatus::Pending,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TransfersModel {
+    pool: PgPool,
+}
+
+impl TransfersModel {
+    pub async fn new(url: &str) -> Result<Self, anyhow::Error> {
+        let pool = PgPool::connect(url).await?;
+        Ok(Self { pool })
+    }
+
+    pub async fn record_sent(
+        &self,
+        message_id: &str,
+        leg: &TransferLeg,
+    ) -> Result<(), anyhow::Error> {
+        self.record("sent", message_id, leg).await
+    }
+
+    pub async fn record_received(
+        &self,
+        message_id: &str,
+        leg: &TransferLeg,
+    ) -> Result<(), anyhow::Error> {
+        self.record("received", message_id, leg).await
+    }
+
+    /// Store a leg of the transfer, `side` being the column of the leg. The chains of the
+    /// transfer are those of the first leg, the other one is compared to them by `mismatches`.
+    async fn record(
+        &self,
+        side: &str,
+        message_id: &str,
+        leg: &TransferLeg,
+    ) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {table} (message_id, source_chain, destination_chain, {side}, {side}_at) VALUES ($1, $2, $3, $4::jsonb, NOW()) ON CONFLICT (message_id) DO UPDATE SET {side} = $4::jsonb, {side}_at = NOW()",
+            table = PG_TABLE_NAME,
+            side = side
+        );
+
+        sqlx::query(&query)
+            .bind(message_id)
+            .bind(&leg.source_chain)
+            .bind(&leg.destination_chain)
+            .bind(serde_json::to_string(leg)?)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn find(&self, message_id: &str) -> Result<Option<Transfer>, anyhow::Error> {
+        let query = format!(
+            "SELECT message_id, source_chain, destination_chain, sent::TEXT AS sent, sent_at, received::TEXT AS received, received_at FROM {} WHERE message_id = $1",
+            PG_TABLE_NAME
+        );
+        let row = sqlx::query(&query)
+            .bind(message_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(Transfer::from_row).transpose()
+    }
+
+    /// Transfers oldest first, optionally only those from or to the given chain
+    pub async fn get_transfers(&self, chain: Option<&str>) -> Result<Vec<Transfer>, anyhow::Error> {
+        let query = format!(
+            "SELECT message_id, source_chain, destination_chain, sent::TEXT AS sent, sent_at, received::TEXT AS received, received_at FROM {} WHERE $1::TEXT IS NULL OR source_chain = $1 OR destination_chain = $1 ORDER BY COALESCE(sent_at, received_at), message_id",
+            PG_TABLE_NAME
+        );
+        let rows = sqlx::query(&query)
+            .bind(chain)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(Transfer::from_row).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use testcontainers::{ContainerAsync, runners::AsyncRunner};
+    use testcontainers_modules::postgres;
+
+    use crate::gmp_types::Amount;
+    use crate::models::transfers::{TransferLeg, TransferStatus, TransfersModel};
+
+    async fn setup_test_container() -> (TransfersModel, ContainerAsync<postgres::Postgres>) {
+        let container = postgres::Postgres::default()
+            .with_init_sql(
+                [
+                    include_str!("../../migrations/0002_events.sql"),
+                    include_str!("../../migrations/0015_app_interchain_transfers.sql"),
+                ]
+                .join("\n")
+                .into_bytes(),
+            )
+            .start()
+            .await
+            .unwrap();
+        let connection_string = format!(
+            "postgres://postgres:postgres@{}:{}/postgres",
+            container.get_host().await.unwrap(),
+            container.get_host_port_ipv4(5432).await.unwrap()
+        );
+        let model = TransfersModel::new(&connection_string).await.unwrap();
+        // we need to return the container too otherwise it will be dropped and the test will run forever
+        (model, container)
+    }
+
+    fn leg(
+        event_id: &str,
+        source_chain: &str,
+        destination_chain: &str,
+        recipient: &str,
+        amount: &str,
+    ) -> TransferLeg {
+        TransferLeg {
+            event_id: event_id.to_string(),
+            source_chain: source_chain.to_string(),
+            destination_chain: destination_chain.to_string(),
+            sender: "0xsender".to_string(),
+            recipient: recipient.to_string(),
+            token: Amount {
+                token_id: Some("0xtoken".to_string()),
+                amount: amount.to_string(),
+            },
+            timestamp: "2025-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sent_and_received_legs_are_paired() {
+        let (db, _container) = setup_test_container().await;
+        let timeout = Some(Duration::seconds(60));
+
+        db.record_sent(
+            "0xabc-1",
+            &leg("sent", "ethereum", "xrpl", "rRecipient", "100"),
+        )
+        .await
+        .unwrap();
+        let transfer = db.find("0xabc-1").await.unwrap().unwrap();
+        assert_eq!(
+            transfer.status(timeout, Utc::now()),
+            TransferStatus::Pending
+        );
+        assert_eq!(
+            transfer.status(timeout, Utc::now() + Duration::seconds(120)),
+            TransferStatus::Overdue
+        );
+
+        db.record_received(
+            "0xabc-1",
+            &leg("received", "ethereum", "xrpl", "rRecipient", "100"),
+        )
+        .await
+        .unwrap();
+        let transfer = db.find("0xabc-1").await.unwrap().unwrap();
+        assert_eq!(
+            transfer.status(timeout, Utc::now() + Duration::seconds(120)),
+            TransferStatus::Completed
+        );
+
+        db.record_received(
+            "0xabc-2",
+            &leg("received", "avalanche", "xrpl", "rOther", "1"),
+        )
+        .await
+        .unwrap();
+        let transfer = db.find("0xabc-2").await.unwrap().unwrap();
+        assert_eq!(
+            transfer.status(timeout, Utc::now()),
+            TransferStatus::Unmatched
+        );
+
+        // the leg recorded last does not change the chains of the transfer
+        db.record_sent("0xabc-2", &leg("sent", "ethereum", "xrpl", "rOther", "1"))
+            .await
+            .unwrap();
+        let transfer = db.find("0xabc-2").await.unwrap().unwrap();
+        assert_eq!(transfer.source_chain, "avalanche");
+        assert_eq!(
+            transfer.status(timeout, Utc::now()),
+            TransferStatus::Mismatched
+        );
+
+        assert_eq!(db.get_transfers(Some("xrpl")).await.unwrap().len(), 2);
+        assert_eq!(db.get_transfers(Some("avalanche")).await.unwrap().len(), 1);
+        assert!(db.find("0xabc-3").await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_mismatches() {
+        let mut transfer = super::Transfer {
+            message_id: "0xabc-1".to_string(),
+            source_chain: "xrpl".to_string(),
+            destination_chain: "ethereum".to_string(),
+            sent: Some(leg("sent", "xrpl", "ethereum", "0xAbCd", "1000")),
+            sent_at: Some(Utc::now()),
+            received: Some(leg("received", "xrpl", "ethereum", "0xabcd", "1000")),
+            received_at: Some(Utc::now()),
+        };
+        assert!(transfer.mismatches().is_empty());
+
+        transfer.received = Some(leg("received", "xrpl", "avalanche", "0xdcba", "999"));
+        let fields: Vec<String> = transfer
+            .mismatches()
+            .into_iter()
+            .map(|mismatch| mismatch.field)
+            .collect();
+        assert_eq!(fields, vec!["destinationChain", "amount", "recipient"]);
+        assert_eq!(
+            transfer.status(None, Utc::now()),
+            TransferStatus::Mismatched
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{PgPool, Row, postgres::PgRow};
+
+const PG_TABLE_NAME: &str = "wasm_events";
+const PG_CHECKPOINTS_TABLE_NAME: &str = "indexer_checkpoints";
+
+/// A wasm event of an indexed transaction
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexedEvent {
+    pub tx_hash: String,
+    pub event_index: i32,
+    pub height: i64,
+    pub event_type: String,
+    pub contract_address: String,
+    pub event_id: Option<String>,
+    /// `[{"key": ..., "value": ...}]`, as found in the transaction result
+    pub attributes: Value,
+    pub block_time: DateTime<Utc>,
+}
+
+impl IndexedEvent {
+    /// The event in the shape `axelard query txs` returns it
+    pub fn to_event(&self) -> Value {
+        serde_json::json!({
+            "type": self.event_type,
+            "attributes": self.attributes,
+        })
+    }
+
+    fn from_row(row: &PgRow) -> Result<Self, anyhow::Error> {
+        let attributes: String = row.get("attributes");
+        Ok(Self {
+            tx_hash: row.get("tx_hash"),
+            event_index: row.get("event_index"),
+            height: row.get("height"),
+            event_type: row.get("event_type"),
+            contract_address: row.get("contract_address"),
+            event_id: row.get("event_id"),
+            attributes: serde_json::from_str(&attributes)?,
+            block_time: row.get("block_time"),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WasmEventsModel {
+    pool: PgPool,
+}
+
+impl WasmEventsModel {
+    pub async fn new(url: &str) -> Result<Self, anyhow::Error> {
+        let pool = PgPool::connect(url).await?;
+        Ok(Self { pool })
+    }
+
+    /// Store the events found up to `height` and move the checkpoint of `indexer` there, in one
+    /// transaction so that a restart neither skips nor duplicates events
+    pub async fn store(
+        &self,
+        indexer: &str,
+        events: &[IndexedEvent],
+        height: i64,
+    ) -> Result<(), anyhow::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let query = format!(
+            "INSERT INTO {} (tx_hash, event_index, height, event_type, contract_address, event_id, attributes, block_time) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT (tx_hash, event_index) DO NOTHING",
+            PG_TABLE_NAME
+        );
+        for event in events {
+            sqlx::query(&query)
+                .bind(&event.tx_hash)
+                .bind(event.event_index)
+                .bind(event.height)
+                .bind(&event.event_type)
+                .bind(&event.contract_address)
+                .bind(&event.event_id)
+                .bind(serde_json::to_string(&event.attributes)?)
+                .bind(event.block_time)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let query = format!(
+            "INSERT INTO {} (name, height) VALUES ($1, $2) ON CONFLICT (name) DO UPDATE SET height = $2, updated_at = NOW()",
+            PG_CHECKPOINTS_TABLE_NAME
+        );
+        sqlx::query(&query)
+            .bind(indexer)
+            .bind(height)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Last height processed by `indexer`
+    pub async fn checkpoint(&self, indexer: &str) -> Result<Option<i64>, anyhow::Error> {
+        let query = format!(
+            "SELECT height FROM {} WHERE name = $1",
+            PG_CHECKPOINTS_TABLE_NAME
+        );
+        let row = sqlx::query(&query)
+            .bind(indexer)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("height")))
+    }
+
+    /// The latest event of `event_type` emitted by `contract_address` with the given poll or
+    /// session ID
+    pub async fn find(
+        &self,
+        event_type: &str,
+        contract_address: &str,
+        event_id: &str,
+    ) -> Result<Option<IndexedEvent>, anyhow::Error> {
+        let query = format!(
+            "SELECT tx_hash, event_index, height, event_type, contract_address, event_id, attributes, block_time FROM {} WHERE event_type = $1 AND contract_address = $2 AND event_id = $3 ORDER BY height DESC LIMIT 1",
+            PG_TABLE_NAME
+        );
+        let row = sqlx::query(&query)
+            .bind(event_type)
+            .bind(contract_address)
+            .bind(event_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(IndexedEvent::from_row).transpose()
+    }
+
+    /// Every event of `event_type` emitted by `contract_address` with the given poll or session
+    /// ID, e.g. the quorum_reached event of each message of a poll
+    pub async fn find_all(
+        &self,
+        event_type: &str,
+        contract_address: &str,
+        event_id: &str,
+    ) -> Result<Vec<IndexedEvent>, anyhow::Error> {
+        let query = format!(
+            "SELECT tx_hash, event_index, height, event_type, contract_address, event_id, attributes, block_time FROM {} WHERE event_type = $1 AND contract_address = $2 AND event_id = $3 ORDER BY height ASC, event_index ASC",
+            PG_TABLE_NAME
+        );
+        let rows = sqlx::query(&query)
+            .bind(event_type)
+            .bind(contract_address)
+            .bind(event_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter().map(IndexedEvent::from_row).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use testcontainers::{ContainerAsync, runners::AsyncRunner};
+    use testcontainers_modules::postgres;
+
+    use crate::models::wasm_events::{IndexedEvent, WasmEventsModel};
+
+    async fn setup_test_container() -> (WasmEventsModel, ContainerAsync<postgres::Postgres>) {
+        let container = postgres::Postgres::default()
+            .with_init_sql(
+                include_str!("../../migrations/0008_wasm_events.sql")
+                    .to_string()
+                    .into_bytes(),
+            )
+            .start()
+            .await
+            .unwrap();
+        let connection_string = format!(
+            "postgres://postgres:postgres@{}:{}/postgres",
+            container.get_host().await.unwrap(),
+            container.get_host_port_ipv4(5432).await.unwrap()
+        );
+        let model = WasmEventsModel::new(&connection_string).await.unwrap();
+        // we need to return the container too otherwise it will be dropped and the test will run forever
+        (model, container)
+    }
+
+    #[tokio::test]
+    async fn test_store_events_and_checkpoint() {
+        let (db, _container) = setup_test_container().await;
+        assert_eq!(db.checkpoint("quorum").await.unwrap(), None);
+
+        let event = IndexedEvent {
+            tx_hash: "ABCD".to_string(),
+            event_index: 3,
+            height: 1234,
+            event_type: "wasm-quorum_reached".to_string(),
+            contract_address: "axelar1verifier".to_string(),
+            event_id: Some("\"42\"".to_string()),
+            attributes: serde_json::json!([
+                {"key": "_contract_address", "value": "axelar1verifier"},
+                {"key": "poll_id", "value": "\"42\""}
+            ]),
+            block_time: Utc::now(),
+        };
+        db.store("quorum", std::slice::from_ref(&event), 1300)
+            .await
+            .unwrap();
+        // storing a range again after a crash does not duplicate its events
+        db.store("quorum", std::slice::from_ref(&event), 1300)
+            .await
+            .unwrap();
+        assert_eq!(db.checkpoint("quorum").await.unwrap(), Some(1300));
+
+        let found = db
+            .find("wasm-quorum_reached", "axelar1verifier", "\"42\"")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.tx_hash, event.tx_hash);
+        assert_eq!(found.to_event()["attributes"], event.attributes);
+        assert_eq!(
+            db.find_all("wasm-quorum_reached", "axelar1verifier", "\"42\"")
+                .await
+                .unwrap(),
+            vec![found]
+        );
+        assert!(
+            db.find("wasm-quorum_reached", "axelar1verifier", "\"43\"")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use lapin::{
+    BasicProperties, Channel, Connection, ConnectionProperties,
+    acker::Acker as LapinAcker,
+    options::*,
+    types::{AMQPValue, FieldTable, LongString, ShortString},
+};
+use tracing::debug;
+
+use super::{Acker, Delivery, DeliveryStream, Headers, QueueItem, QueueTrait};
+use crate::telemetry::inject_context;
+
+/// RabbitMQ queue
+#[derive(Clone)]
+pub struct LapinConnection {
+    channel: Channel,
+    queue_name: String,
+}
+
+impl LapinConnection {
+    pub async fn new(addr: &str, queue_name: &str) -> Result<Self, anyhow::Error> {
+        let conn = Connection::connect(addr, ConnectionProperties::default())
+            .await
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+        debug!("CONNECTED");
+
+        let channel = conn
+            .create_channel()
+            .await
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+        channel
+            .confirm_select(ConfirmSelectOptions { nowait: false })
+            .await?;
+
+        let _queue = channel
+            .queue_declare(
+                queue_name,
+                QueueDeclareOptions::default(),
+                FieldTable::default(),
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+        Ok(LapinConnection {
+            channel,
+            queue_name: queue_name.to_string(),
+        })
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.channel.status().connected()
+    }
+
+    async fn publish_to(&self, queue_name: &str, item: &QueueItem) -> Result<(), anyhow::Error> {
+        let msg = serde_json::to_vec(item)?;
+        let properties = BasicProperties::default()
+            .with_delivery_mode(2)
+            .with_headers(to_field_table(inject_context()));
+
+        let confirm = self
+            .channel
+            .basic_publish(
+                "",
+                queue_name,
+                BasicPublishOptions::default(),
+                &msg,
+                properties,
+            )
+            .await?
+            .await?;
+
+        if confirm.is_ack() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Failed to publish message"))
+        }
+    }
+}
+
+#[async_trait]
+impl QueueTrait for LapinConnection {
+    async fn publish(&self, item: &QueueItem) -> Result<(), anyhow::Error> {
+        self.publish_to(&self.queue_name, item).await
+    }
+
+    async fn publish_delayed(
+        &self,
+        item: &QueueItem,
+        delay: Duration,
+    ) -> Result<(), anyhow::Error> {
+        // One queue per delay, as a message only expires once it reaches the head of its queue.
+        // Expired messages are dead-lettered back to the main queue.
+        // the TTL of a queue is a u32, a longer delay would silently become a shorter one
+        let delay_ms = u32::try_from(delay.as_millis())
+            .map_err(|_| anyhow::anyhow!("Delay of {:?} is too long for a queue TTL", delay))?;
+        let delay_queue_name = format!("{}.delay.{}", self.queue_name, delay_ms);
+
+        let mut arguments = FieldTable::default();
+        arguments.insert(
+            ShortString::from("x-message-ttl"),
+            AMQPValue::LongUInt(delay_ms),
+        );
+        arguments.insert(
+            ShortString::from("x-dead-letter-exchange"),
+            AMQPValue::LongString(LongString::from("")),
+        );
+        arguments.insert(
+            ShortString::from("x-dead-letter-routing-key"),
+            AMQPValue::LongString(LongString::from(self.queue_name.as_str())),
+        );
+        // remove delay queues that have not been used for a while
+        arguments.insert(
+            ShortString::from("x-expires"),
+            AMQPValue::LongUInt(delay_ms.saturating_mul(2).saturating_add(60_000)),
+        );
+        self.channel
+            .queue_declare(&delay_queue_name, QueueDeclareOptions::default(), arguments)
+            .await?;
+
+        self.publish_to(&delay_queue_name, item).await
+    }
+
+    async fn consumer(
+        &self,
+        consumer_name: &str,
+        prefetch: u16,
+    ) -> Result<DeliveryStream, anyhow::Error> {
+        self.channel
+            .basic_qos(prefetch, BasicQosOptions::default())
+            .await?;
+        let consumer = self
+            .channel
+            .basic_consume(
+                &self.queue_name,
+                consumer_name,
+                BasicConsumeOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+
+        Ok(consumer
+            .map(|delivery| {
+                let delivery = delivery?;
+                Ok(Delivery {
+                    headers: from_field_table(delivery.properties.headers().as_ref()),
+                    data: delivery.data,
+                    acker: Box::new(delivery.acker),
+                })
+            })
+            .boxed())
+    }
+
+    async fn message_count(&self) -> Result<u64, anyhow::Error> {
+        let queue = self
+            .channel
+            .queue_declare(
+                &self.queue_name,
+                QueueDeclareOptions {
+                    passive: true,
+                    ..Default::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+        Ok(queue.message_count() as u64)
+    }
+
+    async fn check(&self) -> Result<(), anyhow::Error> {
+        if self.is_connected() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("AMQP channel is not connected"))
+        }
+    }
+
+    /// Close the channel, so that the broker requeues every delivery that was not acked
+    async fn close(&self) -> Result<(), anyhow::Error> {
+        if self.is_connected() {
+            self.channel.close(200, "shutting down").await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Acker for LapinAcker {
+    async fn ack(self: Box<Self>) -> Result<(), anyhow::Error> {
+        LapinAcker::ack(&self, BasicAckOptions::default()).await?;
+        Ok(())
+    }
+
+    async fn nack(self: Box<Self>, requeue: bool) -> Result<(), anyhow::Error> {
+        LapinAcker::nack(
+            &self,
+            BasicNackOptions {
+                multiple: false,
+                requeue,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+fn to_field_table(headers: Headers) -> FieldTable {
+    let mut table = FieldTable::default();
+    for (key, value) in headers {
+        table.insert(
+            ShortString::from(key),
+            AMQPValue::LongString(LongString::from(value)),
+        );
+    }
+    table
+}
+
+fn from_field_table(table: Option<&FieldTable>) -> Headers {
+    table
+        .map(FieldTable::inner)
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| match value {
+            AMQPValue::LongString(value) => Some((
+                key.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).to_string(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,191 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use tokio::sync::{
+    Mutex,
+    mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
+};
+
+use super::{Acker, Delivery, DeliveryStream, Headers, QueueItem, QueueTrait};
+use crate::telemetry::inject_context;
+
+struct Message {
+    data: Vec<u8>,
+    headers: Headers,
+}
+
+/// Queue living in the memory of the process, for tests and for running the server and the
+/// subscriber in a single process. Items are lost when the process exits.
+#[derive(Clone)]
+pub struct MemoryQueue {
+    sender: UnboundedSender<Message>,
+    receiver: Arc<Mutex<UnboundedReceiver<Message>>>,
+    ready: Arc<AtomicU64>,
+}
+
+impl MemoryQueue {
+    pub fn new() -> Self {
+        let (sender, receiver) = unbounded_channel();
+        Self {
+            sender,
+            receiver: Arc::new(Mutex::new(receiver)),
+            ready: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    fn send(&self, message: Message) -> Result<(), anyhow::Error> {
+        self.ready.fetch_add(1, Ordering::Relaxed);
+        self.sender
+            .send(message)
+            .map_err(|_| anyhow::anyhow!("Queue is closed"))
+    }
+}
+
+impl Default for MemoryQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl QueueTrait for MemoryQueue {
+    async fn publish(&self, item: &QueueItem) -> Result<(), anyhow::Error> {
+        self.send(Message {
+            data: serde_json::to_vec(item)?,
+            headers: inject_context(),
+        })
+    }
+
+    async fn publish_delayed(
+        &self,
+        item: &QueueItem,
+        delay: Duration,
+    ) -> Result<(), anyhow::Error> {
+        let message = Message {
+            data: serde_json::to_vec(item)?,
+            headers: inject_context(),
+        };
+        let queue = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let _ = queue.send(message);
+        });
+        Ok(())
+    }
+
+    async fn consumer(
+        &self,
+        _consumer_name: &str,
+        _prefetch: u16,
+    ) -> Result<DeliveryStream, anyhow::Error> {
+        let queue = self.clone();
+        Ok(futures::stream::unfold(queue, |queue| async move {
+            let message = queue.receiver.lock().await.recv().await?;
+            queue.ready.fetch_sub(1, Ordering::Relaxed);
+            let delivery = Delivery {
+                data: message.data.clone(),
+                headers: message.headers.clone(),
+                acker: Box::new(MemoryAcker {
+                    queue: queue.clone(),
+                    message,
+                }),
+            };
+            Some((Ok(delivery), queue))
+        })
+        .boxed())
+    }
+
+    async fn message_count(&self) -> Result<u64, anyhow::Error> {
+        Ok(self.ready.load(Ordering::Relaxed))
+    }
+
+    async fn check(&self) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+
+    async fn close(&self) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+}
+
+struct MemoryAcker {
+    queue: MemoryQueue,
+    message: Message,
+}
+
+#[async_trait]
+impl Acker for MemoryAcker {
+    async fn ack(self: Box<Self>) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+
+    async fn nack(self: Box<Self>, requeue: bool) -> Result<(), anyhow::Error> {
+        if requeue {
+            self.queue.send(self.message)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use futures::StreamExt;
+
+    use super::MemoryQueue;
+    use crate::queue::{QueueItem, QueueTrait, RetryInfo, VerifyMessagesItem};
+
+    fn item(poll_id: &str) -> QueueItem {
+        QueueItem::VerifyMessages(VerifyMessagesItem {
+            poll_id: poll_id.to_string(),
+            contract_address: "axelar1verifier".to_string(),
+            broadcast_created_at: Utc::now(),
+            chain: "xrpl".to_string(),
+            broadcast_id: None,
+            expires_at: None,
+            retry: RetryInfo::default(),
+        })
+    }
+
+    fn poll_id(data: &[u8]) -> String {
+        match serde_json::from_slice(data).unwrap() {
+            QueueItem::VerifyMessages(item) => item.poll_id,
+            _ => panic!("unexpected item"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_nacked_and_delayed_items_are_delivered_again() {
+        let queue = MemoryQueue::new();
+        let mut consumer = queue.consumer("test", 1).await.unwrap();
+
+        queue
+            .publish_delayed(&item("2"), Duration::from_millis(50))
+            .await
+            .unwrap();
+        queue.publish(&item("1")).await.unwrap();
+        assert_eq!(queue.message_count().await.unwrap(), 1);
+
+        let delivery = consumer.next().await.unwrap().unwrap();
+        assert_eq!(poll_id(&delivery.data), "1");
+        delivery.nack(true).await.unwrap();
+
+        let delivery = consumer.next().await.unwrap().unwrap();
+        assert_eq!(poll_id(&delivery.data), "1");
+        delivery.ack().await.unwrap();
+
+        let delivery = consumer.next().await.unwrap().unwrap();
+        assert_eq!(poll_id(&delivery.data), "2");
+        delivery.ack().await.unwrap();
+        assert_eq!(queue.message_count().await.unwrap(), 0);
+    }
+}
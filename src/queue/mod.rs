@@ -0,0 +1,246 @@
+mod amqp;
+mod memory;
+mod postgres;
+
+pub use amqp::LapinConnection;
+pub use memory::MemoryQueue;
+pub use postgres::PostgresQueue;
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Config, QueueBackend},
+    rules::{CONSTRUCT_PROOF, EventRule, VERIFY_MESSAGES},
+    subscriber::RetryPolicy,
+};
+
+/// Trace context and other metadata travelling with a queue item
+pub type Headers = HashMap<String, String>;
+
+pub type DeliveryStream = BoxStream<'static, Result<Delivery, anyhow::Error>>;
+
+#[async_trait]
+pub trait QueueTrait: Send + Sync {
+    async fn publish(&self, item: &QueueItem) -> Result<(), anyhow::Error>;
+    /// Publish an item that is only delivered after `delay`
+    async fn publish_delayed(&self, item: &QueueItem, delay: Duration)
+    -> Result<(), anyhow::Error>;
+    /// Consume the queue, with up to `prefetch` deliveries sent ahead by backends that push them
+    async fn consumer(
+        &self,
+        consumer_name: &str,
+        prefetch: u16,
+    ) -> Result<DeliveryStream, anyhow::Error>;
+    /// Number of items ready to be delivered
+    async fn message_count(&self) -> Result<u64, anyhow::Error>;
+    /// Check that the backend is reachable
+    async fn check(&self) -> Result<(), anyhow::Error>;
+    /// Stop consuming, so that every delivery that was not acked is delivered again
+    async fn close(&self) -> Result<(), anyhow::Error>;
+}
+
+/// Settles a delivery with the backend it came from
+#[async_trait]
+trait Acker: Send + Sync {
+    async fn ack(self: Box<Self>) -> Result<(), anyhow::Error>;
+    async fn nack(self: Box<Self>, requeue: bool) -> Result<(), anyhow::Error>;
+}
+
+/// A queue item received from a consumer, to be acked once handled or nacked to give it back
+pub struct Delivery {
+    pub data: Vec<u8>,
+    pub headers: Headers,
+    acker: Box<dyn Acker>,
+}
+
+impl Delivery {
+    pub async fn ack(self) -> Result<(), anyhow::Error> {
+        self.acker.ack().await
+    }
+
+    pub async fn nack(self, requeue: bool) -> Result<(), anyhow::Error> {
+        self.acker.nack(requeue).await
+    }
+}
+
+/// Connect to the queue backend selected by `queue.backend`
+pub async fn connect(config: &Config) -> Result<Arc<dyn QueueTrait>, anyhow::Error> {
+    let queue: Arc<dyn QueueTrait> = match config.queue.backend {
+        QueueBackend::Amqp => {
+            Arc::new(LapinConnection::new(&config.queue.address, &config.queue.name).await?)
+        }
+        QueueBackend::Postgres => Arc::new(
+            PostgresQueue::new(&config.database.url, &config.queue.name)
+                .await?
+                .with_error_backoff(RetryPolicy::from_config(&config.subscriber)),
+        ),
+        QueueBackend::Memory => Arc::new(MemoryQueue::new()),
+    };
+    Ok(queue)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum QueueItem {
+    VerifyMessages(VerifyMessagesItem),
+    ConstructProof(ConstructProofItem),
+    WasmEvent(WasmEventItem),
+}
+
+impl QueueItem {
+    /// Item waiting for the follow-up event of `rule`, the built-in rules keeping their own items
+    pub fn new(
+        rule: &EventRule,
+        id: String,
+        contract_address: String,
+        chain: String,
+        broadcast_id: String,
+        expires_at: Option<u64>,
+    ) -> Self {
+        let broadcast_created_at = Utc::now();
+        let broadcast_id = Some(broadcast_id);
+        let retry = RetryInfo::default();
+        match rule.name.as_str() {
+            VERIFY_MESSAGES => QueueItem::VerifyMessages(VerifyMessagesItem {
+                poll_id: id,
+                contract_address,
+                broadcast_created_at,
+                chain,
+                broadcast_id,
+                expires_at,
+                retry,
+            }),
+            CONSTRUCT_PROOF => QueueItem::ConstructProof(ConstructProofItem {
+                session_id: id,
+                contract_address,
+                broadcast_created_at,
+                chain,
+                broadcast_id,
+                expires_at,
+                retry,
+            }),
+            _ => QueueItem::WasmEvent(WasmEventItem {
+                rule: rule.name.clone(),
+                id,
+                contract_address,
+                broadcast_created_at,
+                chain,
+                broadcast_id,
+                expires_at,
+                retry,
+            }),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            QueueItem::VerifyMessages(_) => "verify_messages",
+            QueueItem::ConstructProof(_) => "construct_proof",
+            QueueItem::WasmEvent(_) => "wasm_event",
+        }
+    }
+
+    pub fn retry(&self) -> &RetryInfo {
+        match self {
+            QueueItem::VerifyMessages(item) => &item.retry,
+            QueueItem::ConstructProof(item) => &item.retry,
+            QueueItem::WasmEvent(item) => &item.retry,
+        }
+    }
+
+    pub fn retry_mut(&mut self) -> &mut RetryInfo {
+        match self {
+            QueueItem::VerifyMessages(item) => &mut item.retry,
+            QueueItem::ConstructProof(item) => &mut item.retry,
+            QueueItem::WasmEvent(item) => &mut item.retry,
+        }
+    }
+}
+
+/// How often a queue item has been handled without success, and since when
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryInfo {
+    pub attempts: u32,
+    pub first_seen_at: DateTime<Utc>,
+}
+
+impl Default for RetryInfo {
+    fn default() -> Self {
+        Self {
+            attempts: 0,
+            first_seen_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VerifyMessagesItem {
+    pub poll_id: String,
+    pub contract_address: String,
+    pub broadcast_created_at: DateTime<Utc>,
+    pub chain: String,
+    /// Broadcast that started the poll, missing in items queued by older versions
+    #[serde(default)]
+    pub broadcast_id: Option<String>,
+    /// Block height after which the poll has expired
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(flatten)]
+    pub retry: RetryInfo,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConstructProofItem {
+    pub session_id: String,
+    pub contract_address: String,
+    pub broadcast_created_at: DateTime<Utc>,
+    pub chain: String,
+    /// Broadcast that started the signing session, missing in items queued by older versions
+    #[serde(default)]
+    pub broadcast_id: Option<String>,
+    /// Block height after which the signing session has expired
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(flatten)]
+    pub retry: RetryInfo,
+}
+
+/// Item of a rule from the config, waiting for its follow-up event
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WasmEventItem {
+    pub rule: String,
+    /// Value of the correlation attribute of the rule
+    pub id: String,
+    pub contract_address: String,
+    pub broadcast_created_at: DateTime<Utc>,
+    pub chain: String,
+    pub broadcast_id: Option<String>,
+    pub expires_at: Option<u64>,
+    #[serde(flatten)]
+    pub retry: RetryInfo,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueueItem;
+
+    #[test]
+    fn test_items_without_retry_info_are_accepted() {
+        let item: QueueItem = serde_json::from_str(
+            r#"{"VerifyMessages":{"poll_id":"1","contract_address":"axelar1verifier","broadcast_created_at":"2025-01-01T00:00:00Z","chain":"xrpl"}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(item.retry().attempts, 0);
+
+        let mut item = item;
+        item.retry_mut().attempts = 3;
+        let json = serde_json::to_value(&item).unwrap();
+        assert_eq!(json["VerifyMessages"]["attempts"], 3);
+    }
+}
@@ -0,0 +1,256 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use sqlx::{PgPool, Row};
+
+use super::{Acker, Delivery, DeliveryStream, Headers, QueueItem, QueueTrait};
+use crate::{config::SubscriberConfig, subscriber::RetryPolicy, telemetry::inject_context};
+
+const PG_TABLE_NAME: &str = "queue_jobs";
+/// How long to wait before looking for jobs again when there are none
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// A job that was neither acked nor nacked within this time, e.g. because its consumer crashed,
+/// is delivered again
+const LEASE: Duration = Duration::from_secs(300);
+
+/// Job table in Postgres, consumed with `FOR UPDATE SKIP LOCKED` so that several subscribers can
+/// share it without an AMQP broker
+#[derive(Clone, Debug)]
+pub struct PostgresQueue {
+    pool: PgPool,
+    queue_name: String,
+    /// How long the consumer waits before polling again after the database failed
+    error_backoff: RetryPolicy,
+}
+
+impl PostgresQueue {
+    pub async fn new(url: &str, queue_name: &str) -> Result<Self, anyhow::Error> {
+        let pool = PgPool::connect(url).await?;
+        Ok(Self {
+            pool,
+            queue_name: queue_name.to_string(),
+            error_backoff: RetryPolicy::from_config(&SubscriberConfig::default()),
+        })
+    }
+
+    pub fn with_error_backoff(mut self, error_backoff: RetryPolicy) -> Self {
+        self.error_backoff = error_backoff;
+        self
+    }
+
+    async fn insert(&self, item: &QueueItem, delay: Duration) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} (queue, item, headers, available_at) VALUES ($1, $2, $3, NOW() + make_interval(secs => $4))",
+            PG_TABLE_NAME
+        );
+
+        sqlx::query(&query)
+            .bind(&self.queue_name)
+            .bind(serde_json::to_string(item)?)
+            .bind(serde_json::to_string(&inject_context())?)
+            .bind(delay.as_secs_f64())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Lock the oldest available job, if any
+    async fn take(&self) -> Result<Option<Delivery>, anyhow::Error> {
+        let query = format!(
+            "UPDATE {table} SET locked_until = NOW() + make_interval(secs => $2)
+            WHERE id = (
+                SELECT id FROM {table}
+                WHERE queue = $1 AND available_at <= NOW()
+                    AND (locked_until IS NULL OR locked_until < NOW())
+                ORDER BY available_at ASC, id ASC
+                FOR UPDATE SKIP LOCKED
+                LIMIT 1
+            )
+            RETURNING id, item, headers",
+            table = PG_TABLE_NAME
+        );
+
+        let row = sqlx::query(&query)
+            .bind(&self.queue_name)
+            .bind(LEASE.as_secs_f64())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let item: String = row.get("item");
+        let headers: String = row.get("headers");
+        Ok(Some(Delivery {
+            data: item.into_bytes(),
+            headers: serde_json::from_str::<Headers>(&headers)?,
+            acker: Box::new(PostgresAcker {
+                pool: self.pool.clone(),
+                id: row.get("id"),
+            }),
+        }))
+    }
+}
+
+#[async_trait]
+impl QueueTrait for PostgresQueue {
+    async fn publish(&self, item: &QueueItem) -> Result<(), anyhow::Error> {
+        self.insert(item, Duration::ZERO).await
+    }
+
+    async fn publish_delayed(
+        &self,
+        item: &QueueItem,
+        delay: Duration,
+    ) -> Result<(), anyhow::Error> {
+        self.insert(item, delay).await
+    }
+
+    /// Jobs are only locked when the stream is polled, so there is nothing to prefetch
+    async fn consumer(
+        &self,
+        _consumer_name: &str,
+        _prefetch: u16,
+    ) -> Result<DeliveryStream, anyhow::Error> {
+        let queue = self.clone();
+        // the number of polls that failed in a row, so that a database that is down is not
+        // hammered with queries
+        Ok(
+            futures::stream::unfold((queue, 0u32), |(queue, failures)| async move {
+                if failures > 0 {
+                    tokio::time::sleep(queue.error_backoff.delay(failures)).await;
+                }
+                loop {
+                    match queue.take().await {
+                        Ok(Some(delivery)) => return Some((Ok(delivery), (queue, 0))),
+                        Ok(None) => tokio::time::sleep(POLL_INTERVAL).await,
+                        Err(e) => return Some((Err(e), (queue, failures.saturating_add(1)))),
+                    }
+                }
+            })
+            .boxed(),
+        )
+    }
+
+    async fn message_count(&self) -> Result<u64, anyhow::Error> {
+        let query = format!(
+            "SELECT COUNT(*) AS count FROM {} WHERE queue = $1 AND available_at <= NOW() AND (locked_until IS NULL OR locked_until < NOW())",
+            PG_TABLE_NAME
+        );
+        let row = sqlx::query(&query)
+            .bind(&self.queue_name)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(row.get::<i64, _>("count") as u64)
+    }
+
+    async fn check(&self) -> Result<(), anyhow::Error> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Nothing to release: jobs that were not acked are delivered again once their lease expires
+    async fn close(&self) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+}
+
+struct PostgresAcker {
+    pool: PgPool,
+    id: i64,
+}
+
+#[async_trait]
+impl Acker for PostgresAcker {
+    async fn ack(self: Box<Self>) -> Result<(), anyhow::Error> {
+        let query = format!("DELETE FROM {} WHERE id = $1", PG_TABLE_NAME);
+        sqlx::query(&query)
+            .bind(self.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn nack(self: Box<Self>, requeue: bool) -> Result<(), anyhow::Error> {
+        if !requeue {
+            return self.ack().await;
+        }
+        let query = format!(
+            "UPDATE {} SET locked_until = NULL WHERE id = $1",
+            PG_TABLE_NAME
+        );
+        sqlx::query(&query)
+            .bind(self.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::Utc;
+    use futures::StreamExt;
+    use testcontainers::{ContainerAsync, runners::AsyncRunner};
+    use testcontainers_modules::postgres;
+
+    use super::PostgresQueue;
+    use crate::queue::{ConstructProofItem, QueueItem, QueueTrait, RetryInfo};
+
+    async fn setup_test_container() -> (PostgresQueue, ContainerAsync<postgres::Postgres>) {
+        let container = postgres::Postgres::default()
+            .with_init_sql(
+                include_str!("../../migrations/0007_queue_jobs.sql")
+                    .to_string()
+                    .into_bytes(),
+            )
+            .start()
+            .await
+            .unwrap();
+        let connection_string = format!(
+            "postgres://postgres:postgres@{}:{}/postgres",
+            container.get_host().await.unwrap(),
+            container.get_host_port_ipv4(5432).await.unwrap()
+        );
+        let queue = PostgresQueue::new(&connection_string, "mock_gmp_api")
+            .await
+            .unwrap();
+        // we need to return the container too otherwise it will be dropped and the test will run forever
+        (queue, container)
+    }
+
+    #[tokio::test]
+    async fn test_jobs_are_locked_until_settled() {
+        let (queue, _container) = setup_test_container().await;
+        let item = QueueItem::ConstructProof(ConstructProofItem {
+            session_id: "22128".to_string(),
+            contract_address: "axelar1multisig".to_string(),
+            broadcast_created_at: Utc::now(),
+            chain: "xrpl".to_string(),
+            broadcast_id: None,
+            expires_at: None,
+            retry: RetryInfo::default(),
+        });
+        queue.publish(&item).await.unwrap();
+        queue
+            .publish_delayed(&item, Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert_eq!(queue.message_count().await.unwrap(), 1);
+
+        let mut consumer = queue.consumer("test", 1).await.unwrap();
+        let delivery = consumer.next().await.unwrap().unwrap();
+        // a locked job is not handed out twice
+        assert!(queue.take().await.unwrap().is_none());
+
+        delivery.nack(true).await.unwrap();
+        let delivery = queue.take().await.unwrap().unwrap();
+        delivery.ack().await.unwrap();
+        assert_eq!(queue.message_count().await.unwrap(), 0);
+    }
+}
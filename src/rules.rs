@@ -0,0 +1,102 @@
+use serde::Deserialize;
+
+pub const VERIFY_MESSAGES: &str = "verify_messages";
+pub const CONSTRUCT_PROOF: &str = "construct_proof";
+
+/// Task the subscriber stores once the follow-up event of a rule is found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleTask {
+    /// REACT_TO_WASM_EVENT with the follow-up event
+    ReactToWasmEvent,
+    /// GATEWAY_TX with the proof the multisig prover of the chain built for the signing session
+    /// given by the correlation attribute
+    GatewayTx,
+}
+
+/// Ties an event of a broadcast transaction to the event that completes the flow it started, and
+/// to the task created from it
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EventRule {
+    pub name: String,
+    /// Event of the broadcast transaction starting the flow, e.g. `wasm-messages_poll_started`
+    pub trigger_event: String,
+    /// Attribute of the trigger event identifying the flow, found again in the follow-up event
+    pub correlation_attribute: String,
+    /// Attribute of the trigger event naming the chain, for logging
+    #[serde(default)]
+    pub chain_attribute: Option<String>,
+    /// Event completing the flow, e.g. `wasm-quorum_reached`
+    pub follow_up_event: String,
+    pub task: RuleTask,
+}
+
+impl EventRule {
+    /// A poll of the voting verifier, reported back once it reaches a quorum
+    pub fn verify_messages() -> Self {
+        Self {
+            name: VERIFY_MESSAGES.to_string(),
+            trigger_event: "wasm-messages_poll_started".to_string(),
+            correlation_attribute: "poll_id".to_string(),
+            chain_attribute: Some("source_chain".to_string()),
+            follow_up_event: "wasm-quorum_reached".to_string(),
+            task: RuleTask::ReactToWasmEvent,
+        }
+    }
+
+    /// A signing session of the multisig contract, whose proof is sent to the gateway once it
+    /// completes
+    pub fn construct_proof() -> Self {
+        Self {
+            name: CONSTRUCT_PROOF.to_string(),
+            trigger_event: "wasm-signing_started".to_string(),
+            correlation_attribute: "session_id".to_string(),
+            chain_attribute: Some("chain".to_string()),
+            follow_up_event: "wasm-signing_completed".to_string(),
+            task: RuleTask::GatewayTx,
+        }
+    }
+}
+
+/// The built-in rules followed by the ones of the `[[rules]]` config sections
+#[derive(Debug, Clone)]
+pub struct Rules(Vec<EventRule>);
+
+impl Rules {
+    pub fn new(configured: &[EventRule]) -> Self {
+        let mut rules = vec![EventRule::verify_messages(), EventRule::construct_proof()];
+        rules.extend_from_slice(configured);
+        Self(rules)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &EventRule> {
+        self.0.iter()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&EventRule> {
+        self.0.iter().find(|rule| rule.name == name)
+    }
+
+    /// The first rule completed by events of `event_type`. Rules sharing a follow-up event also
+    /// share its correlation attribute, which `Config::validate` checks.
+    pub fn by_follow_up(&self, event_type: &str) -> Option<&EventRule> {
+        self.0
+            .iter()
+            .find(|rule| rule.follow_up_event == event_type)
+    }
+
+    pub fn verify_messages(&self) -> &EventRule {
+        self.get(VERIFY_MESSAGES).expect("built-in rule")
+    }
+
+    pub fn construct_proof(&self) -> &EventRule {
+        self.get(CONSTRUCT_PROOF).expect("built-in rule")
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
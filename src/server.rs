@@ -1,5 +1,7 @@
-use actix_web::{App, Error, HttpResponse, HttpServer, error, get, post, web};
-use base64::{Engine as _, engine::general_purpose};
+use actix_web::{
+    App, Error, HttpRequest, HttpResponse, HttpServer, delete, error, get, http::header,
+    middleware, post, put, route, web,
+};
 use chrono::{DateTime, Utc};
 use futures::StreamExt;
 use once_cell::sync::Lazy;
@@ -7,37 +9,70 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha3::{Digest, Keccak256};
 use std::collections::HashMap;
-use std::env;
+use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::Mutex;
-use tracing::{debug, error, info, warn};
+use tracing::{Span, debug, error, field, info, instrument, warn};
 use uuid::Uuid;
 
 use crate::{
     TasksModel,
-    event_handler::handle_call_or_gas_credit_event,
-    gmp_types::{Event, PostEventResponse, PostEventResult, StorePayloadResult, Task},
+    config::{Config, MAX_VISIBILITY_TIMEOUT},
+    event_handler::{
+        decode_payload_hash, handle_app_transfer_event, handle_call_or_gas_credit_event,
+        handle_its_token_event, handle_message_approved_event, verified_call_payload,
+    },
+    generator::Generator,
+    gmp_types::{Amount, Event, PostEventResponse, PostEventResult, StorePayloadResult, TaskKind},
+    health::{Readiness, healthz, readyz},
+    logging::{loggable, truncate},
+    metrics::{METRICS, TaskSource, metrics, track_requests},
     models::{
         broadcasts::{BroadcastStatus, BroadcastsModel},
+        chains::{Chain, ChainContracts, ChainsModel},
+        dead_letters::DeadLettersModel,
         events::EventsModel,
-        payloads::PayloadsModel,
+        payloads::{PayloadMetadata, PayloadsModel},
+        tasks::{TaskFilter, TaskStatus},
+        tokens::{LinkedToken, Token, TokensModel},
+        transfers::{Transfer, TransferLeg, TransferMismatch, TransferStatus, TransfersModel},
     },
-    queue::{ConstructProofItem, LapinConnection, QueueItem, QueueTrait, VerifyMessagesItem},
-    utils::{extract_info_from_script, parse_task},
+    queue::{QueueItem, QueueTrait, RetryInfo},
+    rules::Rules,
+    telemetry::trace_requests,
+    utils::{collect_message_ids, extract_event_attribute, extract_info_from_script},
+    validation::validate_task,
 };
 
+/// Bytes read from the database at a time when streaming a payload
+const PAYLOAD_CHUNK_SIZE: i64 = 65_536;
+
 static AXELARD_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
 
 pub struct Server {
-    pub port: u16,
-    pub address: String,
+    pub config: Config,
     pub tasks_model: TasksModel,
     pub events_model: EventsModel,
     pub broadcasts_model: BroadcastsModel,
     pub payloads_model: PayloadsModel,
-    pub queue: LapinConnection,
+    pub chains_model: ChainsModel,
+    pub dead_letters_model: DeadLettersModel,
+    pub tokens_model: TokensModel,
+    pub transfers_model: TransfersModel,
+    pub queue: Arc<dyn QueueTrait>,
 }
 
-const MAX_SIZE: usize = 262_144; // max payload size is 256k
+async fn read_body(mut payload: web::Payload, max_size: usize) -> Result<web::BytesMut, Error> {
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if (body.len() + chunk.len()) > max_size {
+            return Err(error::ErrorBadRequest("overflow"));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 struct EventsRequest {
@@ -70,27 +105,51 @@ struct BroadcastGetResponse {
 }
 
 #[post("/contracts/{contract_address}/broadcasts")]
+#[instrument(
+    skip_all,
+    fields(
+        contract_address = %contract_address,
+        chain = field::Empty,
+        broadcast_id = field::Empty,
+        message_ids = field::Empty,
+        poll_id = field::Empty,
+        session_id = field::Empty,
+    )
+)]
 async fn address_broadcast(
     contract_address: web::Path<String>,
     broadcasts_model: web::Data<BroadcastsModel>,
-    queue: web::Data<LapinConnection>,
-    mut payload: web::Payload,
+    chains_model: web::Data<ChainsModel>,
+    queue: web::Data<dyn QueueTrait>,
+    config: web::Data<Config>,
+    rules: web::Data<Rules>,
+    payload: web::Payload,
 ) -> Result<HttpResponse, Error> {
-    let mut body = web::BytesMut::new();
-    while let Some(chunk) = payload.next().await {
-        let chunk = chunk?;
-        if (body.len() + chunk.len()) > MAX_SIZE {
-            return Err(error::ErrorBadRequest("overflow"));
-        }
-        body.extend_from_slice(&chunk);
-    }
+    let body = read_body(payload, config.server.max_body_size).await?;
+
+    let chain = chains_model
+        .find_by_contract(&contract_address)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?
+        .ok_or_else(|| {
+            error::ErrorBadRequest(format!(
+                "Contract {} does not belong to any registered chain",
+                contract_address
+            ))
+        })?;
+    Span::current().record("chain", chain.name.as_str());
 
     let broadcast_request: Value = serde_json::from_slice(&body)
         .map_err(|e| error::ErrorBadRequest(format!("Invalid broadcast request: {}", e)))?;
+    Span::current().record(
+        "message_ids",
+        collect_message_ids(&broadcast_request).join(","),
+    );
 
     info!(
-        "Broadcast request: {:?} to contract: {}",
-        broadcast_request, contract_address
+        "Broadcast request: {} to contract: {}",
+        loggable(&broadcast_request),
+        contract_address
     );
 
     let broadcast_json = serde_json::to_string(&broadcast_request).map_err(|e| {
@@ -112,6 +171,7 @@ async fn address_broadcast(
     // make it generic
 
     let broadcast_id = Uuid::new_v4().simple().to_string();
+    Span::current().record("broadcast_id", broadcast_id.as_str());
 
     broadcasts_model
         .insert(
@@ -137,13 +197,15 @@ async fn address_broadcast(
   --keyring-backend test \
   --node {} \
   --chain-id {} \
-  --gas-prices 0.00005uamplifier \
-  --gas auto --gas-adjustment 1.5 --output json",
+  --gas-prices {} \
+  --gas auto --gas-adjustment {} --output json",
         contract_address_clone,
         broadcast_json_clone,
-        env::var("AXELAR_KEY_NAME").unwrap(),
-        env::var("AXELAR_RPC").unwrap(),
-        env::var("CHAIN_ID").unwrap()
+        config.axelar.key_name,
+        config.axelar.rpc,
+        config.axelar.chain_id,
+        config.axelar.gas_price,
+        config.axelar.gas_adjustment
     );
 
     info!(
@@ -152,18 +214,21 @@ async fn address_broadcast(
     );
     let _guard = AXELARD_LOCK.lock().await;
 
+    let axelard_started_at = Instant::now();
     let axelard_execute_script = tokio::process::Command::new("bash")
         .arg("-c")
         .arg(axelard_execute_script_str)
         .output()
         .await;
+    METRICS.axelard_call("tx_execute", axelard_started_at);
 
     match axelard_execute_script {
         Ok(output) => {
             let output_str = String::from_utf8_lossy(&output.stdout);
             info!(
                 "Transaction execution output for broadcast {}: {}",
-                broadcast_id_clone, output_str
+                broadcast_id_clone,
+                truncate(&output_str)
             );
 
             if output.status.success() {
@@ -184,71 +249,51 @@ async fn address_broadcast(
                             broadcast_id_clone, tx_hash
                         );
 
-                        let maybe_verify_messages_json = broadcast_request.get("verify_messages");
-
-                        if maybe_verify_messages_json.is_some() {
-                            let maybe_poll_id_and_contract_address = extract_info_from_script(
+                        for rule in rules.iter() {
+                            let Some((id, contract_address, event_chain)) =
+                                extract_info_from_script(&script_result, rule)
+                                    .map_err(|e| error::ErrorInternalServerError(e.to_string()))?
+                            else {
+                                // e.g. a poll is not started again for messages already being verified
+                                if broadcast_request.get(&rule.name).is_some() {
+                                    warn!(
+                                        "No {} and contract_address extracted from script result",
+                                        rule.correlation_attribute
+                                    );
+                                }
+                                continue;
+                            };
+                            Span::current()
+                                .record(rule.correlation_attribute.as_str(), id.as_str());
+                            debug!(
+                                "Publishing {} for {}: {:?}, contract_address: {:?}, chain: {:?} (event chain: {:?})",
+                                rule.name,
+                                rule.correlation_attribute,
+                                id,
+                                contract_address,
+                                chain.name,
+                                event_chain
+                            );
+                            let expires_at = extract_event_attribute(
                                 &script_result,
-                                "wasm-messages_poll_started",
+                                &rule.trigger_event,
+                                "expires_at",
                             )
-                            .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
-                            if let Some((poll_id, contract_address, chain)) =
-                                maybe_poll_id_and_contract_address
-                            {
-                                debug!(
-                                    "Publishing verify messages for poll_id: {:?}, contract_address: {:?}, chain: {:?}",
-                                    poll_id, contract_address, chain
-                                );
-                                queue
-                                    .publish(
-                                        &QueueItem::VerifyMessages(VerifyMessagesItem {
-                                            poll_id,
-                                            contract_address,
-                                            broadcast_created_at: chrono::Utc::now(),
-                                            chain,
-                                        }),
-                                        None,
-                                    )
-                                    .await
-                                    .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
-                            } else {
-                                warn!(
-                                    "No poll_id and contract_address extracted from script result"
-                                );
-                            }
+                            .and_then(|height| height.parse().ok());
+                            queue
+                                .publish(&QueueItem::new(
+                                    rule,
+                                    id,
+                                    contract_address,
+                                    chain.name.clone(),
+                                    broadcast_id.clone(),
+                                    expires_at,
+                                ))
+                                .await
+                                .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
                         }
 
-                        let maybe_construct_proof_json = broadcast_request.get("construct_proof");
-
-                        if maybe_construct_proof_json.is_some() {
-                            let maybe_session_id_and_contract_address =
-                                extract_info_from_script(&script_result, "wasm-signing_started")
-                                    .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
-                            if let Some((session_id, contract_address, chain)) =
-                                maybe_session_id_and_contract_address
-                            {
-                                debug!(
-                                    "Publishing construct proof for session_id: {:?}, contract_address: {:?}, chain: {:?}",
-                                    session_id, contract_address, chain
-                                );
-                                queue
-                                    .publish(
-                                        &QueueItem::ConstructProof(ConstructProofItem {
-                                            session_id,
-                                            contract_address,
-                                            broadcast_created_at: chrono::Utc::now(),
-                                            chain,
-                                        }),
-                                        None,
-                                    )
-                                    .await
-                                    .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
-                            } else {
-                                warn!(
-                                    "No session_id and contract_address extracted from script result"
-                                );
-                            }
-                        }
+                        METRICS.broadcast_finished("success");
 
                         if !tx_hash.is_empty() {
                             if let Err(e) = broadcasts_model_clone
@@ -277,6 +322,8 @@ async fn address_broadcast(
                             broadcast_id_clone, raw_log
                         );
 
+                        METRICS.broadcast_finished("failed");
+
                         if let Err(e) = broadcasts_model_clone
                             .upsert(
                                 &broadcast_id_clone,
@@ -297,6 +344,8 @@ async fn address_broadcast(
                         broadcast_id_clone, output_str
                     );
 
+                    METRICS.broadcast_finished("failed");
+
                     if let Err(e) = broadcasts_model_clone
                         .upsert(
                             &broadcast_id_clone,
@@ -318,6 +367,8 @@ async fn address_broadcast(
                     broadcast_id_clone, error_str
                 );
 
+                METRICS.broadcast_finished("failed");
+
                 if let Err(e) = broadcasts_model_clone
                     .upsert(
                         &broadcast_id_clone,
@@ -339,6 +390,8 @@ async fn address_broadcast(
                 broadcast_id_clone, e
             );
 
+            METRICS.broadcast_finished("failed");
+
             if let Err(e) = broadcasts_model_clone
                 .upsert(
                     &broadcast_id_clone,
@@ -444,37 +497,109 @@ async fn get_broadcast(
 }
 
 #[post("/chains/{chain}/events")]
+#[instrument(skip_all, fields(chain = %chain, message_ids = field::Empty))]
+#[allow(clippy::too_many_arguments)]
 async fn post_events(
     chain: web::Path<String>,
     events_model: web::Data<EventsModel>,
     tasks_model: web::Data<TasksModel>,
-    mut payload: web::Payload,
+    chains_model: web::Data<ChainsModel>,
+    payloads_model: web::Data<PayloadsModel>,
+    tokens_model: web::Data<TokensModel>,
+    transfers_model: web::Data<TransfersModel>,
+    config: web::Data<Config>,
+    payload: web::Payload,
 ) -> Result<HttpResponse, Error> {
-    let mut body = web::BytesMut::new();
-    while let Some(chunk) = payload.next().await {
-        let chunk = chunk?;
-        if (body.len() + chunk.len()) > MAX_SIZE {
-            return Err(error::ErrorBadRequest("overflow"));
-        }
-        body.extend_from_slice(&chunk);
-    }
+    let body = read_body(payload, config.server.max_body_size).await?;
 
     let events_request: EventsRequest = serde_json::from_slice(&body)
         .map_err(|e| error::ErrorBadRequest(format!("Invalid JSON: {}", e)))?;
 
+    Span::current().record(
+        "message_ids",
+        events_request
+            .events
+            .iter()
+            .map(|event| event.message_id())
+            .collect::<Vec<_>>()
+            .join(","),
+    );
+
     debug!(
         "Received {} events for chain: {}",
         events_request.events.len(),
         chain
     );
 
+    let results = store_events(
+        &chain,
+        &events_request.events,
+        &events_model,
+        &tasks_model,
+        &chains_model,
+        &payloads_model,
+        &tokens_model,
+        &transfers_model,
+        &config,
+    )
+    .await?;
+
+    let response = PostEventResponse { results };
+
+    info!("Responding with: {:?}", response);
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Store the events of a chain and react to them, returning whether each one was accepted
+#[allow(clippy::too_many_arguments)]
+async fn store_events(
+    chain: &str,
+    events: &[Event],
+    events_model: &EventsModel,
+    tasks_model: &TasksModel,
+    chains_model: &ChainsModel,
+    payloads_model: &PayloadsModel,
+    tokens_model: &TokensModel,
+    transfers_model: &TransfersModel,
+    config: &Config,
+) -> Result<Vec<PostEventResult>, Error> {
     let mut results: Vec<PostEventResult> = Vec::new();
 
-    for (index, event) in events_request.events.iter().enumerate() {
-        debug!("Event {}: {:?}", index, event);
+    let chain_is_registered = chains_model
+        .find(chain)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?
+        .is_some();
+
+    for (index, event) in events.iter().enumerate() {
+        debug!("Event {}: {}", index, loggable(event));
+
+        if !chain_is_registered {
+            results.push(PostEventResult {
+                status: "REJECTED".to_string(),
+                index,
+                error: Some(format!("Chain {} is not registered", chain)),
+                retriable: Some(false),
+            });
+            continue;
+        }
 
         let (event_id, event_type_str, timestamp) = event.common_fields();
 
+        // events are untagged, so a malformed event can parse as another type
+        if event_type_str != event.event_type().as_str() {
+            results.push(PostEventResult {
+                status: "REJECTED".to_string(),
+                index,
+                error: Some(format!(
+                    "Fields of event do not match its type {}",
+                    event_type_str
+                )),
+                retriable: Some(false),
+            });
+            continue;
+        }
+
         // Check that no other event with the same ID exists
         let maybe_event_with_same_id = events_model
             .find(event_id)
@@ -487,10 +612,62 @@ async fn post_events(
                 error: None,
                 retriable: None,
             });
-            warn!("Event with same ID already exists: {:?}", event);
+            warn!("Event with same ID already exists: {}", event_id);
             continue;
         }
 
+        // CANNOT_EXECUTE_MESSAGE refers to its EXECUTE task, it is stored under the task's message
+        let message_id = match event {
+            Event::CannotExecuteMessage { task_item_id, .. } => {
+                let task = tasks_model
+                    .find_json(task_item_id)
+                    .await
+                    .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+                match task
+                    .as_ref()
+                    .and_then(|task| task.pointer("/task/message/messageID"))
+                    .and_then(Value::as_str)
+                {
+                    Some(message_id) => message_id.to_string(),
+                    None => {
+                        results.push(PostEventResult {
+                            status: "REJECTED".to_string(),
+                            index,
+                            error: Some(format!("No task {} with a message", task_item_id)),
+                            retriable: Some(false),
+                        });
+                        continue;
+                    }
+                }
+            }
+            _ => event.message_id(),
+        };
+
+        // transfers of interchain tokens have to spend a token known on the chain
+        if let Event::ITSInterchainTransfer {
+            token_spent:
+                Amount {
+                    token_id: Some(token_id),
+                    ..
+                },
+            ..
+        } = event
+        {
+            let token = tokens_model
+                .find(token_id, chain)
+                .await
+                .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+            if token.is_none() {
+                results.push(PostEventResult {
+                    status: "REJECTED".to_string(),
+                    index,
+                    error: Some(format!("Unknown token {} on chain {}", token_id, chain)),
+                    retriable: Some(false),
+                });
+                continue;
+            }
+        }
+
         let event_json_str = match serde_json::to_string(event) {
             Ok(json_str) => json_str,
             Err(e) => {
@@ -517,12 +694,52 @@ async fn post_events(
             }
         };
 
+        // the payload of an approved message is looked up by its hash for the EXECUTE task
+        if let Event::MessageApproved { message, .. } = event
+            && decode_payload_hash(&message.payload_hash).is_none()
+        {
+            results.push(PostEventResult {
+                status: "REJECTED".to_string(),
+                index,
+                error: Some(format!("Invalid payloadHash {}", message.payload_hash)),
+                retriable: Some(false),
+            });
+            continue;
+        }
+
+        // destination relayers fetch the payload by its hash when executing the message
+        match verified_call_payload(event) {
+            Ok(Some((hash, call_payload))) => {
+                payloads_model
+                    .upsert(
+                        &hash,
+                        &call_payload,
+                        "application/octet-stream",
+                        payload_expiry(config),
+                    )
+                    .await
+                    .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+                debug!("Stored payload {} of message {}", hash, event.message_id());
+            }
+            Ok(None) => {}
+            Err(e) => {
+                warn!("Rejected event {}: {}", event_id, e);
+                results.push(PostEventResult {
+                    status: "REJECTED".to_string(),
+                    index,
+                    error: Some(e.to_string()),
+                    retriable: Some(false),
+                });
+                continue;
+            }
+        }
+
         if event_type_str == "CALL" || event_type_str == "GAS_CREDIT" {
             handle_call_or_gas_credit_event(
                 event.clone(),
-                &events_model,
-                &tasks_model,
-                &chain,
+                events_model,
+                tasks_model,
+                chain,
                 event_type_str,
             )
             .await
@@ -536,11 +753,28 @@ async fn post_events(
                 parsed_timestamp,
                 event.event_type(),
                 &event_json_str,
-                &event.message_id(),
+                &message_id,
             )
             .await
         {
             Ok(_) => {
+                // only events that were stored issue EXECUTE tasks and change the token registry
+                // and transfer ledger
+                handle_message_approved_event(
+                    event,
+                    events_model,
+                    tasks_model,
+                    payloads_model,
+                    chain,
+                )
+                .await
+                .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+                handle_its_token_event(event, tokens_model, chain)
+                    .await
+                    .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+                handle_app_transfer_event(event, transfers_model, chain)
+                    .await
+                    .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
                 results.push(PostEventResult {
                     status: "ACCEPTED".to_string(),
                     index,
@@ -559,32 +793,37 @@ async fn post_events(
         }
     }
 
-    let response = PostEventResponse { results };
+    for result in &results {
+        METRICS.event_posted(&events[result.index].event_type(), &result.status);
+    }
 
-    info!("Responding with: {:?}", response);
-    Ok(HttpResponse::Ok().json(response))
+    Ok(results)
 }
 
+/// Tasks are validated against their type before being stored, see [`validate_task`]
 #[post("/chains/{chain}/task")]
 async fn post_task(
+    chain: web::Path<String>,
     db: web::Data<TasksModel>,
-    mut payload: web::Payload,
+    chains_model: web::Data<ChainsModel>,
+    config: web::Data<Config>,
+    payload: web::Payload,
 ) -> Result<HttpResponse, Error> {
-    let mut body = web::BytesMut::new();
-    while let Some(chunk) = payload.next().await {
-        let chunk = chunk?;
-        if (body.len() + chunk.len()) > MAX_SIZE {
-            return Err(error::ErrorBadRequest("overflow"));
-        }
-        body.extend_from_slice(&chunk);
-    }
+    let body = read_body(payload, config.server.max_body_size).await?;
 
     let json_value = serde_json::from_slice::<Value>(&body)?;
-    let task = parse_task(&json_value).map_err(|e| error::ErrorBadRequest(e.to_string()))?;
 
-    if matches!(task, Task::Unknown(_)) {
-        return Err(error::ErrorBadRequest("Cannot store unknown tasks"));
-    }
+    let known_chains: Vec<String> = chains_model
+        .get_chains()
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?
+        .into_iter()
+        .map(|chain| chain.name)
+        .collect();
+    let task = match validate_task(&json_value, &chain, &known_chains) {
+        Ok(task) => task,
+        Err(e) => return Ok(HttpResponse::BadRequest().json(e)),
+    };
 
     let common = task.common();
 
@@ -603,26 +842,64 @@ async fn post_task(
     .await
     .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
 
+    METRICS.task_created(&task.kind(), TaskSource::Api);
     info!("task upserted: {:?}", task.id());
 
     Ok(HttpResponse::Ok().json(task))
 }
 
+/// Tasks of the chain after `after`, filtered and paged as by the GMP API, or with a `consumer`
+/// the tasks it leases for `visibilityTimeout` seconds, to be acked before they are handed out again
 #[get("/chains/{chain}/tasks")]
 async fn get_tasks(
     db: web::Data<TasksModel>,
+    config: web::Data<Config>,
     chain: web::Path<String>,
     query: web::Query<HashMap<String, String>>,
 ) -> Result<HttpResponse, Error> {
+    if let Some(consumer) = query.get("consumer") {
+        let visibility_timeout = match query.get("visibilityTimeout") {
+            Some(timeout) => timeout
+                .parse::<u64>()
+                .map_err(|e| error::ErrorBadRequest(format!("Invalid visibilityTimeout: {}", e)))?,
+            None => config.tasks.visibility_timeout,
+        };
+        // a lease of 0 would hand the tasks out again right away
+        if visibility_timeout == 0 || visibility_timeout > MAX_VISIBILITY_TIMEOUT {
+            return Err(error::ErrorBadRequest(format!(
+                "visibilityTimeout must be between 1 and {}",
+                MAX_VISIBILITY_TIMEOUT
+            )));
+        }
+        let tasks = db
+            .claim(
+                &chain,
+                consumer,
+                visibility_timeout,
+                config.tasks.claim_limit,
+            )
+            .await
+            .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+        debug!(
+            "Leased {} tasks to {} for {}s",
+            tasks.len(),
+            consumer,
+            visibility_timeout
+        );
+        return Ok(HttpResponse::Ok().json(serde_json::json!({ "tasks": tasks })));
+    }
+
     let after = query.get("after").map(|s| s.as_str());
-    if after.is_some() {
-        debug!("Requesting tasks after: {:?}", after.unwrap());
+    if let Some(after) = after {
+        debug!("Requesting tasks after: {:?}", after);
     } else {
         debug!("Requesting all tasks");
     }
 
+    let filter = task_filter(&query)?;
     let raw_tasks = db
-        .get_tasks(&chain, after)
+        .get_tasks(&chain, after, &filter)
         .await
         .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
 
@@ -630,98 +907,442 @@ async fn get_tasks(
         "tasks": raw_tasks
     });
 
-    info!("Returning {} tasks", raw_tasks.len());
+    debug!("Returning {} tasks", raw_tasks.len());
 
     Ok(HttpResponse::Ok().json(response))
 }
 
+fn task_filter(query: &HashMap<String, String>) -> Result<TaskFilter, Error> {
+    let timestamp = |name: &str| {
+        query
+            .get(name)
+            .map(|timestamp| {
+                timestamp
+                    .parse::<DateTime<Utc>>()
+                    .map_err(|e| error::ErrorBadRequest(format!("Invalid {}: {}", name, e)))
+            })
+            .transpose()
+    };
+    let number = |name: &str| {
+        query
+            .get(name)
+            .map(|number| {
+                number
+                    .parse::<u32>()
+                    .map(i64::from)
+                    .map_err(|e| error::ErrorBadRequest(format!("Invalid {}: {}", name, e)))
+            })
+            .transpose()
+    };
+
+    Ok(TaskFilter {
+        r#type: query
+            .get("type")
+            .map(|kind| kind.parse::<TaskKind>())
+            .transpose()
+            .map_err(|e| error::ErrorBadRequest(e.to_string()))?,
+        from: timestamp("from")?,
+        to: timestamp("to")?,
+        message_id: query.get("messageID").cloned(),
+        limit: number("limit")?,
+        offset: number("offset")?,
+    })
+}
+
+#[get("/chains/{chain}/tasks/{task_id}")]
+async fn get_task(
+    path: web::Path<(String, String)>,
+    db: web::Data<TasksModel>,
+) -> Result<HttpResponse, Error> {
+    let (chain, task_id) = path.into_inner();
+    let task = db
+        .find_json(&task_id)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?
+        .filter(|task| task.get("chain").and_then(Value::as_str) == Some(chain.as_str()));
+
+    match task {
+        Some(task) => Ok(HttpResponse::Ok().json(task)),
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Task not found"
+        }))),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct AckRequest {
+    #[serde(rename = "consumerID")]
+    consumer_id: String,
+}
+
+/// Complete a task leased to the consumer, so that it is not handed out again
+#[post("/chains/{chain}/tasks/{task_id}/ack")]
+async fn ack_task(
+    path: web::Path<(String, String)>,
+    db: web::Data<TasksModel>,
+    request: web::Json<AckRequest>,
+) -> Result<HttpResponse, Error> {
+    let (chain, task_id) = path.into_inner();
+    let state = db
+        .find_state(&task_id)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?
+        .filter(|state| state.chain == chain);
+    let Some(state) = state else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Task not found"
+        })));
+    };
+
+    let acked = db
+        .ack(&task_id, &request.consumer_id)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+    if !acked {
+        let error = match &state.claimed_by {
+            Some(claimed_by) => format!("Task is leased to {}", claimed_by),
+            None => "Task was never claimed".to_string(),
+        };
+        warn!(
+            "Rejected ack of task {} by {}: {}",
+            task_id, request.consumer_id, error
+        );
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({ "error": error })));
+    }
+
+    info!("Task {} completed by {}", task_id, request.consumer_id);
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "id": task_id,
+        "status": TaskStatus::Completed,
+    })))
+}
+
+#[derive(Deserialize, Debug)]
+struct TaskStatesQuery {
+    chain: Option<String>,
+    status: Option<TaskStatus>,
+}
+
+/// Lease states of the tasks, e.g. `?status=expired` for the tasks a relayer claimed but never
+/// completed
+#[get("/admin/tasks")]
+async fn get_task_states(
+    db: web::Data<TasksModel>,
+    query: web::Query<TaskStatesQuery>,
+) -> Result<HttpResponse, Error> {
+    let states = db
+        .get_task_states(query.chain.as_deref(), query.status)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "tasks": states })))
+}
+
+#[get("/admin/tasks/{task_id}")]
+async fn get_task_state(
+    task_id: web::Path<String>,
+    db: web::Data<TasksModel>,
+) -> Result<HttpResponse, Error> {
+    let state = db
+        .find_state(&task_id)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    match state {
+        Some(state) => Ok(HttpResponse::Ok().json(state)),
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Task not found"
+        }))),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct MessageResponse {
+    #[serde(rename = "messageID")]
+    message_id: String,
+    status: String,
+    failures: Vec<MessageFailure>,
+    events: Vec<Value>,
+    tasks: Vec<Value>,
+}
+
+/// Why a message could not be routed or executed, as its relayer reported it
+#[derive(Serialize, Deserialize, Debug)]
+struct MessageFailure {
+    r#type: String,
+    #[serde(rename = "eventID")]
+    event_id: String,
+    reason: String,
+    details: String,
+}
+
+const FAILURE_EVENT_TYPES: [&str; 3] = [
+    "CANNOT_EXECUTE_MESSAGE_V2",
+    "CANNOT_EXECUTE_MESSAGE",
+    "CANNOT_ROUTE_MESSAGE",
+];
+
+fn message_failures(events: &[Value]) -> Vec<MessageFailure> {
+    let text = |event: &Value, key: &str| event[key].as_str().unwrap_or_default().to_string();
+    events
+        .iter()
+        .filter(|event| FAILURE_EVENT_TYPES.iter().any(|t| event["type"] == *t))
+        .map(|event| MessageFailure {
+            r#type: text(event, "type"),
+            event_id: text(event, "eventID"),
+            reason: text(event, "reason"),
+            details: text(event, "details"),
+        })
+        .collect()
+}
+
+/// Furthest a message got, from its events and tasks: `called`, `verifying`, `proving`,
+/// `approving`, `approved` (by the destination gateway, which issues its EXECUTE task), then
+/// `executed`, `reverted` or `cannot_execute`. `cannot_route` if Amplifier could not route it.
+fn message_status(events: &[Value], tasks: &[Value]) -> &'static str {
+    let has_event = |event_type: &str| events.iter().any(|event| event["type"] == event_type);
+    let has_task = |task_type: &str| tasks.iter().any(|task| task["type"] == task_type);
+
+    if let Some(executed) = events
+        .iter()
+        .find(|event| event["type"] == "MESSAGE_EXECUTED")
+    {
+        return if executed["status"] == "REVERTED" {
+            "reverted"
+        } else {
+            "executed"
+        };
+    }
+    if has_event("CANNOT_EXECUTE_MESSAGE_V2") || has_event("CANNOT_EXECUTE_MESSAGE") {
+        "cannot_execute"
+    } else if has_event("CANNOT_ROUTE_MESSAGE") {
+        "cannot_route"
+    } else if has_event("MESSAGE_APPROVED") || has_task("EXECUTE") {
+        "approved"
+    } else if has_task("GATEWAY_TX") {
+        "approving"
+    } else if has_task("CONSTRUCT_PROOF") {
+        "proving"
+    } else if has_task("VERIFY") {
+        "verifying"
+    } else {
+        "called"
+    }
+}
+
+#[get("/messages/{message_id}")]
+async fn get_message(
+    message_id: web::Path<String>,
+    events_model: web::Data<EventsModel>,
+    tasks_model: web::Data<TasksModel>,
+) -> Result<HttpResponse, Error> {
+    let events = events_model
+        .get_events_by_message_id(&message_id)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+    let tasks = tasks_model
+        .get_tasks_by_message_id(&message_id)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    if events.is_empty() && tasks.is_empty() {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Message not found"
+        })));
+    }
+
+    debug!(
+        "Message {} has {} events and {} tasks",
+        message_id,
+        events.len(),
+        tasks.len()
+    );
+
+    Ok(HttpResponse::Ok().json(MessageResponse {
+        message_id: message_id.into_inner(),
+        status: message_status(&events, &tasks).to_string(),
+        failures: message_failures(&events),
+        events,
+        tasks,
+    }))
+}
+
+#[derive(Serialize, Debug)]
+struct PayloadMetadataResponse {
+    #[serde(flatten)]
+    metadata: PayloadMetadata,
+    #[serde(rename = "messageIDs")]
+    message_ids: Vec<String>,
+}
+
 #[post("/payloads")]
 async fn post_payloads(
+    req: HttpRequest,
     payloads_model: web::Data<PayloadsModel>,
-    mut payload: web::Payload,
+    config: web::Data<Config>,
+    payload: web::Payload,
 ) -> Result<HttpResponse, Error> {
-    let mut body = web::BytesMut::new();
-    while let Some(chunk) = payload.next().await {
-        let chunk = chunk?;
-        if (body.len() + chunk.len()) > MAX_SIZE {
-            return Err(error::ErrorBadRequest("overflow"));
-        }
-        body.extend_from_slice(&chunk);
-    }
+    let body = read_body(payload, config.payloads.max_size).await?;
 
     let mut hasher = Keccak256::new();
     hasher.update(&body);
     let result = hasher.finalize();
     let hash = format!("0x{}", hex::encode(result));
 
-    let payload_str = general_purpose::STANDARD.encode(&body);
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/octet-stream");
+    let expires_at = payload_expiry(&config);
 
+    let purged = payloads_model
+        .purge_expired()
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+    if purged > 0 {
+        debug!("Purged {} expired payloads", purged);
+    }
     payloads_model
-        .upsert(&hash, &payload_str)
+        .upsert(&hash, &body, content_type, expires_at)
         .await
         .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
 
     let response = StorePayloadResult { keccak256: hash };
 
-    info!("Stored payload with hash: {}", response.keccak256);
+    info!(
+        "Stored payload of {} bytes with hash: {}",
+        body.len(),
+        response.keccak256
+    );
     Ok(HttpResponse::Ok().json(response))
 }
 
+/// When a payload stored now stops being served, given `payloads.ttl`
+fn payload_expiry(config: &Config) -> Option<DateTime<Utc>> {
+    (config.payloads.ttl > 0)
+        .then(|| Utc::now() + chrono::Duration::seconds(config.payloads.ttl as i64))
+}
+
+fn payload_not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({
+        "error": "Payload not found"
+    }))
+}
+
+/// Streams the payload in chunks read from the database, so that large payloads are never held
+/// in memory as a whole
 #[get("/payloads/0x{hash}")]
 async fn get_payload(
     hash: web::Path<String>,
     payloads_model: web::Data<PayloadsModel>,
 ) -> Result<HttpResponse, Error> {
-    let full_hash = format!("0x{}", hash);
-    let payload_base64 = payloads_model
-        .find(&full_hash)
+    let full_hash = format!("0x{}", hash.to_lowercase());
+    let Some(metadata) = payloads_model
+        .metadata(&full_hash)
         .await
-        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?
+    else {
+        return Ok(payload_not_found());
+    };
 
-    match payload_base64 {
-        Some(payload_str) => {
-            let payload_bytes = general_purpose::STANDARD
-                .decode(&payload_str)
-                .map_err(|e| {
-                    error::ErrorInternalServerError(format!("Failed to decode payload: {}", e))
-                })?;
-
-            Ok(HttpResponse::Ok()
-                .content_type("application/octet-stream")
-                .body(payload_bytes))
+    let size = metadata.size;
+    let model = payloads_model.into_inner();
+    let chunks = futures::stream::try_unfold(0i64, move |offset| {
+        let model = model.clone();
+        let full_hash = full_hash.clone();
+        async move {
+            if offset >= size {
+                return Ok(None);
+            }
+            let chunk = model
+                .read_chunk(&full_hash, offset, PAYLOAD_CHUNK_SIZE)
+                .await
+                .and_then(|chunk| {
+                    chunk.filter(|chunk| !chunk.is_empty()).ok_or_else(|| {
+                        anyhow::anyhow!("Payload {} expired while reading", full_hash)
+                    })
+                })
+                .inspect_err(|e| error!("Failed to stream payload: {}", e))?;
+            let next = offset + chunk.len() as i64;
+            Ok::<_, anyhow::Error>(Some((web::Bytes::from(chunk), next)))
         }
-        None => Ok(HttpResponse::NotFound().json(serde_json::json!({
-            "error": "Payload not found"
-        }))),
-    }
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type(metadata.content_type)
+        .no_chunking(size as u64)
+        .streaming(chunks))
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct QueryPostResponse {
-    #[serde(rename = "queryID")]
-    query_id: String,
+/// Whether a payload is stored, with its size and content type in the headers
+#[route("/payloads/0x{hash}", method = "HEAD")]
+async fn head_payload(
+    hash: web::Path<String>,
+    payloads_model: web::Data<PayloadsModel>,
+) -> Result<HttpResponse, Error> {
+    let full_hash = format!("0x{}", hash.to_lowercase());
+    let metadata = payloads_model
+        .metadata(&full_hash)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    Ok(match metadata {
+        Some(metadata) => HttpResponse::Ok()
+            .content_type(metadata.content_type)
+            .insert_header((
+                header::LAST_MODIFIED,
+                metadata
+                    .created_at
+                    .format("%a, %d %b %Y %H:%M:%S GMT")
+                    .to_string(),
+            ))
+            .no_chunking(metadata.size as u64)
+            .streaming(futures::stream::empty::<Result<web::Bytes, Error>>()),
+        None => HttpResponse::NotFound().finish(),
+    })
+}
+
+#[get("/payloads/0x{hash}/metadata")]
+async fn get_payload_metadata(
+    hash: web::Path<String>,
+    payloads_model: web::Data<PayloadsModel>,
+    events_model: web::Data<EventsModel>,
+) -> Result<HttpResponse, Error> {
+    let full_hash = format!("0x{}", hash.to_lowercase());
+    let Some(metadata) = payloads_model
+        .metadata(&full_hash)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?
+    else {
+        return Ok(payload_not_found());
+    };
+    let message_ids = events_model
+        .find_message_ids_by_payload_hash(&full_hash)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    Ok(HttpResponse::Ok().json(PayloadMetadataResponse {
+        metadata,
+        message_ids,
+    }))
 }
 
 #[post("/contracts/{contract_address}/queries")]
 async fn post_queries(
     contract_address: web::Path<String>,
-    mut payload: web::Payload,
+    config: web::Data<Config>,
+    payload: web::Payload,
 ) -> Result<HttpResponse, Error> {
-    let mut body = web::BytesMut::new();
-    while let Some(chunk) = payload.next().await {
-        let chunk = chunk?;
-        if (body.len() + chunk.len()) > MAX_SIZE {
-            return Err(error::ErrorBadRequest("overflow"));
-        }
-        body.extend_from_slice(&chunk);
-    }
+    let body = read_body(payload, config.server.max_body_size).await?;
 
     let query_request: Value = serde_json::from_slice(&body)
         .map_err(|e| error::ErrorBadRequest(format!("Invalid query request: {}", e)))?;
 
     info!(
-        "Query request: {:?} to contract: {}",
-        query_request, contract_address
+        "Query request: {} to contract: {}",
+        loggable(&query_request),
+        contract_address
     );
 
     let query_json = serde_json::to_string(&query_request).map_err(|e| {
@@ -736,17 +1357,16 @@ async fn post_queries(
             --node {} \
             --chain-id {} \
             --output json",
-        contract_address,
-        query_json,
-        env::var("AXELAR_RPC").unwrap(),
-        env::var("CHAIN_ID").unwrap()
+        contract_address, query_json, config.axelar.rpc, config.axelar.chain_id
     );
 
+    let axelard_started_at = Instant::now();
     let axelard_query_result = tokio::process::Command::new("bash")
         .arg("-c")
         .arg(axelard_query_command)
         .output()
         .await;
+    METRICS.axelard_call("query_contract", axelard_started_at);
 
     match axelard_query_result {
         Ok(output) => {
@@ -811,46 +1431,589 @@ async fn post_queries(
     }
 }
 
+#[get("/admin/chains")]
+async fn get_chains(chains_model: web::Data<ChainsModel>) -> Result<HttpResponse, Error> {
+    let chains = chains_model
+        .get_chains()
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "chains": chains })))
+}
+
+#[get("/admin/chains/{chain}")]
+async fn get_chain(
+    chain: web::Path<String>,
+    chains_model: web::Data<ChainsModel>,
+) -> Result<HttpResponse, Error> {
+    let maybe_chain = chains_model
+        .find(&chain)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    match maybe_chain {
+        Some(chain) => Ok(HttpResponse::Ok().json(chain)),
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Chain not found"
+        }))),
+    }
+}
+
+#[put("/admin/chains/{chain}")]
+async fn put_chain(
+    chain: web::Path<String>,
+    chains_model: web::Data<ChainsModel>,
+    config: web::Data<Config>,
+    payload: web::Payload,
+) -> Result<HttpResponse, Error> {
+    let body = read_body(payload, config.server.max_body_size).await?;
+
+    let contracts: ChainContracts = serde_json::from_slice(&body)
+        .map_err(|e| error::ErrorBadRequest(format!("Invalid chain contracts: {}", e)))?;
+
+    if [
+        &contracts.gateway,
+        &contracts.voting_verifier,
+        &contracts.multisig_prover,
+    ]
+    .iter()
+    .any(|address| address.trim().is_empty())
+    {
+        return Err(error::ErrorBadRequest(
+            "Contract addresses must not be empty",
+        ));
+    }
+
+    chains_model
+        .upsert(&chain, &contracts)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    info!("Registered chain {}: {:?}", chain, contracts);
+
+    Ok(HttpResponse::Ok().json(Chain {
+        name: chain.into_inner(),
+        contracts,
+    }))
+}
+
+#[delete("/admin/chains/{chain}")]
+async fn delete_chain(
+    chain: web::Path<String>,
+    chains_model: web::Data<ChainsModel>,
+) -> Result<HttpResponse, Error> {
+    let deleted = chains_model
+        .delete(&chain)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    if !deleted {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Chain not found"
+        })));
+    }
+    info!("Removed chain {}", chain);
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Debug, Deserialize)]
+struct TokensQuery {
+    #[serde(rename = "tokenID")]
+    token_id: Option<String>,
+    chain: Option<String>,
+}
+
+#[get("/admin/tokens")]
+async fn get_tokens(
+    tokens_model: web::Data<TokensModel>,
+    query: web::Query<TokensQuery>,
+) -> Result<HttpResponse, Error> {
+    let tokens = tokens_model
+        .get_tokens(query.token_id.as_deref(), query.chain.as_deref())
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "tokens": tokens })))
+}
+
+/// The token on every chain it is known on
+#[get("/admin/tokens/{token_id}")]
+async fn get_token(
+    token_id: web::Path<String>,
+    tokens_model: web::Data<TokensModel>,
+) -> Result<HttpResponse, Error> {
+    let tokens = tokens_model
+        .get_tokens(Some(&token_id), None)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    if tokens.is_empty() {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Token not found"
+        })));
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "tokens": tokens })))
+}
+
+/// What can be known about a token registered by hand
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TokenDetails {
+    address: Option<String>,
+    name: Option<String>,
+    symbol: Option<String>,
+    decimals: Option<u8>,
+    deployer: Option<String>,
+    #[serde(rename = "linkedTokens", default)]
+    linked_tokens: Vec<LinkedToken>,
+}
+
+/// Register a token without its deployment events, e.g. one deployed before the relayer
+/// under test was started
+#[put("/admin/tokens/{token_id}/{chain}")]
+async fn put_token(
+    path: web::Path<(String, String)>,
+    tokens_model: web::Data<TokensModel>,
+    config: web::Data<Config>,
+    payload: web::Payload,
+) -> Result<HttpResponse, Error> {
+    let (token_id, chain) = path.into_inner();
+    let body = read_body(payload, config.server.max_body_size).await?;
+
+    let details: TokenDetails = if body.is_empty() {
+        TokenDetails::default()
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| error::ErrorBadRequest(format!("Invalid token: {}", e)))?
+    };
+
+    let token = Token {
+        address: details.address,
+        name: details.name,
+        symbol: details.symbol,
+        decimals: details.decimals.map(i32::from),
+        deployer: details.deployer,
+        linked_tokens: details.linked_tokens,
+        ..Token::new(&token_id, &chain)
+    };
+    tokens_model
+        .upsert(&token)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    info!("Registered token {} on {}", token_id, chain);
+
+    let token = tokens_model
+        .find(&token_id, &chain)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+    Ok(HttpResponse::Ok().json(token))
+}
+
+#[delete("/admin/tokens/{token_id}")]
+async fn delete_token(
+    token_id: web::Path<String>,
+    tokens_model: web::Data<TokensModel>,
+) -> Result<HttpResponse, Error> {
+    tokens_model
+        .delete(&token_id)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    info!("Removed token {}", token_id);
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// A transfer of the ledger with its status as of the request
+#[derive(Serialize, Debug)]
+struct TransferResponse {
+    #[serde(rename = "messageID")]
+    message_id: String,
+    #[serde(rename = "sourceChain")]
+    source_chain: String,
+    #[serde(rename = "destinationChain")]
+    destination_chain: String,
+    status: TransferStatus,
+    mismatches: Vec<TransferMismatch>,
+    sent: Option<TransferLeg>,
+    received: Option<TransferLeg>,
+}
+
+impl TransferResponse {
+    fn new(transfer: Transfer, config: &Config) -> Self {
+        let arrival_timeout = (config.transfers.arrival_timeout > 0)
+            .then(|| chrono::Duration::seconds(config.transfers.arrival_timeout as i64));
+        Self {
+            status: transfer.status(arrival_timeout, Utc::now()),
+            mismatches: transfer.mismatches(),
+            message_id: transfer.message_id,
+            source_chain: transfer.source_chain,
+            destination_chain: transfer.destination_chain,
+            sent: transfer.sent,
+            received: transfer.received,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TransfersQuery {
+    chain: Option<String>,
+    status: Option<TransferStatus>,
+}
+
+/// The transfer ledger, optionally only the transfers from or to a chain or with a status
+#[get("/admin/transfers")]
+async fn get_transfers(
+    transfers_model: web::Data<TransfersModel>,
+    config: web::Data<Config>,
+    query: web::Query<TransfersQuery>,
+) -> Result<HttpResponse, Error> {
+    let transfers: Vec<TransferResponse> = transfers_model
+        .get_transfers(query.chain.as_deref())
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?
+        .into_iter()
+        .map(|transfer| TransferResponse::new(transfer, &config))
+        .filter(|transfer| query.status.as_ref().is_none_or(|s| *s == transfer.status))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "transfers": transfers })))
+}
+
+#[get("/admin/transfers/{message_id}")]
+async fn get_transfer(
+    message_id: web::Path<String>,
+    transfers_model: web::Data<TransfersModel>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, Error> {
+    let maybe_transfer = transfers_model
+        .find(&message_id)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    match maybe_transfer {
+        Some(transfer) => Ok(HttpResponse::Ok().json(TransferResponse::new(transfer, &config))),
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Transfer not found"
+        }))),
+    }
+}
+
+/// Most items `POST /admin/chains/{chain}/generate` injects at once
+const MAX_GENERATED_ITEMS: usize = 10_000;
+/// Generated events are handled this many at a time, so that they are not all held in memory
+const GENERATED_EVENTS_BATCH_SIZE: usize = 100;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum GeneratedKind {
+    Tasks,
+    Events,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenerateRequest {
+    kind: GeneratedKind,
+    count: usize,
+    /// Random if not given, the response has the one used to reproduce the items
+    seed: Option<u64>,
+    #[serde(rename = "edgeCases", default)]
+    edge_cases: bool,
+}
+
+/// Inject random tasks or events into a chain, to fuzz its relayer. Tasks are stored as they
+/// are, events go through the same handling as posted ones.
+#[post("/admin/chains/{chain}/generate")]
+#[allow(clippy::too_many_arguments)]
+async fn generate(
+    chain: web::Path<String>,
+    events_model: web::Data<EventsModel>,
+    tasks_model: web::Data<TasksModel>,
+    chains_model: web::Data<ChainsModel>,
+    payloads_model: web::Data<PayloadsModel>,
+    tokens_model: web::Data<TokensModel>,
+    transfers_model: web::Data<TransfersModel>,
+    config: web::Data<Config>,
+    request: web::Json<GenerateRequest>,
+) -> Result<HttpResponse, Error> {
+    if request.count == 0 || request.count > MAX_GENERATED_ITEMS {
+        return Err(error::ErrorBadRequest(format!(
+            "count must be between 1 and {}",
+            MAX_GENERATED_ITEMS
+        )));
+    }
+    let seed = request.seed.unwrap_or_else(rand::random);
+    let mut generator =
+        Generator::new(&chain, seed, Utc::now()).with_edge_cases(request.edge_cases);
+
+    if request.kind == GeneratedKind::Events {
+        let mut ids = Vec::new();
+        let mut results = Vec::new();
+        while ids.len() < request.count {
+            let batch_size = GENERATED_EVENTS_BATCH_SIZE.min(request.count - ids.len());
+            let events: Vec<Event> = (0..batch_size).map(|_| generator.event()).collect();
+            let batch_results = store_events(
+                &chain,
+                &events,
+                &events_model,
+                &tasks_model,
+                &chains_model,
+                &payloads_model,
+                &tokens_model,
+                &transfers_model,
+                &config,
+            )
+            .await?;
+            // results are indexed within their batch
+            let offset = ids.len();
+            results.extend(batch_results.into_iter().map(|result| PostEventResult {
+                index: offset + result.index,
+                ..result
+            }));
+            ids.extend(
+                events
+                    .iter()
+                    .map(|event| event.common_fields().0.to_string()),
+            );
+        }
+        info!(
+            "Generated {} events for {} with seed {}",
+            ids.len(),
+            chain,
+            seed
+        );
+
+        return Ok(HttpResponse::Ok().json(serde_json::json!({
+            "seed": seed,
+            "ids": ids,
+            "results": results,
+        })));
+    }
+
+    let mut ids = Vec::new();
+    for _ in 0..request.count {
+        let task = generator.task();
+        let common = task.common();
+        let timestamp = common
+            .timestamp
+            .parse::<DateTime<Utc>>()
+            .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+        let task_json = task
+            .to_json()
+            .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+        tasks_model
+            .upsert(
+                &common.id,
+                &chain,
+                timestamp,
+                task.kind(),
+                Some(&task_json.to_string()),
+            )
+            .await
+            .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+        METRICS.task_created(&task.kind(), TaskSource::Generator);
+        ids.push(common.id.clone());
+    }
+    info!(
+        "Generated {} tasks for {} with seed {}",
+        ids.len(),
+        chain,
+        seed
+    );
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "seed": seed, "ids": ids })))
+}
+
+#[get("/admin/dead-letters")]
+async fn get_dead_letters(
+    dead_letters_model: web::Data<DeadLettersModel>,
+) -> Result<HttpResponse, Error> {
+    let dead_letters = dead_letters_model
+        .get_dead_letters()
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "deadLetters": dead_letters })))
+}
+
+#[get("/admin/dead-letters/{id}")]
+async fn get_dead_letter(
+    id: web::Path<String>,
+    dead_letters_model: web::Data<DeadLettersModel>,
+) -> Result<HttpResponse, Error> {
+    let maybe_dead_letter = dead_letters_model
+        .find(&id)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    match maybe_dead_letter {
+        Some(dead_letter) => Ok(HttpResponse::Ok().json(dead_letter)),
+        None => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Dead letter not found"
+        }))),
+    }
+}
+
+/// Put a dead-lettered item back on the queue with a fresh attempt counter
+#[post("/admin/dead-letters/{id}/replay")]
+async fn replay_dead_letter(
+    id: web::Path<String>,
+    dead_letters_model: web::Data<DeadLettersModel>,
+    queue: web::Data<dyn QueueTrait>,
+) -> Result<HttpResponse, Error> {
+    let maybe_dead_letter = dead_letters_model
+        .find(&id)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+    let Some(dead_letter) = maybe_dead_letter else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Dead letter not found"
+        })));
+    };
+
+    let mut item = dead_letter.item;
+    *item.retry_mut() = RetryInfo::default();
+    queue
+        .publish(&item)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+    dead_letters_model
+        .delete(&id)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    info!("Replayed dead letter {}", id);
+
+    Ok(HttpResponse::Ok().json(item))
+}
+
+#[delete("/admin/dead-letters/{id}")]
+async fn delete_dead_letter(
+    id: web::Path<String>,
+    dead_letters_model: web::Data<DeadLettersModel>,
+) -> Result<HttpResponse, Error> {
+    let deleted = dead_letters_model
+        .delete(&id)
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    if !deleted {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "error": "Dead letter not found"
+        })));
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[delete("/admin/dead-letters")]
+async fn purge_dead_letters(
+    dead_letters_model: web::Data<DeadLettersModel>,
+) -> Result<HttpResponse, Error> {
+    let purged = dead_letters_model
+        .purge()
+        .await
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+
+    info!("Purged {} dead letters", purged);
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "purged": purged })))
+}
+
 impl Server {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        port: u16,
-        address: String,
+        config: Config,
         tasks_model: TasksModel,
         events_model: EventsModel,
         broadcasts_model: BroadcastsModel,
         payloads_model: PayloadsModel,
-        queue: LapinConnection,
+        chains_model: ChainsModel,
+        dead_letters_model: DeadLettersModel,
+        tokens_model: TokensModel,
+        transfers_model: TransfersModel,
+        queue: Arc<dyn QueueTrait>,
     ) -> Self {
         Self {
-            port,
-            address,
+            config,
             tasks_model,
             events_model,
             broadcasts_model,
             payloads_model,
+            chains_model,
+            dead_letters_model,
+            tokens_model,
+            transfers_model,
             queue,
         }
     }
 
     pub async fn run(self) -> anyhow::Result<()> {
-        let addr = format!("{}:{}", self.address, self.port);
+        let addr = format!("{}:{}", self.config.server.address, self.config.server.port);
+        let shutdown_timeout = self.config.server.shutdown_timeout;
+        let readiness = Readiness::new(
+            self.tasks_model.clone(),
+            self.queue.clone(),
+            self.config.axelar.rpc.clone(),
+        );
+        let rules = web::Data::new(Rules::new(&self.config.rules));
 
         HttpServer::new(move || {
             App::new()
+                .wrap(middleware::from_fn(track_requests))
+                .wrap(middleware::from_fn(trace_requests))
+                .app_data(web::Data::new(readiness.clone()))
+                .app_data(web::Data::new(self.config.clone()))
                 .app_data(web::Data::new(self.tasks_model.clone()))
                 .app_data(web::Data::new(self.events_model.clone()))
                 .app_data(web::Data::new(self.broadcasts_model.clone()))
                 .app_data(web::Data::new(self.payloads_model.clone()))
-                .app_data(web::Data::new(self.queue.clone()))
+                .app_data(web::Data::new(self.chains_model.clone()))
+                .app_data(web::Data::new(self.dead_letters_model.clone()))
+                .app_data(web::Data::new(self.tokens_model.clone()))
+                .app_data(web::Data::new(self.transfers_model.clone()))
+                .app_data(web::Data::from(self.queue.clone()))
+                .app_data(rules.clone())
+                .service(healthz)
+                .service(readyz)
+                .service(metrics)
                 .service(get_tasks)
+                .service(get_task)
+                .service(ack_task)
+                .service(get_task_states)
+                .service(get_task_state)
                 .service(post_task)
                 .service(address_broadcast)
                 .service(get_broadcast)
                 .service(post_events)
                 .service(post_payloads)
                 .service(get_payload)
+                .service(head_payload)
+                .service(get_payload_metadata)
                 .service(post_queries)
+                .service(get_message)
+                .service(get_chains)
+                .service(get_chain)
+                .service(put_chain)
+                .service(delete_chain)
+                .service(generate)
+                .service(get_tokens)
+                .service(get_token)
+                .service(put_token)
+                .service(delete_token)
+                .service(get_transfers)
+                .service(get_transfer)
+                .service(get_dead_letters)
+                .service(get_dead_letter)
+                .service(replay_dead_letter)
+                .service(delete_dead_letter)
+                .service(purge_dead_letters)
         })
+        // on SIGTERM/SIGINT stop accepting connections and let in-flight requests, such as
+        // broadcasts waiting for axelard, finish
+        .shutdown_timeout(shutdown_timeout)
         .bind(addr)?
         .run()
         .await
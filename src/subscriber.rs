@@ -1,226 +1,1017 @@
 use crate::{
+    config::{Config, SubscriberConfig},
     gmp_types::{
-        CommonTaskFields, EventAttribute, GatewayTxTask, GatewayTxTaskFields, ReactToWasmEventTask,
-        ReactToWasmEventTaskFields, WasmEvent,
+        CommonTaskFields, ConstructProofTask, ConstructProofTaskFields, EventAttribute,
+        GatewayTxTask, GatewayTxTaskFields, GatewayV2Message, QuorumReachedEvent,
+        ReactToExpiredSigningSessionTask, ReactToExpiredSigningSessionTaskFields,
+        ReactToRetriablePollTask, ReactToRetriablePollTaskFields, ReactToWasmEventTask,
+        ReactToWasmEventTaskFields, TaskKind, VerificationStatus, WasmEvent,
     },
-    models::tasks::TasksModel,
-    queue::{ConstructProofItem, QueueItem, QueueTrait, VerifyMessagesItem},
+    indexer::{self, Indexer},
+    logging::loggable,
+    metrics::{METRICS, TaskSource},
+    models::{
+        broadcasts::BroadcastsModel,
+        chains::ChainsModel,
+        dead_letters::DeadLettersModel,
+        payloads::PayloadsModel,
+        tasks::TasksModel,
+        wasm_events::{IndexedEvent, WasmEventsModel},
+    },
+    queue::{
+        ConstructProofItem, Delivery, QueueItem, QueueTrait, VerifyMessagesItem, WasmEventItem,
+    },
+    rules::{EventRule, RuleTask, Rules},
+    telemetry::extract_context,
+    watcher::EventWatcher,
 };
 use base64::{Engine as _, engine::general_purpose};
 use chrono::{DateTime, Utc};
-use futures::StreamExt;
-use lapin::{
-    Consumer,
-    options::{BasicAckOptions, BasicNackOptions},
-};
+use futures::{StreamExt, stream::FuturesUnordered};
 use serde_json::Value;
 use std::collections::HashMap;
-use tracing::{debug, error, info, warn};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::{Instrument, debug, error, info, info_span, warn};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Keccak256 of an empty payload, the hash of messages that carry none
+const EMPTY_PAYLOAD_HASH: &str = "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+
+/// How often and how fast a failed queue item is retried
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &SubscriberConfig) -> Self {
+        Self {
+            max_attempts: config.max_attempts,
+            initial_delay: Duration::from_secs(config.retry_initial_delay),
+            max_delay: Duration::from_secs(config.retry_max_delay),
+        }
+    }
 
-pub struct Subscriber<Q: QueueTrait> {
-    queue: Q,
+    /// Delay before retrying an item that failed `attempts` times, doubling from the initial delay
+    pub fn delay(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+/// How many queue items the subscriber handles at a time and how long each may take
+#[derive(Clone, Debug)]
+pub struct RunOptions {
+    pub workers: usize,
+    pub prefetch: u16,
+    pub job_timeout: Duration,
+    pub shutdown_timeout: Duration,
+}
+
+impl RunOptions {
+    pub fn from_config(config: &SubscriberConfig) -> Self {
+        Self {
+            workers: config.workers,
+            prefetch: config.prefetch,
+            job_timeout: Duration::from_secs(config.job_timeout),
+            shutdown_timeout: Duration::from_secs(config.shutdown_timeout),
+        }
+    }
+}
+
+/// Start the subscriber on `queue`, with the indexer and the event watcher if the config enables
+/// them. All of them stop once `shutdown` turns true, the subscriber after draining its jobs.
+pub async fn spawn_workers(
+    config: &Config,
+    queue: Arc<dyn QueueTrait>,
+    shutdown: watch::Receiver<bool>,
+) -> Result<JoinHandle<Result<(), anyhow::Error>>, anyhow::Error> {
+    let chains = ChainsModel::new(&config.database.url).await?;
+    let rules = Rules::new(&config.rules);
+    let mut subscriber = Subscriber::new(
+        queue,
+        TasksModel::new(&config.database.url).await?,
+        chains.clone(),
+        BroadcastsModel::new(&config.database.url).await?,
+        DeadLettersModel::new(&config.database.url).await?,
+        config.axelar.rpc.clone(),
+        RetryPolicy::from_config(&config.subscriber),
+    )
+    .with_rules(rules.clone())
+    .with_payloads(PayloadsModel::new(&config.database.url).await?);
+    if config.indexer.enabled {
+        let wasm_events = WasmEventsModel::new(&config.database.url).await?;
+        Indexer::new(
+            &config.axelar.rpc,
+            chains.clone(),
+            wasm_events.clone(),
+            rules.clone(),
+            &config.indexer,
+        )
+        .spawn(shutdown.clone());
+        subscriber = subscriber.with_index(wasm_events);
+    }
+    if config.subscriber.websocket {
+        let watcher = EventWatcher::new(
+            &config.axelar.rpc,
+            &config.axelar.multisig_contract,
+            chains,
+            rules,
+            Duration::from_secs(config.subscriber.websocket_wait),
+        );
+        subscriber = subscriber.with_watcher(watcher.spawn(shutdown.clone()));
+    }
+
+    let options = RunOptions::from_config(&config.subscriber);
+    Ok(tokio::spawn(async move {
+        subscriber.run(shutdown, options).await
+    }))
+}
+
+pub struct Subscriber {
+    queue: Arc<dyn QueueTrait>,
     database: TasksModel,
+    chains: ChainsModel,
+    broadcasts: BroadcastsModel,
+    dead_letters: DeadLettersModel,
     rpc: String,
+    http_client: reqwest::Client,
+    retry_policy: RetryPolicy,
+    rules: Rules,
+    watcher: Option<Arc<EventWatcher>>,
+    index: Option<WasmEventsModel>,
+    payloads: Option<PayloadsModel>,
 }
 
-impl<Q: QueueTrait> Subscriber<Q> {
-    pub fn new(queue: Q, database: TasksModel, rpc: String) -> Self {
+impl Subscriber {
+    pub fn new(
+        queue: Arc<dyn QueueTrait>,
+        database: TasksModel,
+        chains: ChainsModel,
+        broadcasts: BroadcastsModel,
+        dead_letters: DeadLettersModel,
+        rpc: String,
+        retry_policy: RetryPolicy,
+    ) -> Self {
         Self {
             queue,
             database,
+            chains,
+            broadcasts,
+            dead_letters,
             rpc,
+            http_client: reqwest::Client::new(),
+            retry_policy,
+            rules: Rules::default(),
+            watcher: None,
+            index: None,
+            payloads: None,
         }
     }
-}
 
-pub enum DesiredEventType {
-    QuorumReached,
-    SigningCompleted,
+    /// Follow the configured rules besides the built-in ones
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Take quorum_reached and signing_completed events from the websocket subscription of
+    /// `watcher`, paging through the transactions only if they do not show up there
+    pub fn with_watcher(mut self, watcher: Arc<EventWatcher>) -> Self {
+        self.watcher = Some(watcher);
+        self
+    }
+
+    /// Look events up in the table filled by the indexer instead of paging through the
+    /// transactions. Events that are not indexed yet fail the attempt, to be retried later.
+    pub fn with_index(mut self, index: WasmEventsModel) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Route verified messages: a CONSTRUCT_PROOF task with the payload stored in `payloads` is
+    /// issued to the destination chain of every message whose poll succeeded on the source chain
+    pub fn with_payloads(mut self, payloads: PayloadsModel) -> Self {
+        self.payloads = Some(payloads);
+        self
+    }
 }
 
-impl DesiredEventType {
-    pub fn event_type_name(&self) -> &str {
-        match self {
-            DesiredEventType::QuorumReached => "wasm-quorum_reached",
-            DesiredEventType::SigningCompleted => "wasm-signing_completed",
+impl Subscriber {
+    /// Consume the queue with up to `options.workers` items handled at a time, until `shutdown`
+    /// turns true. Items that are being handled at that point get `options.shutdown_timeout` to
+    /// finish before they are requeued.
+    pub async fn run(
+        &self,
+        mut shutdown: watch::Receiver<bool>,
+        options: RunOptions,
+    ) -> Result<(), anyhow::Error> {
+        let mut consumer = self.queue.consumer("subscriber", options.prefetch).await?;
+        let drain = shutdown.clone();
+        // every job acks or nacks its own delivery, so they can finish in any order
+        let mut jobs = FuturesUnordered::new();
+
+        let result = loop {
+            tokio::select! {
+                biased;
+                _ = shutdown.wait_for(|stop| *stop) => {
+                    info!("Shutting down subscriber");
+                    break Ok(());
+                }
+                Some(job) = jobs.next(), if !jobs.is_empty() => {
+                    // one failed job must not stop the others, its delivery comes back after the
+                    // lease or when the channel closes
+                    if let Err(e) = job {
+                        error!("Job failed: {}", e);
+                    }
+                }
+                delivery = consumer.next(), if jobs.len() < options.workers => match delivery {
+                    Some(Ok(delivery)) => {
+                        jobs.push(self.work(delivery, &drain, &options));
+                    }
+                    Some(Err(e)) => {
+                        error!("Error: {}", e);
+                    }
+                    None => {
+                        break Err(anyhow::anyhow!("Consumer closed"));
+                    }
+                },
+            }
+        };
+
+        if !jobs.is_empty() {
+            info!("Waiting for {} jobs to finish", jobs.len());
         }
+        while let Some(job) = jobs.next().await {
+            if let Err(e) = job {
+                error!("Job failed: {}", e);
+            }
+        }
+
+        // deliveries the consumer received but did not get to are requeued by the broker
+        if let Err(e) = self.queue.close().await {
+            error!("Failed to close queue: {}", e);
+        }
+        result
     }
 
-    pub fn attribute_name(&self) -> &str {
-        match self {
-            DesiredEventType::QuorumReached => "poll_id",
-            DesiredEventType::SigningCompleted => "session_id",
+    pub async fn work(
+        &self,
+        delivery: Delivery,
+        shutdown: &watch::Receiver<bool>,
+        options: &RunOptions,
+    ) -> Result<(), anyhow::Error> {
+        let item: QueueItem = match serde_json::from_slice(&delivery.data) {
+            Ok(item) => item,
+            Err(e) => {
+                // it would fail the same way on every redelivery
+                error!("Dropping undecodable delivery: {}", e);
+                METRICS.subscriber_job("undecodable", "rejected");
+                return delivery.nack(false).await;
+            }
+        };
+        let kind = item.kind();
+
+        // continue the trace of the broadcast that published the item
+        let span = match &item {
+            QueueItem::VerifyMessages(item) => info_span!(
+                "subscriber.verify_messages",
+                chain = %item.chain,
+                poll_id = %item.poll_id,
+                contract_address = %item.contract_address,
+            ),
+            QueueItem::ConstructProof(item) => info_span!(
+                "subscriber.construct_proof",
+                chain = %item.chain,
+                session_id = %item.session_id,
+                contract_address = %item.contract_address,
+            ),
+            QueueItem::WasmEvent(item) => info_span!(
+                "subscriber.wasm_event",
+                rule = %item.rule,
+                chain = %item.chain,
+                id = %item.id,
+                contract_address = %item.contract_address,
+            ),
+        };
+        span.set_parent(extract_context(&delivery.headers));
+
+        let result = self
+            .process(delivery, item, shutdown, options)
+            .instrument(span)
+            .await;
+        if result.is_err() {
+            METRICS.subscriber_job(kind, "error");
         }
+        result
     }
-}
 
-impl<Q: QueueTrait> Subscriber<Q> {
-    pub async fn run(&mut self) -> Result<(), anyhow::Error> {
-        let mut consumer = self.queue.consumer("subscriber").await?;
-        loop {
-            self.work(&mut consumer).await?;
+    async fn process(
+        &self,
+        delivery: Delivery,
+        item: QueueItem,
+        shutdown: &watch::Receiver<bool>,
+        options: &RunOptions,
+    ) -> Result<(), anyhow::Error> {
+        let handled = tokio::select! {
+            result = tokio::time::timeout(options.job_timeout, self.handle_item(item.clone())) => {
+                Some(result.unwrap_or_else(|_| {
+                    Err(anyhow::anyhow!("Timed out after {:?}", options.job_timeout))
+                }))
+            }
+            _ = Self::drain_deadline(shutdown.clone(), options.shutdown_timeout) => None,
+        };
+
+        let Some(result) = handled else {
+            METRICS.subscriber_job(item.kind(), "interrupted");
+            warn!("Shutdown timeout reached, requeueing item: {:?}", item);
+            delivery.nack(true).await?;
+            return Ok(());
+        };
+
+        METRICS.subscriber_job(
+            item.kind(),
+            if result.is_ok() { "success" } else { "failure" },
+        );
+
+        if let Err(e) = result {
+            error!("Error: {}", e);
+            self.retry_or_dead_letter(item, &e).await?;
         }
+        // a failed item was either republished with a delay or dead-lettered
+        delivery.ack().await?;
+        Ok(())
     }
 
-    pub async fn work(&self, consumer: &mut Consumer) -> Result<(), anyhow::Error> {
-        info!("Waiting for message...");
-        match consumer.next().await {
-            Some(Ok(delivery)) => {
-                let message = String::from_utf8(delivery.data.clone())?;
-                let item: QueueItem = serde_json::from_str(&message)?;
-
-                match item {
-                    QueueItem::VerifyMessages(item) => {
-                        info!("Got verify messages item: {:?}", item);
-                        if let Err(e) = self.handle_verify_messages(item.clone()).await {
-                            error!("Error: {}", e);
-                            debug!("Republishing verify messages item");
-                            delivery
-                                .nack(BasicNackOptions {
-                                    multiple: false,
-                                    requeue: true,
-                                })
-                                .await?;
-                        } else {
-                            delivery.ack(BasicAckOptions::default()).await?;
-                        }
-                    }
-                    QueueItem::ConstructProof(item) => {
-                        info!("Got construct proof item: {:?}", item);
-                        if let Err(e) = self.handle_construct_proof(item.clone()).await {
-                            error!("Error: {}", e);
-                            debug!("Republishing construct proof item");
-                            self.queue
-                                .publish(
-                                    &QueueItem::ConstructProof(item),
-                                    Some(delivery.properties.clone()),
-                                )
-                                .await?;
-                        } else {
-                            delivery.ack(BasicAckOptions::default()).await?;
-                        }
-                    }
-                }
+    /// Republish a failed item after the backoff delay, or move it to the dead letters once it
+    /// used up its attempts
+    async fn retry_or_dead_letter(
+        &self,
+        mut item: QueueItem,
+        error: &anyhow::Error,
+    ) -> Result<(), anyhow::Error> {
+        let retry = item.retry_mut();
+        retry.attempts += 1;
+        let attempts = retry.attempts;
+
+        if attempts >= self.retry_policy.max_attempts {
+            warn!(
+                "Giving up on {} item after {} attempts",
+                item.kind(),
+                attempts
+            );
+            self.dead_letters
+                .insert(&uuid::Uuid::new_v4().to_string(), &item, &error.to_string())
+                .await?;
+            METRICS.subscriber_job(item.kind(), "dead_lettered");
+        } else {
+            let delay = self.retry_policy.delay(attempts);
+            debug!(
+                "Retrying {} item in {:?} (attempt {})",
+                item.kind(),
+                delay,
+                attempts + 1
+            );
+            METRICS.queue_retry(item.kind());
+            self.queue.publish_delayed(&item, delay).await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_item(&self, item: QueueItem) -> Result<(), anyhow::Error> {
+        match item {
+            QueueItem::VerifyMessages(item) => {
+                info!("Got verify messages item: {:?}", item);
+                self.handle_verify_messages(item).await
             }
-            Some(Err(e)) => {
-                error!("Error: {}", e);
+            QueueItem::ConstructProof(item) => {
+                info!("Got construct proof item: {:?}", item);
+                self.handle_construct_proof(item).await
             }
-            None => {
-                error!("Consumer closed");
+            QueueItem::WasmEvent(item) => {
+                info!("Got wasm event item: {:?}", item);
+                self.handle_wasm_event(item).await
             }
         }
-        Ok(())
+    }
+
+    /// Resolve `timeout` after shutdown was requested, never if it is not
+    async fn drain_deadline(mut shutdown: watch::Receiver<bool>, timeout: Duration) {
+        if shutdown.wait_for(|stop| *stop).await.is_err() {
+            // the sender is gone without requesting a shutdown
+            std::future::pending::<()>().await;
+        }
+        tokio::time::sleep(timeout).await;
     }
 
     async fn handle_verify_messages(&self, item: VerifyMessagesItem) -> Result<(), anyhow::Error> {
-        let initial_axelard_query_script_str = format!(
-            "axelard query txs --events 'wasm-quorum_reached._contract_address={}' --node {} --output json --limit 1",
-            item.contract_address, self.rpc
-        );
+        let rule = self.rules.verify_messages();
+        let expired = self.has_expired(item.expires_at).await?;
+        // a poll over several messages reaches a quorum for each of them
+        let quorum_reached_events = self
+            .find_events(
+                rule,
+                &item.contract_address,
+                &item.poll_id,
+                item.broadcast_created_at,
+            )
+            .await?;
+        if quorum_reached_events.is_empty() {
+            if expired {
+                return self.react_to_retriable_poll(item).await;
+            }
+            return Err(anyhow::anyhow!("No quorum reached event found"));
+        }
+        // built before anything is stored, so that a missing payload fails the attempt as a whole
+        let mut construct_proof_tasks = Vec::new();
+        for (quorum_reached_event, event_timestamp, _) in &quorum_reached_events {
+            if let Some(task) = self
+                .construct_proof_task(quorum_reached_event, *event_timestamp)
+                .await?
+            {
+                construct_proof_tasks.push((task, *event_timestamp));
+            }
+        }
 
-        let total_pages =
-            Self::get_total_page_number_from_query(initial_axelard_query_script_str).await?;
+        for (quorum_reached_event, event_timestamp, block_height) in quorum_reached_events {
+            let message = event_attribute(&quorum_reached_event, "content").unwrap_or_default();
+            let task_id = follow_up_task_id(
+                rule,
+                "REACT_TO_WASM_EVENT",
+                &item.contract_address,
+                &format!("{}/{}", item.poll_id, message),
+            );
+            self.react_to_wasm_event(
+                rule,
+                task_id,
+                item.chain.clone(),
+                quorum_reached_event,
+                event_timestamp,
+                block_height,
+                item.broadcast_created_at,
+            )
+            .await?;
+        }
 
-        // request for pages in reverse order to get the latest data first
-        for page in (1..=total_pages).rev() {
-            let axelard_query_script_str = format!(
-                "axelard query txs --events 'wasm-quorum_reached._contract_address={}' --node {} --output json --limit 100 --page {}",
-                item.contract_address, self.rpc, page
+        for (task, event_timestamp) in construct_proof_tasks {
+            let task_json = serde_json::to_string(&task)?;
+            self.database
+                .upsert(
+                    &task.common.id,
+                    &task.common.chain,
+                    event_timestamp,
+                    TaskKind::ConstructProof,
+                    Some(&task_json),
+                )
+                .await?;
+
+            METRICS.task_created(&TaskKind::ConstructProof, TaskSource::Subscriber);
+            info!(
+                "Inserted ConstructProof task with ID {} for message {}",
+                task.common.id, task.task.message.message_id
             );
+        }
+        Ok(())
+    }
 
-            let maybe_quorum_reached_event_fields = Self::get_event_fields_from_script(
-                axelard_query_script_str,
-                DesiredEventType::QuorumReached,
-                item.poll_id.clone(),
+    /// The CONSTRUCT_PROOF task for the destination chain of the message a quorum_reached event
+    /// verified, if it succeeded on the source chain and payloads are routed at all
+    async fn construct_proof_task(
+        &self,
+        quorum_reached_event: &Value,
+        event_timestamp: DateTime<Utc>,
+    ) -> Result<Option<ConstructProofTask>, anyhow::Error> {
+        let Some(payloads) = &self.payloads else {
+            return Ok(None);
+        };
+        let Some((destination_chain, message)) = verified_message(quorum_reached_event) else {
+            return Ok(None);
+        };
+
+        let payload = if message.payload_hash == EMPTY_PAYLOAD_HASH {
+            String::new()
+        } else {
+            payloads
+                .find(&format!("0x{}", message.payload_hash))
+                .await?
+                .map(|payload| general_purpose::STANDARD.encode(payload))
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Payload 0x{} of message {} not found",
+                        message.payload_hash,
+                        message.message_id
+                    )
+                })?
+        };
+
+        Ok(Some(ConstructProofTask {
+            common: CommonTaskFields {
+                // a message verified again, e.g. by a redelivered item, gets the same task
+                id: task_id(&[
+                    "CONSTRUCT_PROOF",
+                    &message.source_chain,
+                    &message.message_id,
+                ]),
+                chain: destination_chain,
+                timestamp: event_timestamp.to_rfc3339(),
+                r#type: "CONSTRUCT_PROOF".to_string(),
+                meta: None,
+            },
+            task: ConstructProofTaskFields { message, payload },
+        }))
+    }
+
+    async fn handle_construct_proof(&self, item: ConstructProofItem) -> Result<(), anyhow::Error> {
+        let rule = self.rules.construct_proof();
+        let expired = self.has_expired(item.expires_at).await?;
+        let Some((signing_completed_event, event_timestamp, _block_height)) = self
+            .find_event(
+                rule,
+                &item.contract_address,
+                &item.session_id,
+                item.broadcast_created_at,
+            )
+            .await?
+        else {
+            if expired {
+                return self.react_to_expired_signing_session(item).await;
+            }
+            return Err(anyhow::anyhow!("No signing completed event found"));
+        };
+
+        info!(
+            "Found signing completed event: {}",
+            loggable(&signing_completed_event)
+        );
+
+        self.gateway_tx(
+            rule,
+            item.chain,
+            &item.contract_address,
+            &item.session_id,
+            event_timestamp,
+            item.broadcast_created_at,
+        )
+        .await
+    }
+
+    /// Follow a rule from the config, which has no expiry handling: the item is retried until the
+    /// follow-up event shows up or it runs out of attempts
+    async fn handle_wasm_event(&self, item: WasmEventItem) -> Result<(), anyhow::Error> {
+        let rule = self
+            .rules
+            .get(&item.rule)
+            .ok_or_else(|| anyhow::anyhow!("Rule {} is not configured", item.rule))?;
+        let (event, event_timestamp, block_height) = self
+            .find_event(
+                rule,
+                &item.contract_address,
+                &item.id,
                 item.broadcast_created_at,
             )
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No {} event found", rule.follow_up_event))?;
+
+        match rule.task {
+            RuleTask::ReactToWasmEvent => {
+                self.react_to_wasm_event(
+                    rule,
+                    follow_up_task_id(
+                        rule,
+                        "REACT_TO_WASM_EVENT",
+                        &item.contract_address,
+                        &item.id,
+                    ),
+                    item.chain,
+                    event,
+                    event_timestamp,
+                    block_height,
+                    item.broadcast_created_at,
+                )
+                .await
+            }
+            RuleTask::GatewayTx => {
+                info!("Found {} event: {}", rule.follow_up_event, loggable(&event));
+                self.gateway_tx(
+                    rule,
+                    item.chain,
+                    &item.contract_address,
+                    &item.id,
+                    event_timestamp,
+                    item.broadcast_created_at,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Store a REACT_TO_WASM_EVENT task with the follow-up event of `rule`
+    #[allow(clippy::too_many_arguments)]
+    async fn react_to_wasm_event(
+        &self,
+        rule: &EventRule,
+        task_id: String,
+        chain: String,
+        event: Value,
+        event_timestamp: DateTime<Utc>,
+        block_height: String,
+        broadcast_created_at: DateTime<Utc>,
+    ) -> Result<(), anyhow::Error> {
+        info!("Found {} event: {}", rule.follow_up_event, loggable(&event));
+
+        let mut attributes = Vec::new();
+        if let Some(attrs) = event.get("attributes").and_then(|v| v.as_array()) {
+            for attr in attrs {
+                if let (Some(key), Some(value)) = (
+                    attr.get("key").and_then(|v| v.as_str()),
+                    attr.get("value").and_then(|v| v.as_str()),
+                ) {
+                    attributes.push(EventAttribute {
+                        key: key.to_string(),
+                        value: value.to_string(),
+                    });
+                }
+            }
+        }
+
+        let react_to_wasm_event_task = ReactToWasmEventTask {
+            common: CommonTaskFields {
+                id: task_id,
+                chain,
+                timestamp: event_timestamp.to_rfc3339(),
+                r#type: "REACT_TO_WASM_EVENT".to_string(),
+                meta: None,
+            },
+            task: ReactToWasmEventTaskFields {
+                event: WasmEvent {
+                    attributes,
+                    r#type: rule.follow_up_event.clone(),
+                },
+                height: block_height.parse::<u64>().unwrap_or(0),
+            },
+        };
+
+        let task_json = serde_json::to_string(&react_to_wasm_event_task)?;
+
+        self.database
+            .upsert(
+                &react_to_wasm_event_task.common.id,
+                &react_to_wasm_event_task.common.chain,
+                event_timestamp,
+                TaskKind::ReactToWasmEvent,
+                Some(&task_json),
+            )
+            .await?;
+
+        METRICS.task_created(&TaskKind::ReactToWasmEvent, TaskSource::Subscriber);
+        METRICS.subscriber_wait(
+            follow_up_name(rule),
+            (event_timestamp - broadcast_created_at).num_milliseconds() as f64 / 1000.0,
+        );
+        info!(
+            "Inserted ReactToWasmEvent task with ID: {}",
+            react_to_wasm_event_task.common.id
+        );
+
+        Ok(())
+    }
+
+    /// Store a GATEWAY_TX task with the proof the multisig prover of `chain` built for the
+    /// completed signing session
+    async fn gateway_tx(
+        &self,
+        rule: &EventRule,
+        chain: String,
+        contract_address: &str,
+        session_id: &str,
+        event_timestamp: DateTime<Utc>,
+        broadcast_created_at: DateTime<Utc>,
+    ) -> Result<(), anyhow::Error> {
+        let multisig_prover = self
+            .chains
+            .find(&chain)
+            .await?
+            .map(|chain| chain.contracts.multisig_prover)
+            .ok_or_else(|| anyhow::anyhow!("Chain {} is not registered", chain))?;
+
+        let axelard_query_command = format!(
+            "axelard query wasm contract-state smart {} '{{ \"proof\": {{ \"multisig_session_id\": \"{}\" }} }}' --node {} --output json",
+            multisig_prover, session_id, self.rpc,
+        );
+
+        let axelard_started_at = Instant::now();
+        let output = tokio::process::Command::new("bash")
+            .arg("-c")
+            .arg(axelard_query_command.clone())
+            .output()
             .await;
+        METRICS.axelard_call("query_proof", axelard_started_at);
 
-            match maybe_quorum_reached_event_fields {
-                Ok(Some((quorum_reached_event, event_timestamp, block_height))) => {
-                    info!("Found quorum reached event: {:?}", quorum_reached_event);
+        let output = output.map_err(|e| anyhow::anyhow!("Error: {}", e))?;
+        if !output.status.success() {
+            error!("Query failed: {}", axelard_query_command);
+            return Err(anyhow::anyhow!("Query failed: {}", axelard_query_command));
+        }
 
-                    let mut attributes = Vec::new();
-                    if let Some(attrs) = quorum_reached_event
-                        .get("attributes")
-                        .and_then(|v| v.as_array())
-                    {
-                        for attr in attrs {
-                            if let (Some(key), Some(value)) = (
-                                attr.get("key").and_then(|v| v.as_str()),
-                                attr.get("value").and_then(|v| v.as_str()),
-                            ) {
-                                attributes.push(EventAttribute {
-                                    key: key.to_string(),
-                                    value: value.to_string(),
-                                });
-                            }
-                        }
-                    }
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        let json_value = serde_json::from_str::<Value>(&output_str)?;
 
-                    let react_to_wasm_quorum_reached_task = ReactToWasmEventTask {
-                        common: CommonTaskFields {
-                            id: uuid::Uuid::new_v4().to_string(),
-                            chain: item.chain,
-                            timestamp: event_timestamp.to_rfc3339(),
-                            r#type: "REACT_TO_WASM_EVENT".to_string(),
-                            meta: None,
-                        },
-                        task: ReactToWasmEventTaskFields {
-                            event: WasmEvent {
-                                attributes,
-                                r#type: "wasm-quorum_reached".to_string(),
-                            },
-                            height: block_height.parse::<u64>().unwrap_or(0),
-                        },
-                    };
-
-                    let task_json = serde_json::to_string(&react_to_wasm_quorum_reached_task)?;
-
-                    self.database
-                        .upsert(
-                            &react_to_wasm_quorum_reached_task.common.id,
-                            &react_to_wasm_quorum_reached_task.common.chain,
-                            event_timestamp,
-                            crate::gmp_types::TaskKind::ReactToWasmEvent,
-                            Some(&task_json),
-                        )
-                        .await?;
+        info!("Query executed successfully: {}", loggable(&json_value));
 
-                    info!(
-                        "Inserted ReactToWasmEvent task with ID: {}",
-                        react_to_wasm_quorum_reached_task.common.id
-                    );
+        let execute_data = json_value
+            .get("data")
+            .and_then(|v| v.get("status"))
+            .and_then(|v| v.get("completed"))
+            .and_then(|v| v.get("execute_data"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
 
-                    return Ok(());
-                }
-                Ok(None) => {
-                    warn!("No quorum reached event found on page {}", page);
-                }
-                Err(e) => {
-                    // Check if this is the "too old timestamp" error - if so, stop searching
-                    if e.to_string()
-                        .contains("Timestamp is less than message timestamp")
-                    {
-                        warn!(
-                            "Reached transactions older than broadcast time, stopping search at page {}",
-                            page
-                        );
-                        break;
-                    } else {
-                        return Err(e);
-                    }
-                }
+        if execute_data.is_empty() {
+            warn!("No execute data found");
+        }
+
+        // Base64 encode the execute_data
+        let encoded_execute_data = general_purpose::STANDARD.encode(execute_data);
+
+        let gateway_tx_task = GatewayTxTask {
+            common: CommonTaskFields {
+                id: follow_up_task_id(rule, "GATEWAY_TX", contract_address, session_id),
+                chain,
+                timestamp: event_timestamp.to_rfc3339(),
+                r#type: "GATEWAY_TX".to_string(),
+                meta: None,
+            },
+            task: GatewayTxTaskFields {
+                execute_data: encoded_execute_data,
+            },
+        };
+
+        let task_json = serde_json::to_string(&gateway_tx_task)?;
+
+        self.database
+            .upsert(
+                &gateway_tx_task.common.id,
+                &gateway_tx_task.common.chain,
+                event_timestamp,
+                TaskKind::GatewayTx,
+                Some(&task_json),
+            )
+            .await?;
+
+        METRICS.task_created(&TaskKind::GatewayTx, TaskSource::Subscriber);
+        METRICS.subscriber_wait(
+            follow_up_name(rule),
+            (event_timestamp - broadcast_created_at).num_milliseconds() as f64 / 1000.0,
+        );
+        info!(
+            "Inserted GatewayTx task with ID: {}",
+            gateway_tx_task.common.id
+        );
+
+        Ok(())
+    }
+
+    /// The poll expired without the quorum being reached: hand the verification back to the
+    /// relayer, which decides whether to broadcast it again
+    async fn react_to_retriable_poll(&self, item: VerifyMessagesItem) -> Result<(), anyhow::Error> {
+        let (broadcast_id, invoked_contract_address, request_payload) =
+            self.broadcast_request(item.broadcast_id).await?;
+        let quorum_reached_events = match &self.index {
+            Some(index) => {
+                let events: Vec<QuorumReachedEvent> = index
+                    .find_all(
+                        &self.rules.verify_messages().follow_up_event,
+                        &item.contract_address,
+                        &item.poll_id,
+                    )
+                    .await?
+                    .iter()
+                    .filter_map(quorum_reached_event)
+                    .collect();
+                (!events.is_empty()).then_some(events)
             }
+            None => None,
+        };
+
+        let timestamp = Utc::now();
+        let task = ReactToRetriablePollTask {
+            common: CommonTaskFields {
+                id: follow_up_task_id(
+                    self.rules.verify_messages(),
+                    "REACT_TO_RETRIABLE_POLL",
+                    &item.contract_address,
+                    &item.poll_id,
+                ),
+                chain: item.chain,
+                timestamp: timestamp.to_rfc3339(),
+                r#type: "REACT_TO_RETRIABLE_POLL".to_string(),
+                meta: None,
+            },
+            task: ReactToRetriablePollTaskFields {
+                poll_id: numeric_id(&item.poll_id)?,
+                broadcast_id,
+                invoked_contract_address,
+                request_payload,
+                quorum_reached_events,
+            },
+        };
+
+        let task_json = serde_json::to_string(&task)?;
+        self.database
+            .upsert(
+                &task.common.id,
+                &task.common.chain,
+                timestamp,
+                TaskKind::ReactToRetriablePoll,
+                Some(&task_json),
+            )
+            .await?;
+
+        METRICS.task_created(&TaskKind::ReactToRetriablePoll, TaskSource::Subscriber);
+        info!(
+            "Poll {} expired, inserted ReactToRetriablePoll task with ID: {}",
+            item.poll_id, task.common.id
+        );
+
+        Ok(())
+    }
+
+    /// The signing session expired before enough signatures were submitted
+    async fn react_to_expired_signing_session(
+        &self,
+        item: ConstructProofItem,
+    ) -> Result<(), anyhow::Error> {
+        let (broadcast_id, invoked_contract_address, request_payload) =
+            self.broadcast_request(item.broadcast_id).await?;
+
+        let timestamp = Utc::now();
+        let task = ReactToExpiredSigningSessionTask {
+            common: CommonTaskFields {
+                id: follow_up_task_id(
+                    self.rules.construct_proof(),
+                    "REACT_TO_EXPIRED_SIGNING_SESSION",
+                    &item.contract_address,
+                    &item.session_id,
+                ),
+                chain: item.chain,
+                timestamp: timestamp.to_rfc3339(),
+                r#type: "REACT_TO_EXPIRED_SIGNING_SESSION".to_string(),
+                meta: None,
+            },
+            task: ReactToExpiredSigningSessionTaskFields {
+                session_id: numeric_id(&item.session_id)?,
+                broadcast_id,
+                invoked_contract_address,
+                request_payload,
+            },
+        };
+
+        let task_json = serde_json::to_string(&task)?;
+        self.database
+            .upsert(
+                &task.common.id,
+                &task.common.chain,
+                timestamp,
+                TaskKind::ReactToExpiredSigningSession,
+                Some(&task_json),
+            )
+            .await?;
+
+        METRICS.task_created(
+            &TaskKind::ReactToExpiredSigningSession,
+            TaskSource::Subscriber,
+        );
+        info!(
+            "Signing session {} expired, inserted ReactToExpiredSigningSession task with ID: {}",
+            item.session_id, task.common.id
+        );
+
+        Ok(())
+    }
+
+    /// ID, invoked contract and payload of the broadcast that started a poll or signing session
+    async fn broadcast_request(
+        &self,
+        broadcast_id: Option<String>,
+    ) -> Result<(String, String, String), anyhow::Error> {
+        let broadcast_id = broadcast_id
+            .ok_or_else(|| anyhow::anyhow!("Item was queued without its broadcast ID"))?;
+        let (contract_address, request_payload) = self
+            .broadcasts
+            .find_request(&broadcast_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Broadcast {} not found", broadcast_id))?;
+        Ok((broadcast_id, contract_address, request_payload))
+    }
+
+    /// Whether a poll or signing session ending at `expires_at` is over. The height compared is
+    /// the one the event search covers, i.e. the indexer checkpoint if events are indexed, and it
+    /// is taken before searching so that an event in the last block is not missed.
+    async fn has_expired(&self, expires_at: Option<u64>) -> Result<bool, anyhow::Error> {
+        let Some(expires_at) = expires_at else {
+            return Ok(false);
+        };
+        let height = match &self.index {
+            Some(index) => index
+                .checkpoint(indexer::CHECKPOINT)
+                .await?
+                .unwrap_or_default() as u64,
+            None => self.latest_height().await?,
+        };
+        Ok(height > expires_at)
+    }
+
+    async fn latest_height(&self) -> Result<u64, anyhow::Error> {
+        let status: Value = self
+            .http_client
+            .get(format!("{}/status", self.rpc.trim_end_matches('/')))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        status
+            .pointer("/result/sync_info/latest_block_height")
+            .and_then(|v| v.as_str())
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(|| anyhow::anyhow!("Latest block height not found in RPC status"))
+    }
+
+    /// The latest event with the given poll or session ID, see [`Self::find_events`]
+    async fn find_event(
+        &self,
+        rule: &EventRule,
+        contract_address: &str,
+        id: &str,
+        broadcast_created_at: DateTime<Utc>,
+    ) -> Result<Option<(Value, DateTime<Utc>, String)>, anyhow::Error> {
+        Ok(self
+            .find_events(rule, contract_address, id, broadcast_created_at)
+            .await?
+            .pop())
+    }
+
+    /// The events with the given poll or session ID, oldest first, with their timestamp and block
+    /// height. They are taken from the websocket if the watcher runs, and otherwise or if they
+    /// did not show up there in time from the indexed events or by searching with `axelard query
+    /// txs`.
+    async fn find_events(
+        &self,
+        rule: &EventRule,
+        contract_address: &str,
+        id: &str,
+        broadcast_created_at: DateTime<Utc>,
+    ) -> Result<Vec<(Value, DateTime<Utc>, String)>, anyhow::Error> {
+        if let Some(watcher) = &self.watcher {
+            if let Some(watched) = watcher.wait(rule, contract_address, id).await {
+                return Ok(watched
+                    .into_iter()
+                    .map(|watched| (watched.event, watched.timestamp, watched.height))
+                    .collect());
+            }
+            debug!(
+                "{} for {} not seen on the websocket, falling back to querying",
+                rule.follow_up_event, id
+            );
+        }
+
+        if let Some(index) = &self.index {
+            let indexed = index
+                .find_all(&rule.follow_up_event, contract_address, id)
+                .await?;
+            return Ok(indexed
+                .into_iter()
+                .map(|indexed| {
+                    (
+                        indexed.to_event(),
+                        indexed.block_time,
+                        indexed.height.to_string(),
+                    )
+                })
+                .collect());
         }
 
-        Err(anyhow::anyhow!("No quorum reached event found"))
+        self.search_event_pages(rule, contract_address, id, broadcast_created_at)
+            .await
     }
 
-    async fn handle_construct_proof(&self, item: ConstructProofItem) -> Result<(), anyhow::Error> {
+    async fn search_event_pages(
+        &self,
+        rule: &EventRule,
+        contract_address: &str,
+        id: &str,
+        broadcast_created_at: DateTime<Utc>,
+    ) -> Result<Vec<(Value, DateTime<Utc>, String)>, anyhow::Error> {
+        let event_type = &rule.follow_up_event;
         let initial_axelard_query_script_str = format!(
-            "axelard query txs --events 'wasm-signing_completed._contract_address={}' --node {} --output json --limit 1",
-            item.contract_address, self.rpc
+            "axelard query txs --events '{}._contract_address={}' --node {} --output json --limit 1",
+            event_type, contract_address, self.rpc
         );
 
         let total_pages =
@@ -229,109 +1020,25 @@ impl<Q: QueueTrait> Subscriber<Q> {
         // request for pages in reverse order to get the latest data first
         for page in (1..=total_pages).rev() {
             let axelard_query_script_str = format!(
-                "axelard query txs --events 'wasm-signing_completed._contract_address={}' --node {} --output json --limit 100 --page {}",
-                item.contract_address, self.rpc, page
+                "axelard query txs --events '{}._contract_address={}' --node {} --output json --limit 100 --page {}",
+                event_type, contract_address, self.rpc, page
             );
 
-            let maybe_signing_completed_event_fields = Self::get_event_fields_from_script(
+            let maybe_event_fields = Self::get_event_fields_from_script(
                 axelard_query_script_str,
-                DesiredEventType::SigningCompleted,
-                item.session_id.clone(),
-                item.broadcast_created_at,
+                rule,
+                id.to_string(),
+                broadcast_created_at,
             )
             .await;
 
-            match maybe_signing_completed_event_fields {
-                Ok(Some((signing_completed_event, event_timestamp, _block_height))) => {
-                    info!(
-                        "Found signing completed event: {:?}",
-                        signing_completed_event
-                    );
-
-                    let axelard_query_command = format!(
-                        "axelard query wasm contract-state smart {} '{}' --node {} --output json",
-                        "axelar1ys83sedjffmqh70aksejmx3fy3q2d7twm3msurk7wn3l6nkwxp0sfelzhl",
-                        format!(
-                            "{{ \"proof\": {{ \"multisig_session_id\": \"{}\" }} }}",
-                            item.session_id
-                        ),
-                        self.rpc,
-                    );
-
-                    let axelard_query_result = tokio::process::Command::new("bash")
-                        .arg("-c")
-                        .arg(axelard_query_command.clone())
-                        .output()
-                        .await;
-
-                    match axelard_query_result {
-                        Ok(output) => {
-                            if output.status.success() {
-                                let output_str = String::from_utf8_lossy(&output.stdout);
-                                let json_value = serde_json::from_str::<Value>(&output_str)?;
-
-                                info!("Query executed successfully: {}", output_str);
-
-                                let execute_data = json_value
-                                    .get("data")
-                                    .and_then(|v| v.get("status"))
-                                    .and_then(|v| v.get("completed"))
-                                    .and_then(|v| v.get("execute_data"))
-                                    .and_then(|v| v.as_str())
-                                    .unwrap_or("");
-
-                                if execute_data.is_empty() {
-                                    warn!("No execute data found");
-                                }
-
-                                // Base64 encode the execute_data
-                                let encoded_execute_data =
-                                    general_purpose::STANDARD.encode(execute_data);
-
-                                let gateway_tx_task = GatewayTxTask {
-                                    common: CommonTaskFields {
-                                        id: uuid::Uuid::new_v4().to_string(),
-                                        chain: item.chain,
-                                        timestamp: event_timestamp.to_rfc3339(),
-                                        r#type: "GATEWAY_TX".to_string(),
-                                        meta: None,
-                                    },
-                                    task: GatewayTxTaskFields {
-                                        execute_data: encoded_execute_data,
-                                    },
-                                };
-
-                                let task_json = serde_json::to_string(&gateway_tx_task)?;
-
-                                self.database
-                                    .upsert(
-                                        &gateway_tx_task.common.id,
-                                        &gateway_tx_task.common.chain,
-                                        event_timestamp,
-                                        crate::gmp_types::TaskKind::GatewayTx,
-                                        Some(&task_json),
-                                    )
-                                    .await?;
-
-                                info!(
-                                    "Inserted GatewayTx task with ID: {}",
-                                    gateway_tx_task.common.id
-                                );
-
-                                return Ok(());
-                            } else {
-                                error!("Query failed: {}", axelard_query_command);
-                            }
-                        }
-                        Err(e) => {
-                            error!("Error: {}", e);
-                        }
-                    }
-                }
+            match maybe_event_fields {
+                Ok(Some(events)) => return Ok(events),
                 Ok(None) => {
-                    warn!("No signing completed event found on page {}", page);
+                    warn!("No {} event found on page {}", event_type, page);
                 }
                 Err(e) => {
+                    // Check if this is the "too old timestamp" error - if so, stop searching
                     if e.to_string()
                         .contains("Timestamp is less than message timestamp")
                     {
@@ -346,17 +1053,20 @@ impl<Q: QueueTrait> Subscriber<Q> {
                 }
             }
         }
-        Err(anyhow::anyhow!("No signing completed event found"))
+
+        Ok(vec![])
     }
 
     async fn get_total_page_number_from_query(
         axelard_query_script_str: String,
     ) -> Result<u32, anyhow::Error> {
+        let axelard_started_at = Instant::now();
         let axelard_query_result = tokio::process::Command::new("bash")
             .arg("-c")
             .arg(axelard_query_script_str.clone())
             .output()
             .await;
+        METRICS.axelard_call("query_txs", axelard_started_at);
 
         match axelard_query_result {
             Ok(output) => {
@@ -401,17 +1111,19 @@ impl<Q: QueueTrait> Subscriber<Q> {
 
     async fn get_event_fields_from_script(
         axelard_query_script_str: String,
-        desired_event_type: DesiredEventType,
+        rule: &EventRule,
         item_desired_id: String,
         message_timestamp: DateTime<Utc>,
-    ) -> Result<Option<(Value, DateTime<Utc>, String)>, anyhow::Error> {
-        let event_type = desired_event_type.event_type_name();
-        let desired_attribute = desired_event_type.attribute_name();
+    ) -> Result<Option<Vec<(Value, DateTime<Utc>, String)>>, anyhow::Error> {
+        let event_type = &rule.follow_up_event;
+        let desired_attribute = &rule.correlation_attribute;
+        let axelard_started_at = Instant::now();
         let axelard_query_result = tokio::process::Command::new("bash")
             .arg("-c")
             .arg(axelard_query_script_str.clone())
             .output()
             .await;
+        METRICS.axelard_call("query_txs", axelard_started_at);
 
         match axelard_query_result {
             Ok(output) => {
@@ -444,55 +1156,67 @@ impl<Q: QueueTrait> Subscriber<Q> {
                                         for event in tx_events {
                                             if let Some(event_type_val) =
                                                 event.get("type").and_then(|v| v.as_str())
+                                                && event_type_val == event_type
                                             {
-                                                if event_type_val == event_type {
-                                                    debug!(
-                                                        "Event type matches! Looking for {} = '{}'...",
-                                                        desired_attribute, item_desired_id
+                                                debug!(
+                                                    "Event type matches! Looking for {} = '{}'...",
+                                                    desired_attribute, item_desired_id
+                                                );
+                                                let attributes = event
+                                                    .get("attributes")
+                                                    .and_then(|v| v.as_array())
+                                                    .ok_or_else(|| {
+                                                        error!("Attributes not found in event");
+                                                        anyhow::anyhow!(
+                                                            "Attributes not found in event"
+                                                        )
+                                                    })?;
+
+                                                let event_id = attributes
+                                                    .iter()
+                                                    .find(|attr| {
+                                                        attr.get("key")
+                                                            .and_then(|v| v.as_str())
+                                                            .unwrap_or("")
+                                                            == desired_attribute
+                                                    })
+                                                    .and_then(|attr| {
+                                                        attr.get("value").and_then(|v| v.as_str())
+                                                    })
+                                                    .unwrap_or("");
+
+                                                if event_id == item_desired_id {
+                                                    info!(
+                                                        "ID match found! Returning the events of the transaction."
                                                     );
-                                                    let attributes = event
-                                                        .get("attributes")
-                                                        .and_then(|v| v.as_array())
-                                                        .ok_or_else(|| {
-                                                            error!("Attributes not found in event");
-                                                            anyhow::anyhow!(
-                                                                "Attributes not found in event"
+                                                    let tx_timestamp = if let Some(timestamp) =
+                                                        tx.get("timestamp").and_then(|v| v.as_str())
+                                                    {
+                                                        DateTime::parse_from_rfc3339(timestamp)?
+                                                            .into()
+                                                    } else {
+                                                        message_timestamp
+                                                    };
+                                                    let block_height = maybe_block_height
+                                                        .unwrap_or("0")
+                                                        .to_string();
+                                                    return Ok(Some(
+                                                        tx_events_with_id(
+                                                            tx,
+                                                            event_type,
+                                                            desired_attribute,
+                                                            &item_desired_id,
+                                                        )
+                                                        .into_iter()
+                                                        .map(|event| {
+                                                            (
+                                                                event,
+                                                                tx_timestamp,
+                                                                block_height.clone(),
                                                             )
-                                                        })?;
-
-                                                    let event_id = attributes
-                                                        .iter()
-                                                        .find(|attr| {
-                                                            attr.get("key")
-                                                                .and_then(|v| v.as_str())
-                                                                .unwrap_or("")
-                                                                == desired_attribute
-                                                        })
-                                                        .and_then(|attr| {
-                                                            attr.get("value")
-                                                                .and_then(|v| v.as_str())
                                                         })
-                                                        .unwrap_or("");
-
-                                                    if event_id == item_desired_id {
-                                                        info!("ID match found! Returning event.");
-                                                        let tx_timestamp = if let Some(timestamp) =
-                                                            tx.get("timestamp")
-                                                                .and_then(|v| v.as_str())
-                                                        {
-                                                            DateTime::parse_from_rfc3339(timestamp)?
-                                                                .into()
-                                                        } else {
-                                                            message_timestamp
-                                                        };
-                                                        return Ok(Some((
-                                                            event.clone(),
-                                                            tx_timestamp,
-                                                            maybe_block_height
-                                                                .unwrap_or("0")
-                                                                .to_string(),
-                                                        )));
-                                                    }
+                                                        .collect(),
+                                                    ));
                                                 }
                                             }
                                         }
@@ -530,6 +1254,121 @@ impl<Q: QueueTrait> Subscriber<Q> {
         Ok(None)
     }
 }
+/// Label of the follow-up event of `rule` in the metrics, e.g. `quorum_reached`
+fn follow_up_name(rule: &EventRule) -> &str {
+    rule.follow_up_event.trim_start_matches("wasm-")
+}
+
+/// ID of the `task_type` task stored for `rule` with the given correlation ID, the same on every
+/// attempt so that a retried or redelivered item overwrites the task instead of adding another
+fn follow_up_task_id(
+    rule: &EventRule,
+    task_type: &str,
+    contract_address: &str,
+    id: &str,
+) -> String {
+    task_id(&[&rule.name, task_type, contract_address, id])
+}
+
+/// UUIDv5 of what identifies a task, so that building the task again gives the same ID
+fn task_id(parts: &[&str]) -> String {
+    uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_OID, parts.join("/").as_bytes()).to_string()
+}
+
+fn event_attribute<'a>(event: &'a Value, name: &str) -> Option<&'a str> {
+    event
+        .get("attributes")?
+        .as_array()?
+        .iter()
+        .find(|attr| attr.get("key").and_then(|v| v.as_str()) == Some(name))?
+        .get("value")?
+        .as_str()
+}
+
+/// The events of `event_type` in a transaction of `axelard query txs` whose `attribute` is `id`,
+/// e.g. the quorum_reached events of all messages of a poll
+fn tx_events_with_id(tx: &Value, event_type: &str, attribute: &str, id: &str) -> Vec<Value> {
+    tx.get("logs")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|log| log.get("events").and_then(|v| v.as_array()))
+        .flatten()
+        .filter(|event| {
+            event.get("type").and_then(|v| v.as_str()) == Some(event_type)
+                && event_attribute(event, attribute) == Some(id)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Poll and session IDs are JSON values in event attributes, e.g. `"42"` with the quotes
+fn numeric_id(id: &str) -> Result<u64, anyhow::Error> {
+    id.trim_matches('"')
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid ID {}: {}", id, e))
+}
+
+/// Destination chain and message of a quorum_reached event that succeeded on the source chain.
+/// The content is either a plain message or, for XRPL, a message wrapped by its kind such as
+/// `interchain_transfer_message`, with the `cc_id` next to it. Messages without a destination,
+/// e.g. `add_gas_message`, are not routed.
+fn verified_message(event: &Value) -> Option<(String, GatewayV2Message)> {
+    let attributes = event.get("attributes")?.as_array()?;
+    let attribute = |name: &str| {
+        attributes
+            .iter()
+            .find(|attr| attr.get("key").and_then(|v| v.as_str()) == Some(name))
+            .and_then(|attr| attr.get("value").and_then(|v| v.as_str()))
+    };
+    let status: VerificationStatus = serde_json::from_str(attribute("status")?).ok()?;
+    if status != VerificationStatus::SucceededOnSourceChain {
+        return None;
+    }
+
+    let content: Value = serde_json::from_str(attribute("content")?).ok()?;
+    let cc_id = content.get("cc_id")?;
+    let message = if content.get("destination_chain").is_some() {
+        &content
+    } else {
+        content
+            .as_object()?
+            .values()
+            .find(|value| value.get("destination_chain").is_some())?
+    };
+    let field = |value: &Value, name: &str| Some(value.get(name)?.as_str()?.to_string());
+
+    let payload_hash = match message.get("payload_hash").and_then(|v| v.as_str()) {
+        Some(hash) => hash.trim_start_matches("0x").to_lowercase(),
+        None => EMPTY_PAYLOAD_HASH.to_string(),
+    };
+    Some((
+        field(message, "destination_chain")?,
+        GatewayV2Message {
+            message_id: field(cc_id, "message_id")?,
+            source_chain: field(cc_id, "source_chain")?,
+            source_address: field(message, "source_address")?,
+            destination_address: field(message, "destination_address")?,
+            payload_hash,
+        },
+    ))
+}
+
+/// Status and content of a quorum_reached event, both JSON encoded attributes
+fn quorum_reached_event(event: &IndexedEvent) -> Option<QuorumReachedEvent> {
+    let attributes = event.attributes.as_array()?;
+    let attribute = |name: &str| {
+        attributes
+            .iter()
+            .find(|attr| attr.get("key").and_then(|v| v.as_str()) == Some(name))
+            .and_then(|attr| attr.get("value").and_then(|v| v.as_str()))
+    };
+    Some(QuorumReachedEvent {
+        status: serde_json::from_str(attribute("status")?).ok()?,
+        content: serde_json::from_str(attribute("content")?).ok()?,
+    })
+}
+
 #[derive(Debug, Clone)]
 pub struct EventData {
     pub event_type: String,
@@ -538,23 +1377,339 @@ pub struct EventData {
 
 #[cfg(test)]
 mod tests {
-    use chrono::DateTime;
+    use std::{sync::Arc, time::Duration};
+
+    use chrono::{DateTime, Utc};
     use serde_json::Value;
+    use sha3::{Digest, Keccak256};
+    use testcontainers::{ContainerAsync, runners::AsyncRunner};
+    use testcontainers_modules::postgres;
 
     use crate::{
-        LapinConnection,
-        subscriber::{DesiredEventType, Subscriber},
+        gmp_types::{TaskKind, VerificationStatus},
+        models::{
+            broadcasts::BroadcastsModel,
+            chains::ChainsModel,
+            dead_letters::DeadLettersModel,
+            payloads::PayloadsModel,
+            tasks::{TaskFilter, TasksModel},
+            wasm_events::{IndexedEvent, WasmEventsModel},
+        },
+        queue::{MemoryQueue, QueueItem, RetryInfo, VerifyMessagesItem},
+        rules::EventRule,
+        subscriber::{
+            EMPTY_PAYLOAD_HASH, RetryPolicy, Subscriber, follow_up_task_id, numeric_id,
+            quorum_reached_event, verified_message,
+        },
     };
 
+    /// A subscriber that looks events up in the index and routes verified messages
+    async fn setup_test_subscriber() -> (
+        Subscriber,
+        TasksModel,
+        WasmEventsModel,
+        ContainerAsync<postgres::Postgres>,
+    ) {
+        let container = postgres::Postgres::default()
+            .with_init_sql(
+                [
+                    include_str!("../migrations/0001_tasks.sql"),
+                    include_str!("../migrations/0002_events.sql"),
+                    include_str!("../migrations/0003_broadcasts.sql"),
+                    include_str!("../migrations/0004_payloads.sql"),
+                    include_str!("../migrations/0005_chains.sql"),
+                    include_str!("../migrations/0006_dead_letters.sql"),
+                    include_str!("../migrations/0008_wasm_events.sql"),
+                    include_str!("../migrations/0009_payloads_binary.sql"),
+                    include_str!("../migrations/0010_task_leases.sql"),
+                    include_str!("../migrations/0011_tasks_jsonb.sql"),
+                ]
+                .join("\n")
+                .into_bytes(),
+            )
+            .start()
+            .await
+            .unwrap();
+        let connection_string = format!(
+            "postgres://postgres:postgres@{}:{}/postgres",
+            container.get_host().await.unwrap(),
+            container.get_host_port_ipv4(5432).await.unwrap()
+        );
+        let tasks = TasksModel::new(&connection_string).await.unwrap();
+        let index = WasmEventsModel::new(&connection_string).await.unwrap();
+        let subscriber = Subscriber::new(
+            Arc::new(MemoryQueue::new()),
+            tasks.clone(),
+            ChainsModel::new(&connection_string).await.unwrap(),
+            BroadcastsModel::new(&connection_string).await.unwrap(),
+            DeadLettersModel::new(&connection_string).await.unwrap(),
+            "http://127.0.0.1:1".to_string(),
+            RetryPolicy {
+                max_attempts: 1,
+                initial_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(1),
+            },
+        )
+        .with_index(index.clone())
+        .with_payloads(PayloadsModel::new(&connection_string).await.unwrap());
+        // we need to return the container too otherwise it will be dropped and the test will run forever
+        (subscriber, tasks, index, container)
+    }
+
+    /// The quorum_reached event of a message of poll 42 that succeeded on the source chain
+    fn indexed_quorum_reached(event_index: i32, message_id: &str) -> IndexedEvent {
+        let content = serde_json::json!({
+            "cc_id": {"source_chain": "ethereum", "message_id": message_id},
+            "source_address": "0xabc",
+            "destination_chain": "xrpl",
+            "destination_address": "rDbGtcJPD3nR3dd94dW8B5ihve6sJTYskf",
+            "payload_hash": null,
+        });
+        IndexedEvent {
+            tx_hash: "ABCD".to_string(),
+            event_index,
+            height: 1234,
+            event_type: "wasm-quorum_reached".to_string(),
+            contract_address: "axelar1verifier".to_string(),
+            event_id: Some("\"42\"".to_string()),
+            attributes: serde_json::json!([
+                {"key": "_contract_address", "value": "axelar1verifier"},
+                {"key": "content", "value": content.to_string()},
+                {"key": "status", "value": "\"succeeded_on_source_chain\""},
+                {"key": "poll_id", "value": "\"42\""}
+            ]),
+            block_time: Utc::now(),
+        }
+    }
+
+    fn verify_messages_item() -> QueueItem {
+        QueueItem::VerifyMessages(VerifyMessagesItem {
+            poll_id: "\"42\"".to_string(),
+            contract_address: "axelar1verifier".to_string(),
+            broadcast_created_at: Utc::now(),
+            chain: "ethereum".to_string(),
+            broadcast_id: None,
+            expires_at: None,
+            retry: RetryInfo::default(),
+        })
+    }
+
+    async fn tasks_of_kind(tasks: &TasksModel, chain: &str, kind: TaskKind) -> Vec<Value> {
+        let filter = TaskFilter {
+            r#type: Some(kind),
+            ..Default::default()
+        };
+        tasks.get_tasks(chain, None, &filter).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_redelivered_item_does_not_duplicate_tasks() {
+        let (subscriber, tasks, index, _container) = setup_test_subscriber().await;
+        index
+            .store("wasm_events", &[indexed_quorum_reached(0, "0x171e")], 1234)
+            .await
+            .unwrap();
+
+        subscriber
+            .handle_item(verify_messages_item())
+            .await
+            .unwrap();
+        subscriber
+            .handle_item(verify_messages_item())
+            .await
+            .unwrap();
+
+        let construct_proof = tasks_of_kind(&tasks, "xrpl", TaskKind::ConstructProof).await;
+        assert_eq!(construct_proof.len(), 1);
+        assert_eq!(
+            construct_proof[0].pointer("/task/message/messageID"),
+            Some(&serde_json::json!("0x171e"))
+        );
+        assert_eq!(
+            tasks_of_kind(&tasks, "ethereum", TaskKind::ReactToWasmEvent)
+                .await
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_every_message_of_a_poll_is_routed() {
+        let (subscriber, tasks, index, _container) = setup_test_subscriber().await;
+        index
+            .store(
+                "wasm_events",
+                &[
+                    indexed_quorum_reached(0, "0x171e"),
+                    indexed_quorum_reached(1, "0x2b3f"),
+                ],
+                1234,
+            )
+            .await
+            .unwrap();
+
+        subscriber
+            .handle_item(verify_messages_item())
+            .await
+            .unwrap();
+        subscriber
+            .handle_item(verify_messages_item())
+            .await
+            .unwrap();
+
+        let construct_proof = tasks_of_kind(&tasks, "xrpl", TaskKind::ConstructProof).await;
+        let mut message_ids: Vec<_> = construct_proof
+            .iter()
+            .filter_map(|task| task.pointer("/task/message/messageID"))
+            .collect();
+        message_ids.sort_by_key(|id| id.to_string());
+        assert_eq!(
+            message_ids,
+            vec![&serde_json::json!("0x171e"), &serde_json::json!("0x2b3f")]
+        );
+        assert_eq!(
+            tasks_of_kind(&tasks, "ethereum", TaskKind::ReactToWasmEvent)
+                .await
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_the_maximum() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_delay: Duration::from_secs(5),
+            max_delay: Duration::from_secs(300),
+        };
+
+        assert_eq!(policy.delay(1), Duration::from_secs(5));
+        assert_eq!(policy.delay(2), Duration::from_secs(10));
+        assert_eq!(policy.delay(4), Duration::from_secs(40));
+        assert_eq!(policy.delay(7), Duration::from_secs(300));
+        assert_eq!(policy.delay(64), Duration::from_secs(300));
+    }
+
+    #[test]
+    fn test_quorum_reached_events_of_a_retriable_poll() {
+        let event = IndexedEvent {
+            tx_hash: "ABCD".to_string(),
+            event_index: 0,
+            height: 1234,
+            event_type: "wasm-quorum_reached".to_string(),
+            contract_address: "axelar1verifier".to_string(),
+            event_id: Some("\"42\"".to_string()),
+            attributes: serde_json::json!([
+                {"key": "_contract_address", "value": "axelar1verifier"},
+                {"key": "content", "value": "{\"add_gas_message\":{\"tx_id\":\"5fa1\"}}"},
+                {"key": "status", "value": "\"not_found_on_source_chain\""},
+                {"key": "poll_id", "value": "\"42\""}
+            ]),
+            block_time: Utc::now(),
+        };
+
+        let quorum_reached = quorum_reached_event(&event).unwrap();
+        assert_eq!(
+            quorum_reached.status,
+            VerificationStatus::NotFoundOnSourceChain
+        );
+        assert_eq!(
+            quorum_reached.content,
+            serde_json::json!({"add_gas_message": {"tx_id": "5fa1"}})
+        );
+        assert_eq!(numeric_id("\"42\"").unwrap(), 42);
+        assert!(numeric_id("abc").is_err());
+    }
+
+    #[test]
+    fn test_follow_up_task_id_is_the_same_on_every_attempt() {
+        let rule = EventRule::verify_messages();
+        let task_id = |task_type, contract_address, id| {
+            follow_up_task_id(&rule, task_type, contract_address, id)
+        };
+        let id = task_id("REACT_TO_RETRIABLE_POLL", "axelar1verifier", "\"42\"");
+
+        assert_eq!(
+            id,
+            task_id("REACT_TO_RETRIABLE_POLL", "axelar1verifier", "\"42\"")
+        );
+        assert_ne!(
+            id,
+            task_id("REACT_TO_RETRIABLE_POLL", "axelar1verifier", "\"43\"")
+        );
+        assert_ne!(
+            id,
+            task_id("REACT_TO_RETRIABLE_POLL", "axelar1other", "\"42\"")
+        );
+        assert_ne!(
+            id,
+            task_id("REACT_TO_WASM_EVENT", "axelar1verifier", "\"42\"")
+        );
+        assert!(uuid::Uuid::parse_str(&id).is_ok());
+    }
+
+    #[test]
+    fn test_verified_message_is_routed_to_its_destination_chain() {
+        let quorum_reached = |content: Value, status: &str| {
+            serde_json::json!({
+                "type": "wasm-quorum_reached",
+                "attributes": [
+                    {"key": "content", "value": content.to_string()},
+                    {"key": "status", "value": format!("\"{}\"", status)},
+                ]
+            })
+        };
+        let cc_id = serde_json::json!({"source_chain": "xrpl", "message_id": "0x171e"});
+
+        let content = serde_json::json!({
+            "interchain_transfer_message": {
+                "source_address": "rDbGtcJPD3nR3dd94dW8B5ihve6sJTYskf",
+                "destination_chain": "xrpl-evm",
+                "destination_address": "0cb43a0fe733471fa0a1d99319910b2135905a9e",
+                "payload_hash": null,
+            },
+            "cc_id": cc_id,
+        });
+        let (chain, message) = verified_message(&quorum_reached(
+            content.clone(),
+            "succeeded_on_source_chain",
+        ))
+        .unwrap();
+        assert_eq!(chain, "xrpl-evm");
+        assert_eq!(message.message_id, "0x171e");
+        assert_eq!(message.source_chain, "xrpl");
+        assert_eq!(message.payload_hash, EMPTY_PAYLOAD_HASH);
+        assert_eq!(
+            hex::encode(Keccak256::digest(b"")),
+            EMPTY_PAYLOAD_HASH.to_string()
+        );
+        assert!(verified_message(&quorum_reached(content, "not_found_on_source_chain")).is_none());
+
+        let content = serde_json::json!({
+            "cc_id": cc_id,
+            "source_address": "0xabc",
+            "destination_chain": "avalanche",
+            "destination_address": "0xdef",
+            "payload_hash": "73940153AB066FD16B1CE5AACFFBE6C693B90D2FCD29E5927C0A06CCE85F9E27",
+        });
+        let (chain, message) =
+            verified_message(&quorum_reached(content, "succeeded_on_source_chain")).unwrap();
+        assert_eq!(chain, "avalanche");
+        assert_eq!(
+            message.payload_hash,
+            "73940153ab066fd16b1ce5aacffbe6c693b90d2fcd29e5927c0a06cce85f9e27"
+        );
+
+        let content = serde_json::json!({"add_gas_message": {"tx_id": "5fa1"}, "cc_id": cc_id});
+        assert!(verified_message(&quorum_reached(content, "succeeded_on_source_chain")).is_none());
+    }
+
     #[tokio::test]
     async fn test_proof_query() {
         let axelard_query_command = format!(
-            "axelard query wasm contract-state smart {} '{}' --node {} --output json",
+            "axelard query wasm contract-state smart {} '{{ \"proof\": {{ \"multisig_session_id\": \"{}\" }} }}' --node {} --output json",
             "axelar1ys83sedjffmqh70aksejmx3fy3q2d7twm3msurk7wn3l6nkwxp0sfelzhl",
-            format!(
-                "{{ \"proof\": {{ \"multisig_session_id\": \"{}\" }} }}",
-                "22128"
-            ),
+            "22128",
             "http://devnet-amplifier.axelar.dev:26657",
         );
 
@@ -589,20 +1744,17 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_event_fields_from_script() {
-        let axelard_query_script_str = format!(
-            "axelard query txs --events 'wasm-quorum_reached._contract_address=axelar1w0cwqtytmjuhak4v0rd4fy65pugqcxz4g48n6puw55zcy896e6ksn9gkj2' --node http://devnet-amplifier.axelar.dev:26657/ --output json --limit 100 --page 69"
-        );
-
-        let maybe_quorum_reached_event_fields =
-            Subscriber::<LapinConnection>::get_event_fields_from_script(
-                axelard_query_script_str,
-                DesiredEventType::QuorumReached,
-                "\"7438\"".to_string(),
-                DateTime::parse_from_rfc3339("1970-01-01T00:00:00Z")
-                    .unwrap()
-                    .into(),
-            )
-            .await;
+        let axelard_query_script_str = "axelard query txs --events 'wasm-quorum_reached._contract_address=axelar1w0cwqtytmjuhak4v0rd4fy65pugqcxz4g48n6puw55zcy896e6ksn9gkj2' --node http://devnet-amplifier.axelar.dev:26657/ --output json --limit 100 --page 69".to_string();
+
+        let maybe_quorum_reached_event_fields = Subscriber::get_event_fields_from_script(
+            axelard_query_script_str,
+            &EventRule::verify_messages(),
+            "\"7438\"".to_string(),
+            DateTime::parse_from_rfc3339("1970-01-01T00:00:00Z")
+                .unwrap()
+                .into(),
+        )
+        .await;
 
         match maybe_quorum_reached_event_fields {
             Ok(Some(event_fields)) => {
@@ -0,0 +1,160 @@
+use actix_web::{
+    Error,
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::HeaderMap,
+    middleware::Next,
+};
+use opentelemetry::{Context, global, propagation::Extractor, trace::TracerProvider};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
+use tracing::{Instrument, Span, error, field, info_span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{Registry, prelude::*};
+
+use crate::{
+    config::{LoggingConfig, TelemetryConfig},
+    logging::{LogGuard, log_layer},
+    queue::Headers,
+};
+
+/// Keeps the span exporter alive, call [`Telemetry::shutdown`] before exiting to flush it
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+    _log_guard: LogGuard,
+}
+
+impl Telemetry {
+    pub fn shutdown(self) {
+        if let Some(provider) = self.provider
+            && let Err(e) = provider.shutdown()
+        {
+            error!("Failed to flush spans: {}", e);
+        }
+    }
+}
+
+/// Set up logging and, if an OTLP endpoint is configured, export spans to it. The W3C trace
+/// context is propagated through HTTP and queue headers either way.
+pub fn init(
+    service_name: &str,
+    logging: &LoggingConfig,
+    telemetry: &TelemetryConfig,
+) -> Result<Telemetry, anyhow::Error> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let provider = if telemetry.otlp_endpoint.is_empty() {
+        None
+    } else {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(&telemetry.otlp_endpoint)
+            .build()?;
+        Some(
+            SdkTracerProvider::builder()
+                .with_batch_exporter(exporter)
+                .with_resource(
+                    Resource::builder()
+                        .with_service_name(service_name.to_string())
+                        .build(),
+                )
+                .build(),
+        )
+    };
+
+    let otel_layer = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name.to_string()))
+    });
+
+    let (log_layer, log_guard) = log_layer(logging, service_name)?;
+    let subscriber = Registry::default().with(log_layer).with(otel_layer);
+    tracing::subscriber::set_global_default(subscriber)
+        .expect("Failed to set global tracing subscriber");
+
+    Ok(Telemetry {
+        provider,
+        _log_guard: log_guard,
+    })
+}
+
+/// Headers carrying the trace context of the current span, to be sent along with a queue item
+pub fn inject_context() -> Headers {
+    let mut headers = Headers::new();
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&Span::current().context(), &mut headers)
+    });
+    headers
+}
+
+/// Get the trace context that [`inject_context`] added to the headers of a queue item, if any
+pub fn extract_context(headers: &Headers) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(headers))
+}
+
+struct HttpHeaders<'a>(&'a HeaderMap);
+
+impl Extractor for HttpHeaders<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+/// Open a span per request, continuing the trace of the caller if it sent a `traceparent`
+pub async fn trace_requests(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let span = info_span!(
+        "http.request",
+        http.method = %req.method(),
+        http.target = %req.path(),
+        http.status_code = field::Empty,
+    );
+    let parent = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HttpHeaders(req.headers()))
+    });
+    span.set_parent(parent);
+
+    let response = next.call(req).instrument(span.clone()).await?;
+    span.record("http.status_code", response.status().as_u16());
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::{
+        global,
+        trace::{TraceContextExt, TracerProvider},
+    };
+    use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider};
+    use tracing::info_span;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::{Registry, prelude::*};
+
+    use super::{extract_context, inject_context};
+
+    #[test]
+    fn test_context_roundtrip_through_queue_headers() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = Registry::default()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = info_span!("publish");
+            let _guard = span.enter();
+            let trace_id = span.context().span().span_context().trace_id();
+
+            let headers = inject_context();
+            let extracted = extract_context(&headers);
+
+            assert!(extracted.span().span_context().is_valid());
+            assert_eq!(extracted.span().span_context().trace_id(), trace_id);
+        });
+    }
+}
@@ -1,13 +1,14 @@
 use serde::de::DeserializeOwned;
 use serde_json::Value;
-use tracing::{debug, error, level_filters::LevelFilter};
-use tracing_subscriber::{Registry, fmt, prelude::*};
+use tracing::{debug, error, info};
 
 use crate::gmp_types::{
     CommonTaskFields, ConstructProofTask, ExecuteTask, GatewayTxTask,
     ReactToExpiredSigningSessionTask, ReactToRetriablePollTask, ReactToWasmEventTask, RefundTask,
     Task, UnknownTask, VerifyTask,
 };
+use crate::logging::loggable;
+use crate::rules::EventRule;
 
 fn parse_as<T: DeserializeOwned>(value: &Value) -> Result<T, anyhow::Error> {
     serde_json::from_value(value.clone()).map_err(|e| anyhow::anyhow!(e.to_string()))
@@ -57,27 +58,30 @@ pub fn parse_task(task_json: &Value) -> Result<Task, anyhow::Error> {
     }
 }
 
-pub fn setup_logging() {
-    let fmt_layer = fmt::layer()
-        .with_target(true)
-        .with_filter(LevelFilter::DEBUG);
+/// Resolve once the process receives SIGINT or SIGTERM
+pub async fn shutdown_signal() {
+    let ctrl_c = tokio::signal::ctrl_c();
+    let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .expect("Failed to install SIGTERM handler");
 
-    let gmp_api = Registry::default().with(fmt_layer);
-
-    tracing::subscriber::set_global_default(gmp_api)
-        .expect("Failed to set global tracing subscriber");
+    tokio::select! {
+        _ = ctrl_c => info!("Received SIGINT"),
+        _ = sigterm.recv() => info!("Received SIGTERM"),
+    }
 }
 
+/// Correlation ID, contract address and chain of the trigger event of `rule` in the output of
+/// `axelard tx wasm execute`. The chain is empty if the rule has no chain attribute.
 pub fn extract_info_from_script(
     script_result: &Value,
-    event_type: &str,
+    rule: &EventRule,
 ) -> Result<Option<(String, String, String)>, anyhow::Error> {
     let logs = script_result
         .get("logs")
         .and_then(|v| v.as_array())
         .ok_or_else(|| {
-            error!("Logs not found in verify messages");
-            anyhow::anyhow!("Logs not found in verify messages")
+            error!("Logs not found in script result");
+            anyhow::anyhow!("Logs not found in script result")
         })?;
     for log in logs {
         let events = log
@@ -90,8 +94,12 @@ pub fn extract_info_from_script(
 
         for event in events {
             let parsed_event_type = event.get("type").and_then(|v| v.as_str()).unwrap_or("");
-            if parsed_event_type == event_type {
-                debug!("Event of type {} found: {:?}", event_type, event);
+            if parsed_event_type == rule.trigger_event {
+                debug!(
+                    "Event of type {} found: {}",
+                    rule.trigger_event,
+                    loggable(event)
+                );
                 let attributes = event
                     .get("attributes")
                     .and_then(|v| v.as_array())
@@ -99,44 +107,24 @@ pub fn extract_info_from_script(
                         error!("Attributes not found in event");
                         anyhow::anyhow!("Attributes not found in event")
                     })?;
-                let desired_id_attribute_name = match event_type {
-                    "wasm-messages_poll_started" => "poll_id",
-                    "wasm-signing_started" => "session_id",
-                    _ => return Err(anyhow::anyhow!("Invalid event type")),
+                let attribute = |name: &str| {
+                    attributes
+                        .iter()
+                        .find(|attr| attr.get("key").and_then(|v| v.as_str()).unwrap_or("") == name)
+                        .and_then(|attr| attr.get("value").and_then(|v| v.as_str()))
+                        .unwrap_or("")
                 };
-                let desired_id = attributes
-                    .iter()
-                    .find(|attr| {
-                        attr.get("key").and_then(|v| v.as_str()).unwrap_or("")
-                            == desired_id_attribute_name
-                    })
-                    .and_then(|attr| attr.get("value").and_then(|v| v.as_str()))
-                    .unwrap_or("");
-                let contract_address = attributes
-                    .iter()
-                    .find(|attr| {
-                        attr.get("key").and_then(|v| v.as_str()).unwrap_or("")
-                            == "_contract_address"
-                    })
-                    .and_then(|attr| attr.get("value").and_then(|v| v.as_str()))
-                    .unwrap_or("");
-                let chain = attributes
-                    .iter()
-                    .find(|attr| {
-                        let key = attr.get("key").and_then(|v| v.as_str()).unwrap_or("");
-                        match event_type {
-                            "wasm-messages_poll_started" => key == "source_chain",
-                            "wasm-signing_started" => key == "chain",
-                            _ => false,
-                        }
-                    })
-                    .and_then(|attr| attr.get("value").and_then(|v| v.as_str()))
-                    .unwrap_or("");
-                if !desired_id.is_empty() && !contract_address.is_empty() && !chain.is_empty() {
+                let desired_id = attribute(&rule.correlation_attribute);
+                let contract_address = attribute("_contract_address");
+                let chain = rule.chain_attribute.as_deref().map(attribute);
+                if !desired_id.is_empty()
+                    && !contract_address.is_empty()
+                    && chain.is_none_or(|chain| !chain.is_empty())
+                {
                     return Ok(Some((
                         desired_id.to_string(),
                         contract_address.to_string(),
-                        chain.to_string(),
+                        chain.unwrap_or_default().to_string(),
                     )));
                 }
             }
@@ -144,3 +132,141 @@ pub fn extract_info_from_script(
     }
     Ok(None)
 }
+
+/// Value of the attribute `key` of the first `event_type` event in the output of
+/// `axelard tx wasm execute`, with the JSON quotes of string values removed
+pub fn extract_event_attribute(
+    script_result: &Value,
+    event_type: &str,
+    key: &str,
+) -> Option<String> {
+    script_result
+        .get("logs")?
+        .as_array()?
+        .iter()
+        .filter_map(|log| log.get("events")?.as_array())
+        .flatten()
+        .find(|event| event.get("type").and_then(|v| v.as_str()) == Some(event_type))?
+        .get("attributes")?
+        .as_array()?
+        .iter()
+        .find(|attr| attr.get("key").and_then(|v| v.as_str()) == Some(key))?
+        .get("value")?
+        .as_str()
+        .map(|value| value.trim_matches('"').to_string())
+}
+
+/// Collect every `message_id` field of a contract message, e.g. the messages of a
+/// `verify_messages` or the cc IDs of a `construct_proof`
+pub fn collect_message_ids(value: &Value) -> Vec<String> {
+    let mut ids = Vec::new();
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                match value {
+                    Value::String(id) if key == "message_id" => ids.push(id.clone()),
+                    _ => ids.extend(collect_message_ids(value)),
+                }
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                ids.extend(collect_message_ids(value));
+            }
+        }
+        _ => {}
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collect_message_ids, extract_event_attribute, extract_info_from_script};
+    use crate::rules::{EventRule, RuleTask};
+
+    #[test]
+    fn test_collect_message_ids() {
+        let construct_proof = serde_json::json!({
+            "construct_proof": [
+                {"source_chain": "xrpl", "message_id": "0xabc-1"},
+                {"source_chain": "xrpl", "message_id": "0xdef-2"}
+            ]
+        });
+
+        assert_eq!(
+            collect_message_ids(&construct_proof),
+            vec!["0xabc-1", "0xdef-2"]
+        );
+    }
+
+    #[test]
+    fn test_extract_event_attribute() {
+        let script_result = serde_json::json!({
+            "code": 0,
+            "logs": [{"events": [
+                {"type": "message", "attributes": [{"key": "expires_at", "value": "1"}]},
+                {"type": "wasm-messages_poll_started", "attributes": [
+                    {"key": "poll_id", "value": "\"42\""},
+                    {"key": "expires_at", "value": "1234"}
+                ]}
+            ]}]
+        });
+
+        assert_eq!(
+            extract_event_attribute(&script_result, "wasm-messages_poll_started", "expires_at"),
+            Some("1234".to_string())
+        );
+        assert_eq!(
+            extract_event_attribute(&script_result, "wasm-messages_poll_started", "poll_id"),
+            Some("42".to_string())
+        );
+        assert_eq!(
+            extract_event_attribute(&script_result, "wasm-signing_started", "expires_at"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_extract_info_of_a_rule() {
+        let script_result = serde_json::json!({
+            "code": 0,
+            "logs": [{"events": [
+                {"type": "wasm-messages_poll_started", "attributes": [
+                    {"key": "_contract_address", "value": "axelar1verifier"},
+                    {"key": "poll_id", "value": "\"42\""},
+                    {"key": "source_chain", "value": "xrpl"}
+                ]},
+                {"type": "wasm-signers_rotation_started", "attributes": [
+                    {"key": "_contract_address", "value": "axelar1prover"},
+                    {"key": "session_id", "value": "7"}
+                ]}
+            ]}]
+        });
+
+        assert_eq!(
+            extract_info_from_script(&script_result, &EventRule::verify_messages()).unwrap(),
+            Some((
+                "\"42\"".to_string(),
+                "axelar1verifier".to_string(),
+                "xrpl".to_string()
+            ))
+        );
+        assert_eq!(
+            extract_info_from_script(&script_result, &EventRule::construct_proof()).unwrap(),
+            None
+        );
+
+        let signer_rotation = EventRule {
+            name: "signer_rotation".to_string(),
+            trigger_event: "wasm-signers_rotation_started".to_string(),
+            correlation_attribute: "session_id".to_string(),
+            chain_attribute: None,
+            follow_up_event: "wasm-signing_completed".to_string(),
+            task: RuleTask::GatewayTx,
+        };
+        assert_eq!(
+            extract_info_from_script(&script_result, &signer_rotation).unwrap(),
+            Some(("7".to_string(), "axelar1prover".to_string(), String::new()))
+        );
+    }
+}
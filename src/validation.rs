@@ -0,0 +1,256 @@
+use base64::{Engine as _, engine::general_purpose};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::gmp_types::{Amount, GatewayV2Message, Task};
+use crate::utils::parse_task;
+
+/// Source chain of messages coming from the ITS hub, which is never registered as a chain
+const AXELAR_CHAIN: &str = "axelar";
+
+/// Field names accepted in place of the one a field is serialized under
+const FIELD_ALIASES: &[(&str, &str)] = &[("drops", "amount")];
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FieldError {
+    /// Path of the field, e.g. `task.message.sourceChain`; absent if the task could not be parsed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: Some(field.to_string()),
+            message: message.into(),
+        }
+    }
+}
+
+/// Body of the 400 response to an invalid task
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TaskValidationError {
+    pub error: String,
+    pub details: Vec<FieldError>,
+}
+
+impl TaskValidationError {
+    fn new(details: Vec<FieldError>) -> Self {
+        Self {
+            error: "Invalid task".to_string(),
+            details,
+        }
+    }
+}
+
+/// Parse a task posted for `chain`, rejecting unknown types and fields, a chain other than
+/// `chain`, malformed base64 and amounts, and messages from chains not in `known_chains`
+pub fn validate_task(
+    task_json: &Value,
+    chain: &str,
+    known_chains: &[String],
+) -> Result<Task, TaskValidationError> {
+    let task = parse_task(task_json).map_err(|e| {
+        TaskValidationError::new(vec![FieldError {
+            field: None,
+            message: e.to_string(),
+        }])
+    })?;
+
+    if let Task::Unknown(unknown) = &task {
+        return Err(TaskValidationError::new(vec![FieldError::new(
+            "type",
+            format!("Unknown task type {}", unknown.common.r#type),
+        )]));
+    }
+
+    let mut errors = Vec::new();
+
+    let parsed = task.to_json().unwrap_or_default();
+    unknown_fields(task_json, &parsed, "", &mut errors);
+
+    let common = task.common();
+    if common.chain != chain {
+        errors.push(FieldError::new(
+            "chain",
+            format!("Does not match the chain {} of the path", chain),
+        ));
+    }
+    if common.timestamp.parse::<DateTime<Utc>>().is_err() {
+        errors.push(FieldError::new("timestamp", "Not an RFC 3339 timestamp"));
+    }
+
+    let known_source_chain = |message: &GatewayV2Message, errors: &mut Vec<FieldError>| {
+        if message.source_chain != AXELAR_CHAIN && !known_chains.contains(&message.source_chain) {
+            errors.push(FieldError::new(
+                "task.message.sourceChain",
+                format!("Unknown chain {}", message.source_chain),
+            ));
+        }
+    };
+
+    match &task {
+        Task::Verify(t) => {
+            known_source_chain(&t.task.message, &mut errors);
+            check_base64("task.payload", &t.task.payload, &mut errors);
+        }
+        Task::ConstructProof(t) => {
+            known_source_chain(&t.task.message, &mut errors);
+            check_base64("task.payload", &t.task.payload, &mut errors);
+        }
+        Task::Execute(t) => {
+            known_source_chain(&t.task.message, &mut errors);
+            check_base64("task.payload", &t.task.payload, &mut errors);
+            check_amount(
+                "task.availableGasBalance.amount",
+                &t.task.available_gas_balance,
+                &mut errors,
+            );
+        }
+        Task::GatewayTx(t) => {
+            check_base64("task.executeData", &t.task.execute_data, &mut errors);
+        }
+        Task::Refund(t) => {
+            known_source_chain(&t.task.message, &mut errors);
+            check_amount(
+                "task.remainingGasBalance.amount",
+                &t.task.remaining_gas_balance,
+                &mut errors,
+            );
+        }
+        Task::ReactToWasmEvent(_)
+        | Task::ReactToRetriablePoll(_)
+        | Task::ReactToExpiredSigningSession(_)
+        | Task::Unknown(_) => {}
+    }
+
+    if errors.is_empty() {
+        Ok(task)
+    } else {
+        Err(TaskValidationError::new(errors))
+    }
+}
+
+/// Report the fields of `input` that were dropped when parsing it into `parsed`
+fn unknown_fields(input: &Value, parsed: &Value, path: &str, errors: &mut Vec<FieldError>) {
+    let field_path = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+
+    match (input, parsed) {
+        (Value::Object(input), Value::Object(parsed)) => {
+            for (key, value) in input {
+                match parsed.get(key) {
+                    Some(parsed_value) => {
+                        unknown_fields(value, parsed_value, &field_path(key), errors)
+                    }
+                    None => {
+                        let is_alias = FIELD_ALIASES.iter().any(|(alias, name)| {
+                            alias == key && parsed.contains_key(*name) && !input.contains_key(*name)
+                        });
+                        if !is_alias {
+                            errors.push(FieldError::new(&field_path(key), "Unknown field"));
+                        }
+                    }
+                }
+            }
+        }
+        (Value::Array(input), Value::Array(parsed)) => {
+            for (index, (value, parsed_value)) in input.iter().zip(parsed).enumerate() {
+                unknown_fields(value, parsed_value, &format!("{}[{}]", path, index), errors);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_base64(field: &str, value: &str, errors: &mut Vec<FieldError>) {
+    if general_purpose::STANDARD.decode(value).is_err() {
+        errors.push(FieldError::new(field, "Not valid base64"));
+    }
+}
+
+fn check_amount(field: &str, amount: &Amount, errors: &mut Vec<FieldError>) {
+    if amount.amount.is_empty() || !amount.amount.chars().all(|c| c.is_ascii_digit()) {
+        errors.push(FieldError::new(
+            field,
+            format!("{} is not a non-negative integer", amount.amount),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{FieldError, validate_task};
+
+    fn execute_task() -> serde_json::Value {
+        json!({
+            "id": "execute_1",
+            "chain": "xrpl",
+            "timestamp": "2025-01-01T00:00:00Z",
+            "type": "EXECUTE",
+            "meta": null,
+            "task": {
+                "message": {
+                    "messageID": "0xabc-1",
+                    "sourceChain": "ethereum",
+                    "sourceAddress": "0x1234",
+                    "destinationAddress": "rAddress",
+                    "payloadHash": "0xdef"
+                },
+                "payload": "aGVsbG8=",
+                "availableGasBalance": {"tokenID": null, "amount": "1000"}
+            }
+        })
+    }
+
+    fn fields(task: &serde_json::Value, chain: &str) -> Vec<Option<String>> {
+        validate_task(task, chain, &["ethereum".to_string()])
+            .unwrap_err()
+            .details
+            .into_iter()
+            .map(|FieldError { field, .. }| field)
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_task() {
+        let task = execute_task();
+        assert!(validate_task(&task, "xrpl", &["ethereum".to_string()]).is_ok());
+        assert_eq!(fields(&task, "avalanche"), vec![Some("chain".to_string())]);
+
+        let mut task = execute_task();
+        task["task"]["availableGasBalance"] = json!({"tokenID": null, "drops": 1000});
+        assert!(validate_task(&task, "xrpl", &["ethereum".to_string()]).is_ok());
+
+        let mut task = execute_task();
+        task["task"]["payload"] = json!("not base64!");
+        task["task"]["availableGasBalance"]["amount"] = json!("1.5");
+        task["task"]["message"]["sourceChain"] = json!("solana");
+        task["task"]["mesage"] = json!({});
+        assert_eq!(
+            fields(&task, "xrpl"),
+            vec![
+                Some("task.mesage".to_string()),
+                Some("task.message.sourceChain".to_string()),
+                Some("task.payload".to_string()),
+                Some("task.availableGasBalance.amount".to_string()),
+            ]
+        );
+
+        let mut task = execute_task();
+        task["type"] = json!("SOMETHING_ELSE");
+        assert_eq!(fields(&task, "xrpl"), vec![Some("type".to_string())]);
+
+        let task = json!({"id": "gateway_tx_1", "type": "GATEWAY_TX"});
+        assert_eq!(fields(&task, "xrpl"), vec![None]);
+    }
+}
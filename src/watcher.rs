@@ -0,0 +1,403 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use futures::{SinkExt, StreamExt};
+use serde_json::Value;
+use tokio::sync::{Notify, watch};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tracing::{debug, info, warn};
+
+use crate::{
+    models::chains::ChainsModel,
+    rules::{CONSTRUCT_PROOF, EventRule, Rules, VERIFY_MESSAGES},
+};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// How often the chain registry is checked for contracts to (un)subscribe
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+/// Events are kept this long for jobs that start after their event arrived
+const RETENTION: Duration = Duration::from_secs(3600);
+
+/// A wasm event seen on the websocket, in the shape `axelard query txs` returns it
+#[derive(Clone, Debug)]
+pub struct WatchedEvent {
+    pub event: Value,
+    pub timestamp: DateTime<Utc>,
+    pub height: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct EventKey {
+    event_type: String,
+    contract_address: String,
+    id: String,
+}
+
+/// Subscribes to the follow-up events of the rules, e.g. `wasm-quorum_reached` and
+/// `wasm-signing_completed`, on the CometBFT node behind the RPC, so that subscriber jobs get them
+/// as they happen instead of paging through `axelard query txs`
+pub struct EventWatcher {
+    rpc: String,
+    websocket_url: String,
+    http_client: reqwest::Client,
+    multisig_contract: String,
+    chains: ChainsModel,
+    rules: Rules,
+    wait_timeout: Duration,
+    /// Every event with the key, e.g. the quorum_reached event of each message of a poll, and
+    /// when the last one was seen
+    events: Mutex<HashMap<EventKey, (Vec<WatchedEvent>, Instant)>>,
+    /// Height and time of the last block whose header was fetched, shared by its transactions
+    last_block: Mutex<Option<(String, DateTime<Utc>)>>,
+    arrived: Notify,
+}
+
+impl EventWatcher {
+    pub fn new(
+        rpc: &str,
+        multisig_contract: &str,
+        chains: ChainsModel,
+        rules: Rules,
+        wait_timeout: Duration,
+    ) -> Self {
+        Self {
+            rpc: rpc.trim_end_matches('/').to_string(),
+            websocket_url: websocket_url(rpc),
+            http_client: reqwest::Client::new(),
+            multisig_contract: multisig_contract.to_string(),
+            chains,
+            rules,
+            wait_timeout,
+            events: Mutex::new(HashMap::new()),
+            last_block: Mutex::new(None),
+            arrived: Notify::new(),
+        }
+    }
+
+    /// Run the watcher in the background until `shutdown` turns true
+    pub fn spawn(self, shutdown: watch::Receiver<bool>) -> Arc<Self> {
+        let watcher = Arc::new(self);
+        let running = watcher.clone();
+        tokio::spawn(async move { running.run(shutdown).await });
+        watcher
+    }
+
+    /// Keep the subscriptions open, reconnecting after errors, until `shutdown` turns true
+    pub async fn run(&self, mut shutdown: watch::Receiver<bool>) {
+        loop {
+            let listen = async {
+                if let Err(e) = self.listen().await {
+                    warn!(
+                        "Websocket subscription to {} failed: {}, reconnecting in {:?}",
+                        self.websocket_url, e, RECONNECT_DELAY
+                    );
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+            };
+            tokio::select! {
+                _ = shutdown.wait_for(|stop| *stop) => return,
+                _ = listen => {}
+            }
+        }
+    }
+
+    /// Wait up to the configured timeout for the follow-up events of `rule` with the given
+    /// correlation ID. None means the caller has to fall back to querying the chain.
+    pub async fn wait(
+        &self,
+        rule: &EventRule,
+        contract_address: &str,
+        id: &str,
+    ) -> Option<Vec<WatchedEvent>> {
+        let key = EventKey {
+            event_type: rule.follow_up_event.clone(),
+            contract_address: contract_address.to_string(),
+            id: id.to_string(),
+        };
+        let deadline = tokio::time::Instant::now() + self.wait_timeout;
+
+        loop {
+            // register before looking, so that an event recorded in between is not missed
+            let arrived = self.arrived.notified();
+            if let Some((events, _)) = self.events.lock().unwrap().get(&key) {
+                return Some(events.clone());
+            }
+            if tokio::time::timeout_at(deadline, arrived).await.is_err() {
+                return None;
+            }
+        }
+    }
+
+    async fn listen(&self) -> Result<(), anyhow::Error> {
+        let queries = self.queries().await?;
+        let (mut websocket, _) = connect_async(&self.websocket_url).await?;
+
+        for (id, query) in queries.iter().enumerate() {
+            let request = serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "subscribe",
+                "id": id,
+                "params": { "query": query },
+            });
+            websocket
+                .send(Message::Text(request.to_string().into()))
+                .await?;
+        }
+        info!(
+            "Subscribed to {} event queries on {}",
+            queries.len(),
+            self.websocket_url
+        );
+
+        let mut refresh = tokio::time::interval(REFRESH_INTERVAL);
+        refresh.tick().await;
+        loop {
+            tokio::select! {
+                message = websocket.next() => match message {
+                    Some(Ok(Message::Text(text))) => match serde_json::from_str::<Value>(&text) {
+                        Ok(message) => self.record(&message).await,
+                        Err(e) => warn!("Invalid websocket message: {}", e),
+                    },
+                    Some(Ok(Message::Close(_))) | None => {
+                        return Err(anyhow::anyhow!("Websocket closed"));
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e.into()),
+                },
+                _ = refresh.tick() => {
+                    if self.queries().await? != queries {
+                        info!("Chain registry changed, resubscribing");
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// One query per registered voting verifier for the quorums of their polls, and one for the
+    /// signing sessions of the multisig contract. The follow-up events of the configured rules
+    /// are watched for all contracts, as the rules do not name one.
+    async fn queries(&self) -> Result<Vec<String>, anyhow::Error> {
+        let mut queries: Vec<String> = self
+            .chains
+            .get_chains()
+            .await?
+            .into_iter()
+            .map(|chain| {
+                format!(
+                    "tm.event='Tx' AND {}._contract_address='{}'",
+                    self.rules.verify_messages().follow_up_event,
+                    chain.contracts.voting_verifier
+                )
+            })
+            .collect();
+        queries.sort();
+        queries.dedup();
+
+        let mut follow_ups: Vec<String> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.name != VERIFY_MESSAGES)
+            .map(|rule| {
+                if rule.name == CONSTRUCT_PROOF {
+                    format!(
+                        "tm.event='Tx' AND {}._contract_address='{}'",
+                        rule.follow_up_event, self.multisig_contract
+                    )
+                } else {
+                    format!(
+                        "tm.event='Tx' AND {}.{} EXISTS",
+                        rule.follow_up_event, rule.correlation_attribute
+                    )
+                }
+            })
+            .collect();
+        follow_ups.sort();
+        follow_ups.dedup();
+        queries.extend(follow_ups);
+        Ok(queries)
+    }
+
+    async fn record(&self, message: &Value) {
+        if let Some(error) = message.get("error") {
+            warn!("Websocket subscription error: {}", error);
+            return;
+        }
+        let Some((height, events)) = tx_events(message) else {
+            return;
+        };
+        if !events
+            .iter()
+            .any(|event| event_key(event, &self.rules).is_some())
+        {
+            return;
+        }
+        let timestamp = match self.block_time(&height).await {
+            Ok(timestamp) => timestamp,
+            Err(e) => {
+                // the event is still worth recording, a job would otherwise page for it
+                warn!(
+                    "Failed to get the time of block {}: {}, using the current time",
+                    height, e
+                );
+                Utc::now()
+            }
+        };
+
+        let mut recorded = self.events.lock().unwrap();
+        recorded.retain(|_, (_, seen_at)| seen_at.elapsed() < RETENTION);
+        for event in events {
+            let Some(key) = event_key(event, &self.rules) else {
+                continue;
+            };
+            debug!("Recorded {:?} from the websocket", key);
+            let (events, seen_at) = recorded
+                .entry(key)
+                .or_insert_with(|| (Vec::new(), Instant::now()));
+            *seen_at = Instant::now();
+            // a transaction matching several subscriptions is delivered once for each
+            if !events
+                .iter()
+                .any(|watched| watched.event == *event && watched.height == height)
+            {
+                events.push(WatchedEvent {
+                    event: event.clone(),
+                    timestamp,
+                    height: height.clone(),
+                });
+            }
+        }
+        drop(recorded);
+        self.arrived.notify_waiters();
+    }
+
+    /// Time of the block at `height`, which is not part of the transaction events
+    async fn block_time(&self, height: &str) -> Result<DateTime<Utc>, anyhow::Error> {
+        if let Some((last_height, time)) = self.last_block.lock().unwrap().as_ref()
+            && last_height == height
+        {
+            return Ok(*time);
+        }
+
+        let response: Value = self
+            .http_client
+            .get(format!("{}/header", self.rpc))
+            .query(&[("height", height)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let time = response
+            .get("result")
+            .and_then(|v| v.get("header"))
+            .and_then(|v| v.get("time"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Time of block {} not found", height))?;
+        let time: DateTime<Utc> = DateTime::parse_from_rfc3339(time)?.into();
+
+        *self.last_block.lock().unwrap() = Some((height.to_string(), time));
+        Ok(time)
+    }
+}
+
+fn websocket_url(rpc: &str) -> String {
+    let rpc = rpc.trim_end_matches('/');
+    let rpc = if let Some(host) = rpc.strip_prefix("https://") {
+        format!("wss://{}", host)
+    } else if let Some(host) = rpc.strip_prefix("http://") {
+        format!("ws://{}", host)
+    } else {
+        rpc.to_string()
+    };
+    format!("{}/websocket", rpc)
+}
+
+/// Height and events of a `tm.event='Tx'` subscription message
+fn tx_events(message: &Value) -> Option<(String, &Vec<Value>)> {
+    let tx_result = message
+        .get("result")?
+        .get("data")?
+        .get("value")?
+        .get("TxResult")?;
+    let height = match tx_result.get("height")? {
+        Value::String(height) => height.clone(),
+        height => height.to_string(),
+    };
+    let events = tx_result.get("result")?.get("events")?.as_array()?;
+    Some((height, events))
+}
+
+fn event_key(event: &Value, rules: &Rules) -> Option<EventKey> {
+    let event_type = event.get("type")?.as_str()?;
+    let id_attribute = &rules.by_follow_up(event_type)?.correlation_attribute;
+
+    let attributes = event.get("attributes")?.as_array()?;
+    let attribute = |name: &str| {
+        attributes
+            .iter()
+            .find(|attr| attr.get("key").and_then(|v| v.as_str()) == Some(name))
+            .and_then(|attr| attr.get("value").and_then(|v| v.as_str()))
+            .map(|value| value.to_string())
+    };
+
+    Some(EventKey {
+        event_type: event_type.to_string(),
+        contract_address: attribute("_contract_address")?,
+        id: attribute(id_attribute)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{event_key, tx_events, websocket_url};
+    use crate::rules::Rules;
+
+    #[test]
+    fn test_events_are_keyed_by_contract_and_id() {
+        let message = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "result": {
+                "query": "tm.event='Tx' AND wasm-quorum_reached._contract_address='axelar1verifier'",
+                "data": {
+                    "type": "tendermint/event/Tx",
+                    "value": {
+                        "TxResult": {
+                            "height": "1234",
+                            "result": {
+                                "events": [
+                                    {"type": "message", "attributes": [{"key": "sender", "value": "axelar1sender"}]},
+                                    {"type": "wasm-quorum_reached", "attributes": [
+                                        {"key": "_contract_address", "value": "axelar1verifier"},
+                                        {"key": "poll_id", "value": "\"42\""},
+                                        {"key": "status", "value": "\"succeeded_on_source_chain\""}
+                                    ]}
+                                ]
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        let (height, events) = tx_events(&message).unwrap();
+        assert_eq!(height, "1234");
+        let rules = Rules::default();
+        assert!(event_key(&events[0], &rules).is_none());
+
+        let key = event_key(&events[1], &rules).unwrap();
+        assert_eq!(key.event_type, "wasm-quorum_reached");
+        assert_eq!(key.contract_address, "axelar1verifier");
+        assert_eq!(key.id, "\"42\"");
+
+        assert_eq!(
+            websocket_url("http://devnet-amplifier.axelar.dev:26657/"),
+            "ws://devnet-amplifier.axelar.dev:26657/websocket"
+        );
+    }
+}